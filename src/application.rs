@@ -2,13 +2,15 @@
 
 use crate::{
     backend::PwvucontrolManager,
+    cli,
     config::{APP_ID, VERSION},
+    dbus::DBusInterface,
     ui::PwvucontrolWindow,
 };
 use adw::subclass::prelude::*;
 use glib::{ExitCode, OptionArg, OptionFlags};
 use gtk::{gio, prelude::*};
-use std::cell::{Cell, OnceCell};
+use std::cell::{Cell, OnceCell, RefCell};
 
 mod imp {
     use super::*;
@@ -21,6 +23,8 @@ mod imp {
         pub manager: PwvucontrolManager,
 
         pub(super) tab: Cell<i32>,
+
+        pub(super) dbus_interface: RefCell<Option<DBusInterface>>,
     }
 
     #[glib::object_subclass]
@@ -30,7 +34,12 @@ mod imp {
         type ParentType = adw::Application;
 
         fn new() -> PwvucontrolApplication {
-            PwvucontrolApplication { window: OnceCell::default(), manager: PwvucontrolManager::new(), tab: Default::default() }
+            PwvucontrolApplication {
+                window: OnceCell::default(),
+                manager: PwvucontrolManager::new(),
+                tab: Default::default(),
+                dbus_interface: Default::default(),
+            }
         }
     }
 
@@ -42,12 +51,15 @@ mod imp {
             let obj = self.obj();
             obj.setup_gactions();
             obj.set_accels_for_action("app.quit", &["<primary>q"]);
+            obj.set_accels_for_action("win.search", &["<primary>f"]);
 
             obj.set_accels_for_action("win.switchtab(1)", &["<alt>1"]);
             obj.set_accels_for_action("win.switchtab(2)", &["<alt>2"]);
             obj.set_accels_for_action("win.switchtab(3)", &["<alt>3"]);
             obj.set_accels_for_action("win.switchtab(4)", &["<alt>4"]);
             obj.set_accels_for_action("win.switchtab(5)", &["<alt>5"]);
+            obj.set_accels_for_action("win.switchtab(6)", &["<alt>6"]);
+            obj.set_accels_for_action("win.switchtab(7)", &["<alt>7"]);
         }
     }
 
@@ -70,6 +82,16 @@ mod imp {
 
             let window = PwvucontrolWindow::new(&self.obj());
             self.window.set(window).expect("Failed to initialize application window");
+
+            self.manager.restore_virtual_devices();
+            self.manager.restore_equalizers();
+        }
+
+        fn shutdown(&self) {
+            self.manager.stop_listening();
+            self.manager.stop_recordings();
+            self.manager.save_equalizers();
+            self.parent_shutdown();
         }
 
         fn command_line(&self, command_line: &gio::ApplicationCommandLine) -> ExitCode {
@@ -88,9 +110,36 @@ mod imp {
                 println!("pwvucontrol {}", VERSION);
                 return std::ops::ControlFlow::Break(ExitCode::SUCCESS);
             }
+            if let Ok(Some(args)) = options.lookup::<Vec<String>>(glib::OPTION_REMAINING) {
+                // Commands run in this process without registering, so no window is ever created.
+                let exit_code = match cli::Command::parse(&args) {
+                    Ok(command) => cli::run_headless(&self.manager, command),
+                    Err(e) => {
+                        eprintln!("{e}");
+                        ExitCode::FAILURE
+                    }
+                };
+                return std::ops::ControlFlow::Break(exit_code);
+            }
             self.parent_handle_local_options(options)
         }
 
+        fn dbus_register(&self, connection: &gio::DBusConnection, object_path: &str) -> Result<(), glib::Error> {
+            self.parent_dbus_register(connection, object_path)?;
+
+            let interface = DBusInterface::register(connection, object_path, &self.manager)?;
+            self.dbus_interface.replace(Some(interface));
+
+            Ok(())
+        }
+
+        fn dbus_unregister(&self, connection: &gio::DBusConnection, object_path: &str) {
+            if let Some(interface) = self.dbus_interface.take() {
+                interface.unregister(connection);
+            }
+
+            self.parent_dbus_unregister(connection, object_path);
+        }
     }
 
     impl GtkApplicationImpl for PwvucontrolApplication {}
@@ -115,6 +164,8 @@ impl PwvucontrolApplication {
 
         app.add_main_option("tab", b't'.into(), OptionFlags::NONE, OptionArg::Int, "Select tab to open.", Some("number"));
         app.add_main_option("version", b'v'.into(), OptionFlags::NONE, OptionArg::None, "Show version.", None);
+        app.add_main_option(glib::OPTION_REMAINING, b'\0'.into(), OptionFlags::NONE, OptionArg::StringArray, "Command to run without opening a window.", Some("[COMMAND…]"));
+        app.set_option_context_description(Some(cli::USAGE));
 
         ApplicationExtManual::run(&app)
     }
@@ -0,0 +1,677 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use pipewire::spa::sys::{
+    SPA_AUDIO_CHANNEL_FC, SPA_AUDIO_CHANNEL_FL, SPA_AUDIO_CHANNEL_FLC, SPA_AUDIO_CHANNEL_FR, SPA_AUDIO_CHANNEL_FRC, SPA_AUDIO_CHANNEL_LFE, SPA_AUDIO_CHANNEL_MONO,
+    SPA_AUDIO_CHANNEL_RC, SPA_AUDIO_CHANNEL_RL, SPA_AUDIO_CHANNEL_RR, SPA_AUDIO_CHANNEL_SL, SPA_AUDIO_CHANNEL_SR, SPA_AUDIO_CHANNEL_TC, SPA_AUDIO_CHANNEL_TFC,
+    SPA_AUDIO_CHANNEL_TFL, SPA_AUDIO_CHANNEL_TFR, SPA_AUDIO_CHANNEL_TRC, SPA_AUDIO_CHANNEL_TRL, SPA_AUDIO_CHANNEL_TRR,
+};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+/// FLAC frames hold this many samples per channel.
+const FLAC_BLOCK_SIZE: usize = 4096;
+const FLAC_BITS_PER_SAMPLE: u32 = 24;
+/// 31 is the escape code of RICE2 partitions.
+const FLAC_MAX_RICE_PARAMETER: u32 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFileFormat {
+    /// 32-bit float WAV, exactly what was captured.
+    Wav,
+    /// 24-bit FLAC, lossless but about half the size.
+    Flac,
+}
+
+impl AudioFileFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "wav" => Some(Self::Wav),
+            "flac" => Some(Self::Flac),
+            _ => None,
+        }
+    }
+
+    /// FLAC is limited to eight channels.
+    pub fn max_channels(&self) -> usize {
+        match self {
+            Self::Wav => 64,
+            Self::Flac => 8,
+        }
+    }
+}
+
+/// Writes interleaved float samples to a WAV or FLAC file.
+///
+/// The header is written with placeholder sizes and completed by [`AudioFileWriter::finish`].
+pub struct AudioFileWriter<W: Write + Seek = BufWriter<File>> {
+    out: W,
+    format: AudioFileFormat,
+    rate: u32,
+    channels: usize,
+    /// WAV only: the speakers of the channels, 0 when they cannot be told.
+    channel_mask: u32,
+    frames: u64,
+    bytes: u64,
+    /// FLAC only: samples of the frame being collected, and the frame bookkeeping for STREAMINFO.
+    pending: Vec<f32>,
+    flac_frames: u64,
+    min_frame_size: u32,
+    max_frame_size: u32,
+}
+
+impl AudioFileWriter {
+    pub fn create(path: &Path, format: AudioFileFormat, rate: u32, positions: &[u32]) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?), format, rate, positions)
+    }
+}
+
+impl<W: Write + Seek> AudioFileWriter<W> {
+    /// `positions` are the SPA channel positions of the samples, one per channel.
+    pub fn new(out: W, format: AudioFileFormat, rate: u32, positions: &[u32]) -> io::Result<Self> {
+        let channels = positions.len();
+        if channels == 0 || channels > format.max_channels() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{channels} channels are not supported")));
+        }
+
+        let mut writer = Self {
+            out,
+            format,
+            rate,
+            channels,
+            channel_mask: wav_channel_mask(positions),
+            frames: 0,
+            bytes: 0,
+            pending: Vec::new(),
+            flac_frames: 0,
+            min_frame_size: u32::MAX,
+            max_frame_size: 0,
+        };
+        writer.write_header()?;
+        Ok(writer)
+    }
+
+    /// Samples per channel written so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Size of the file so far.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    pub fn write(&mut self, samples: &[f32]) -> io::Result<()> {
+        let samples = &samples[..samples.len() - samples.len() % self.channels];
+        self.frames += (samples.len() / self.channels) as u64;
+
+        match self.format {
+            AudioFileFormat::Wav => {
+                let data: Vec<u8> = samples.iter().flat_map(|x| x.to_le_bytes()).collect();
+                self.put(&data)
+            }
+            AudioFileFormat::Flac => {
+                self.pending.extend_from_slice(samples);
+                let block = FLAC_BLOCK_SIZE * self.channels;
+                while self.pending.len() >= block {
+                    let rest = self.pending.split_off(block);
+                    let frame = std::mem::replace(&mut self.pending, rest);
+                    self.write_flac_frame(&frame)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes what is left and completes the header.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.format == AudioFileFormat::Flac && !self.pending.is_empty() {
+            let frame = std::mem::take(&mut self.pending);
+            self.write_flac_frame(&frame)?;
+        }
+        self.out.seek(SeekFrom::Start(0))?;
+        self.write_header()?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn put(&mut self, data: &[u8]) -> io::Result<()> {
+        self.out.write_all(data)?;
+        self.bytes += data.len() as u64;
+        Ok(())
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        let header = match self.format {
+            AudioFileFormat::Wav => self.wav_header(),
+            AudioFileFormat::Flac => self.flac_header(),
+        };
+        // Rewriting the header when finishing must not count twice.
+        if self.bytes == 0 {
+            self.put(&header)
+        } else {
+            self.out.write_all(&header)
+        }
+    }
+
+    /// More than two channels need WAVE_FORMAT_EXTENSIBLE, players guess the speakers otherwise.
+    fn wav_header(&self) -> Vec<u8> {
+        let extensible = self.channels > 2;
+        let header_size: u32 = if extensible { 80 } else { 58 };
+        let block_align = (self.channels * 4) as u16;
+        let data_size = (self.frames * block_align as u64).min((u32::MAX - header_size + 8) as u64) as u32;
+
+        let mut header = Vec::with_capacity(header_size as usize);
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&(header_size - 8 + data_size).to_le_bytes());
+        header.extend_from_slice(b"WAVE");
+        header.extend_from_slice(b"fmt ");
+        if extensible {
+            header.extend_from_slice(&40u32.to_le_bytes());
+            header.extend_from_slice(&0xfffeu16.to_le_bytes()); // WAVE_FORMAT_EXTENSIBLE
+        } else {
+            header.extend_from_slice(&18u32.to_le_bytes());
+            header.extend_from_slice(&3u16.to_le_bytes()); // WAVE_FORMAT_IEEE_FLOAT
+        }
+        header.extend_from_slice(&(self.channels as u16).to_le_bytes());
+        header.extend_from_slice(&self.rate.to_le_bytes());
+        header.extend_from_slice(&(self.rate * block_align as u32).to_le_bytes());
+        header.extend_from_slice(&block_align.to_le_bytes());
+        header.extend_from_slice(&32u16.to_le_bytes());
+        if extensible {
+            header.extend_from_slice(&22u16.to_le_bytes());
+            header.extend_from_slice(&32u16.to_le_bytes()); // valid bits per sample
+            header.extend_from_slice(&self.channel_mask.to_le_bytes());
+            // KSDATAFORMAT_SUBTYPE_IEEE_FLOAT
+            header.extend_from_slice(&[3, 0, 0, 0, 0, 0, 0x10, 0, 0x80, 0, 0, 0xaa, 0, 0x38, 0x9b, 0x71]);
+        } else {
+            header.extend_from_slice(&0u16.to_le_bytes());
+        }
+        header.extend_from_slice(b"fact");
+        header.extend_from_slice(&4u32.to_le_bytes());
+        header.extend_from_slice(&(self.frames.min(u32::MAX as u64) as u32).to_le_bytes());
+        header.extend_from_slice(b"data");
+        header.extend_from_slice(&data_size.to_le_bytes());
+        header
+    }
+
+    fn flac_header(&self) -> Vec<u8> {
+        let mut bits = BitWriter::default();
+        bits.put(FLAC_BLOCK_SIZE as u64, 16); // minimum block size
+        bits.put(FLAC_BLOCK_SIZE as u64, 16); // maximum block size
+        bits.put(if self.flac_frames > 0 { self.min_frame_size as u64 } else { 0 }, 24);
+        bits.put(self.max_frame_size as u64, 24);
+        bits.put(self.rate as u64, 20);
+        bits.put(self.channels as u64 - 1, 3);
+        bits.put(FLAC_BITS_PER_SAMPLE as u64 - 1, 5);
+        bits.put(self.frames, 36);
+        bits.put(0, 64); // MD5 of the audio, 0 when unknown
+        bits.put(0, 64);
+
+        let mut header = Vec::with_capacity(42);
+        header.extend_from_slice(b"fLaC");
+        // Last metadata block, type STREAMINFO, 34 bytes long
+        header.extend_from_slice(&[0x80, 0, 0, 34]);
+        header.extend_from_slice(&bits.bytes);
+        header
+    }
+
+    fn write_flac_frame(&mut self, samples: &[f32]) -> io::Result<()> {
+        let block_size = samples.len() / self.channels;
+        let mut bits = BitWriter::default();
+
+        bits.put(0b11111111111110, 14); // sync code
+        bits.put(0, 1);
+        bits.put(0, 1); // fixed block size
+        bits.put(if block_size == FLAC_BLOCK_SIZE { 0b1100 } else { 0b0111 }, 4);
+        let rate_code = match self.rate {
+            44100 => 0b1001,
+            48000 => 0b1010,
+            96000 => 0b1011,
+            _ => 0b0000, // from STREAMINFO
+        };
+        bits.put(rate_code, 4);
+        bits.put(self.channels as u64 - 1, 4); // independent channels
+        bits.put(0b110, 3); // 24 bits per sample
+        bits.put(0, 1);
+        bits.put_utf8(self.flac_frames);
+        if block_size != FLAC_BLOCK_SIZE {
+            bits.put(block_size as u64 - 1, 16);
+        }
+        let crc = crc8(&bits.bytes);
+        bits.put(crc as u64, 8);
+
+        let max = (1i64 << (FLAC_BITS_PER_SAMPLE - 1)) - 1;
+        for channel in 0..self.channels {
+            let channel: Vec<i64> =
+                samples.iter().skip(channel).step_by(self.channels).map(|x| ((*x as f64 * max as f64).round() as i64).clamp(-max - 1, max)).collect();
+            write_subframe(&mut bits, &channel);
+        }
+
+        bits.align();
+        let crc = crc16(&bits.bytes);
+        bits.put(crc as u64, 16);
+
+        let size = bits.bytes.len() as u32;
+        self.min_frame_size = self.min_frame_size.min(size);
+        self.max_frame_size = self.max_frame_size.max(size);
+        self.flac_frames += 1;
+        self.put(&bits.bytes)
+    }
+}
+
+/// Residual of the fixed linear predictor of `order`, see the FLAC format.
+fn fixed_residual(samples: &[i64], order: usize) -> impl Iterator<Item = i64> + '_ {
+    (order..samples.len()).map(move |i| {
+        let s = |k: usize| samples[i - k];
+        match order {
+            0 => s(0),
+            1 => s(0) - s(1),
+            2 => s(0) - 2 * s(1) + s(2),
+            3 => s(0) - 3 * s(1) + 3 * s(2) - s(3),
+            _ => s(0) - 4 * s(1) + 6 * s(2) - 4 * s(3) + s(4),
+        }
+    })
+}
+
+/// Bits to code `residual` with Rice `parameter`.
+fn rice_bits(residual: &[u64], parameter: u32) -> u64 {
+    residual.iter().map(|x| (x >> parameter) + 1 + parameter as u64).sum()
+}
+
+/// Codes `samples` with the best fixed predictor, or verbatim when prediction doesn't pay off as with noise.
+fn write_subframe(bits: &mut BitWriter, samples: &[i64]) {
+    // The predictor leaving the smallest residual usually codes the shortest.
+    let order = (0..=4usize.min(samples.len().saturating_sub(1)))
+        .min_by_key(|order| fixed_residual(samples, *order).map(|x| x.unsigned_abs()).sum::<u64>())
+        .unwrap_or(0);
+    let residual: Vec<u64> = fixed_residual(samples, order).map(|x| ((x << 1) ^ (x >> 63)) as u64).collect();
+
+    // One partition, with the Rice parameter that codes it the shortest. The optimum is
+    // close to log2 of the mean, so only its neighbours are tried.
+    let mean = residual.iter().sum::<u64>() / residual.len().max(1) as u64;
+    let estimate = (u64::BITS - mean.leading_zeros()).min(FLAC_MAX_RICE_PARAMETER);
+    let parameter = (estimate.saturating_sub(1)..=(estimate + 1).min(FLAC_MAX_RICE_PARAMETER))
+        .min_by_key(|parameter| rice_bits(&residual, *parameter))
+        .unwrap_or(0);
+
+    let fixed_size = 8 + order as u64 * FLAC_BITS_PER_SAMPLE as u64 + 2 + 4 + 5 + rice_bits(&residual, parameter);
+    let verbatim_size = 8 + samples.len() as u64 * FLAC_BITS_PER_SAMPLE as u64;
+    if fixed_size > verbatim_size {
+        bits.put(0b00000010, 8); // SUBFRAME_VERBATIM, no wasted bits
+        for sample in samples {
+            bits.put_signed(*sample, FLAC_BITS_PER_SAMPLE);
+        }
+        return;
+    }
+
+    bits.put(0, 1);
+    bits.put(0b001000 | order as u64, 6); // SUBFRAME_FIXED
+    bits.put(0, 1); // no wasted bits
+    for sample in &samples[..order] {
+        bits.put_signed(*sample, FLAC_BITS_PER_SAMPLE);
+    }
+
+    bits.put(0b01, 2); // RICE2, 5-bit parameters
+    bits.put(0, 4); // one partition
+    bits.put(parameter as u64, 5);
+    for value in residual {
+        bits.put_unary(value >> parameter);
+        bits.put(value & ((1 << parameter) - 1), parameter);
+    }
+}
+
+/// The WAVE_FORMAT_EXTENSIBLE speaker mask of SPA channel positions.
+///
+/// The mask lists the speakers in file order, so positions WAV has no speaker for, repeated ones or
+/// another order than WAV's give 0, which leaves the speakers unassigned.
+fn wav_channel_mask(positions: &[u32]) -> u32 {
+    let mut mask = 0u32;
+    for position in positions {
+        let speaker = match *position {
+            SPA_AUDIO_CHANNEL_FL => 0x1,
+            SPA_AUDIO_CHANNEL_FR => 0x2,
+            SPA_AUDIO_CHANNEL_FC | SPA_AUDIO_CHANNEL_MONO => 0x4,
+            SPA_AUDIO_CHANNEL_LFE => 0x8,
+            SPA_AUDIO_CHANNEL_RL => 0x10,
+            SPA_AUDIO_CHANNEL_RR => 0x20,
+            SPA_AUDIO_CHANNEL_FLC => 0x40,
+            SPA_AUDIO_CHANNEL_FRC => 0x80,
+            SPA_AUDIO_CHANNEL_RC => 0x100,
+            SPA_AUDIO_CHANNEL_SL => 0x200,
+            SPA_AUDIO_CHANNEL_SR => 0x400,
+            SPA_AUDIO_CHANNEL_TC => 0x800,
+            SPA_AUDIO_CHANNEL_TFL => 0x1000,
+            SPA_AUDIO_CHANNEL_TFC => 0x2000,
+            SPA_AUDIO_CHANNEL_TFR => 0x4000,
+            SPA_AUDIO_CHANNEL_TRL => 0x8000,
+            SPA_AUDIO_CHANNEL_TRC => 0x10000,
+            SPA_AUDIO_CHANNEL_TRR => 0x20000,
+            _ => return 0,
+        };
+        if speaker <= mask {
+            return 0;
+        }
+        mask |= speaker;
+    }
+    mask
+}
+
+#[derive(Debug, Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    /// Bits not making up a whole byte yet, in the low `pending` bits.
+    acc: u64,
+    pending: u32,
+}
+
+impl BitWriter {
+    fn put(&mut self, value: u64, bits: u32) {
+        if bits > 32 {
+            self.put(value >> 32, bits - 32);
+            self.put(value & 0xffff_ffff, 32);
+            return;
+        }
+        self.acc = (self.acc << bits) | (value & ((1u64 << bits) - 1));
+        self.pending += bits;
+        while self.pending >= 8 {
+            self.pending -= 8;
+            self.bytes.push((self.acc >> self.pending) as u8);
+        }
+        self.acc &= (1 << self.pending) - 1;
+    }
+
+    fn put_signed(&mut self, value: i64, bits: u32) {
+        self.put(value as u64 & ((1 << bits) - 1), bits);
+    }
+
+    fn put_unary(&mut self, mut zeros: u64) {
+        while zeros >= 32 {
+            self.put(0, 32);
+            zeros -= 32;
+        }
+        self.put(1, zeros as u32 + 1);
+    }
+
+    /// The UTF-8 like coding of frame numbers.
+    fn put_utf8(&mut self, value: u64) {
+        if value < 0x80 {
+            self.put(value, 8);
+            return;
+        }
+        let mut continuation = 1;
+        while value >> (6 * continuation) >= 1 << (6 - continuation) {
+            continuation += 1;
+        }
+        let lead = (0xff00u64 >> (continuation + 1)) & 0xff;
+        self.put(lead | (value >> (6 * continuation)), 8);
+        for i in (0..continuation).rev() {
+            self.put(0x80 | ((value >> (6 * i)) & 0x3f), 8);
+        }
+    }
+
+    /// Pads with zeros to the next byte.
+    fn align(&mut self) {
+        if self.pending > 0 {
+            self.put(0, 8 - self.pending);
+        }
+    }
+}
+
+fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |mut crc, byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+        crc
+    })
+}
+
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |mut crc, byte| {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+        }
+        crc
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pipewire::spa::sys::SPA_AUDIO_CHANNEL_AUX0;
+    use std::io::Cursor;
+
+    struct BitReader<'a> {
+        bytes: &'a [u8],
+        pos: usize,
+    }
+
+    impl BitReader<'_> {
+        fn get(&mut self, bits: u32) -> u64 {
+            (0..bits).fold(0, |value, _| {
+                let bit = (self.bytes[self.pos / 8] >> (7 - self.pos % 8)) & 1;
+                self.pos += 1;
+                (value << 1) | bit as u64
+            })
+        }
+
+        fn get_signed(&mut self, bits: u32) -> i64 {
+            let value = self.get(bits) as i64;
+            (value << (64 - bits)) >> (64 - bits)
+        }
+    }
+
+    /// Decodes what the writer produces: verbatim subframes, or fixed ones with one Rice partition.
+    fn decode_flac(data: &[u8]) -> (u32, usize, Vec<Vec<i64>>) {
+        assert_eq!(&data[..4], b"fLaC");
+        let mut bits = BitReader { bytes: &data[8..42], pos: 0 };
+        bits.get(16 + 16 + 24 + 24);
+        let rate = bits.get(20) as u32;
+        let channels = bits.get(3) as usize + 1;
+        assert_eq!(bits.get(5) + 1, FLAC_BITS_PER_SAMPLE as u64);
+        let total = bits.get(36) as usize;
+
+        let mut decoded = vec![Vec::new(); channels];
+        let mut pos = 42 * 8;
+        while decoded[0].len() < total {
+            let mut bits = BitReader { bytes: data, pos };
+            assert_eq!(bits.get(14), 0b11111111111110);
+            bits.get(2);
+            let size_code = bits.get(4);
+            bits.get(4 + 4 + 3 + 1);
+            let frame_start = pos / 8;
+            let lead = bits.get(8);
+            for _ in 1..(lead as u8).leading_ones().max(1) {
+                bits.get(8);
+            }
+            let block_size = if size_code == 0b0111 { bits.get(16) as usize + 1 } else { FLAC_BLOCK_SIZE };
+            assert_eq!(crc8(&data[frame_start..bits.pos / 8]) as u64, bits.get(8));
+
+            for channel in decoded.iter_mut() {
+                bits.get(1);
+                let subframe_type = bits.get(6);
+                bits.get(1);
+                if subframe_type == 0b000001 {
+                    channel.extend((0..block_size).map(|_| bits.get_signed(FLAC_BITS_PER_SAMPLE)));
+                    continue;
+                }
+                let order = (subframe_type & 0b111) as usize;
+                let mut samples: Vec<i64> = (0..order).map(|_| bits.get_signed(FLAC_BITS_PER_SAMPLE)).collect();
+                let parameter_bits = if bits.get(2) == 0b01 { 5 } else { 4 };
+                assert_eq!(bits.get(4), 0);
+                let parameter = bits.get(parameter_bits) as u32;
+                for _ in order..block_size {
+                    let mut q = 0;
+                    while bits.get(1) == 0 {
+                        q += 1;
+                    }
+                    let u = (q << parameter) | bits.get(parameter);
+                    let r = (u >> 1) as i64 ^ -((u & 1) as i64);
+                    let i = samples.len();
+                    let s = |k: usize| samples[i - k];
+                    let prediction = match order {
+                        0 => 0,
+                        1 => s(1),
+                        2 => 2 * s(1) - s(2),
+                        3 => 3 * s(1) - 3 * s(2) + s(3),
+                        _ => 4 * s(1) - 6 * s(2) + 4 * s(3) - s(4),
+                    };
+                    samples.push(prediction + r);
+                }
+                channel.extend(samples);
+            }
+
+            let end = bits.pos.div_ceil(8);
+            assert_eq!(crc16(&data[frame_start..end]), u16::from_be_bytes([data[end], data[end + 1]]));
+            pos = (end + 2) * 8;
+        }
+        (rate, channels, decoded)
+    }
+
+    fn test_signal(frames: usize) -> Vec<f32> {
+        (0..frames).flat_map(|i| [(i as f32 * 0.01).sin() * 0.8, (i as f32 * 0.003).cos() * -0.25]).collect()
+    }
+
+    #[test]
+    fn writes_flac() {
+        let samples = test_signal(FLAC_BLOCK_SIZE * 2 + 123);
+        let mut writer = AudioFileWriter::new(Cursor::new(Vec::new()), AudioFileFormat::Flac, 48000, &[SPA_AUDIO_CHANNEL_FL, SPA_AUDIO_CHANNEL_FR]).unwrap();
+        writer.write(&samples[..1000]).unwrap();
+        writer.write(&samples[1000..]).unwrap();
+        assert_eq!(writer.frames(), FLAC_BLOCK_SIZE as u64 * 2 + 123);
+        let data = writer.finish().unwrap().into_inner();
+
+        let (rate, channels, decoded) = decode_flac(&data);
+        assert_eq!((rate, channels), (48000, 2));
+        let max = ((1 << 23) - 1) as f32;
+        for (channel, decoded) in decoded.iter().enumerate() {
+            assert_eq!(decoded.len(), FLAC_BLOCK_SIZE * 2 + 123);
+            for (sample, expected) in decoded.iter().zip(samples.iter().skip(channel).step_by(2)) {
+                assert!((*sample as f32 / max - expected).abs() < 1e-6);
+            }
+        }
+        // Smooth signals compress well.
+        assert!(data.len() < samples.len() * 3 / 2);
+    }
+
+    #[test]
+    fn noise_is_no_larger_than_wav() {
+        // xorshift, full scale white noise leaves nothing to predict.
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let samples: Vec<f32> = (0..FLAC_BLOCK_SIZE * 2 * 3)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 40) as f32 / (1 << 23) as f32 - 1.0
+            })
+            .collect();
+
+        let write = |format| {
+            let mut writer = AudioFileWriter::new(Cursor::new(Vec::new()), format, 48000, &[SPA_AUDIO_CHANNEL_FL, SPA_AUDIO_CHANNEL_FR]).unwrap();
+            writer.write(&samples).unwrap();
+            writer.finish().unwrap().into_inner()
+        };
+        let flac = write(AudioFileFormat::Flac);
+        assert!(flac.len() <= write(AudioFileFormat::Wav).len(), "{} bytes", flac.len());
+
+        let (_, _, decoded) = decode_flac(&flac);
+        let max = ((1 << 23) - 1) as f32;
+        for (channel, decoded) in decoded.iter().enumerate() {
+            for (sample, expected) in decoded.iter().zip(samples.iter().skip(channel).step_by(2)) {
+                assert!((*sample as f32 / max - expected).abs() < 1e-6);
+            }
+        }
+    }
+
+    /// Checks the writer against libFLAC's reference decoder, which verifies the frame CRCs while decoding.
+    /// Covers more than 128 frames so frame numbers take several bytes, a rate without a code of its own and a short last frame.
+    #[test]
+    fn decodes_with_reference_flac() {
+        let path = std::env::temp_dir().join(format!("pwvucontrol-test-{}.flac", std::process::id()));
+        let frames = FLAC_BLOCK_SIZE * 130 + 77;
+        let samples: Vec<f32> =
+            (0..frames).flat_map(|i| [(i as f32 * 0.01).sin() * 0.8, (i as f32 * 0.003).cos() * -0.25, ((i * 7919) % 1000) as f32 / 1000.0 - 0.5]).collect();
+
+        let mut writer = AudioFileWriter::create(&path, AudioFileFormat::Flac, 22050, &[SPA_AUDIO_CHANNEL_FL, SPA_AUDIO_CHANNEL_FR, SPA_AUDIO_CHANNEL_FC]).unwrap();
+        writer.write(&samples).unwrap();
+        writer.finish().unwrap();
+
+        let output = std::process::Command::new("flac")
+            .args(["--decode", "--silent", "--stdout", "--force-raw-format", "--endian=little", "--sign=signed"])
+            .arg(&path)
+            .output();
+        let _ = std::fs::remove_file(&path);
+        let output = match output {
+            Ok(output) => output,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                eprintln!("flac is not installed, skipping the reference decoder check");
+                return;
+            }
+            Err(e) => panic!("Cannot run flac: {e}"),
+        };
+        assert!(output.status.success(), "flac failed: {}", String::from_utf8_lossy(&output.stderr));
+
+        let decoded: Vec<i32> = output.stdout.chunks_exact(3).map(|x| i32::from_le_bytes([0, x[0], x[1], x[2]]) >> 8).collect();
+        assert_eq!(decoded.len(), samples.len());
+        let max = ((1 << 23) - 1) as f64;
+        for (sample, expected) in decoded.iter().zip(samples.iter()) {
+            assert_eq!(*sample as i64, (*expected as f64 * max).round() as i64);
+        }
+    }
+
+    #[test]
+    fn writes_wav() {
+        let samples = test_signal(10);
+        let mut writer = AudioFileWriter::new(Cursor::new(Vec::new()), AudioFileFormat::Wav, 44100, &[SPA_AUDIO_CHANNEL_FL, SPA_AUDIO_CHANNEL_FR]).unwrap();
+        writer.write(&samples).unwrap();
+        let bytes = writer.bytes();
+        let data = writer.finish().unwrap().into_inner();
+
+        assert_eq!(data.len() as u64, bytes);
+        assert_eq!(&data[..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize, data.len() - 8);
+        assert_eq!(u32::from_le_bytes(data[54..58].try_into().unwrap()), 80);
+        assert_eq!(f32::from_le_bytes(data[58..62].try_into().unwrap()), samples[0]);
+    }
+
+    #[test]
+    fn writes_extensible_wav() {
+        let positions = [SPA_AUDIO_CHANNEL_FL, SPA_AUDIO_CHANNEL_FR, SPA_AUDIO_CHANNEL_FC, SPA_AUDIO_CHANNEL_LFE, SPA_AUDIO_CHANNEL_SL, SPA_AUDIO_CHANNEL_SR];
+        let samples: Vec<f32> = (0..60).map(|i| i as f32 / 60.0).collect();
+        let mut writer = AudioFileWriter::new(Cursor::new(Vec::new()), AudioFileFormat::Wav, 48000, &positions).unwrap();
+        writer.write(&samples).unwrap();
+        let data = writer.finish().unwrap().into_inner();
+
+        assert_eq!(u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize, data.len() - 8);
+        assert_eq!(u32::from_le_bytes(data[16..20].try_into().unwrap()), 40);
+        assert_eq!(u16::from_le_bytes(data[20..22].try_into().unwrap()), 0xfffe);
+        assert_eq!(u16::from_le_bytes(data[22..24].try_into().unwrap()), 6);
+        assert_eq!(u32::from_le_bytes(data[40..44].try_into().unwrap()), 0x60f);
+        assert_eq!(&data[72..76], b"data");
+        assert_eq!(u32::from_le_bytes(data[76..80].try_into().unwrap()), 240);
+        assert_eq!(f32::from_le_bytes(data[84..88].try_into().unwrap()), samples[1]);
+    }
+
+    #[test]
+    fn masks_only_wav_speaker_order() {
+        assert_eq!(wav_channel_mask(&[SPA_AUDIO_CHANNEL_FL, SPA_AUDIO_CHANNEL_FR, SPA_AUDIO_CHANNEL_RL, SPA_AUDIO_CHANNEL_RR]), 0x33);
+        assert_eq!(wav_channel_mask(&[SPA_AUDIO_CHANNEL_FR, SPA_AUDIO_CHANNEL_FL, SPA_AUDIO_CHANNEL_FC]), 0);
+        assert_eq!(wav_channel_mask(&[SPA_AUDIO_CHANNEL_FL, SPA_AUDIO_CHANNEL_FL, SPA_AUDIO_CHANNEL_FC]), 0);
+        assert_eq!(wav_channel_mask(&[SPA_AUDIO_CHANNEL_FL, SPA_AUDIO_CHANNEL_FR, SPA_AUDIO_CHANNEL_AUX0]), 0);
+    }
+
+    #[test]
+    fn codes_frame_numbers() {
+        let mut bits = BitWriter::default();
+        bits.put_utf8(0x7f);
+        bits.put_utf8(0x80);
+        bits.put_utf8(0x800);
+        assert_eq!(bits.bytes, [0x7f, 0xc2, 0x80, 0xe0, 0xa0, 0x80]);
+    }
+}
@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::backend::{pwnodeobject::MetadataExtFix, spajson};
+use crate::macros::*;
+use glib::{clone, SignalHandlerId};
+use gtk::{prelude::*, subclass::prelude::*};
+use std::cell::{Cell, RefCell};
+use wireplumber as wp;
+
+const KEYS: [&str; 7] = [
+    "clock.rate",
+    "clock.quantum",
+    "clock.allowed-rates",
+    "clock.min-quantum",
+    "clock.max-quantum",
+    "clock.force-rate",
+    "clock.force-quantum",
+];
+
+mod imp {
+    use super::*;
+
+    /// Mirrors the clock.* keys of the PipeWire "settings" metadata. A forced rate or quantum of 0 means not forced.
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::PwClockSettings)]
+    pub struct PwClockSettings {
+        #[property(get)]
+        pub(super) metadata: RefCell<Option<wp::pw::Metadata>>,
+        pub(super) changed_handler: RefCell<Option<SignalHandlerId>>,
+
+        #[property(get)]
+        pub(super) rate: Cell<u32>,
+        #[property(get)]
+        pub(super) quantum: Cell<u32>,
+        /// SPA-JSON array like `[ 44100 48000 ]`, see [`super::PwClockSettings::allowed_rate_list`].
+        #[property(get)]
+        pub(super) allowed_rates: RefCell<String>,
+        #[property(get)]
+        pub(super) min_quantum: Cell<u32>,
+        #[property(get)]
+        pub(super) max_quantum: Cell<u32>,
+        #[property(get)]
+        pub(super) force_rate: Cell<u32>,
+        #[property(get)]
+        pub(super) force_quantum: Cell<u32>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwClockSettings {
+        const NAME: &'static str = "PwClockSettings";
+        type Type = super::PwClockSettings;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwClockSettings {}
+
+    impl PwClockSettings {
+        pub(super) fn update(&self, key: &str, value: Option<&str>) {
+            let obj = self.obj();
+            if key == "clock.allowed-rates" {
+                let value = value.unwrap_or_default().to_string();
+                if *self.allowed_rates.borrow() != value {
+                    self.allowed_rates.replace(value);
+                    obj.notify_allowed_rates();
+                }
+                return;
+            }
+
+            let (cell, notify): (&Cell<u32>, fn(&super::PwClockSettings)) = match key {
+                "clock.rate" => (&self.rate, super::PwClockSettings::notify_rate),
+                "clock.quantum" => (&self.quantum, super::PwClockSettings::notify_quantum),
+                "clock.min-quantum" => (&self.min_quantum, super::PwClockSettings::notify_min_quantum),
+                "clock.max-quantum" => (&self.max_quantum, super::PwClockSettings::notify_max_quantum),
+                "clock.force-rate" => (&self.force_rate, super::PwClockSettings::notify_force_rate),
+                "clock.force-quantum" => (&self.force_quantum, super::PwClockSettings::notify_force_quantum),
+                _ => return,
+            };
+
+            let value = value.and_then(|x| x.trim().parse().ok()).unwrap_or_default();
+            if cell.replace(value) != value {
+                notify(&obj);
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct PwClockSettings(ObjectSubclass<imp::PwClockSettings>);
+}
+
+impl Default for PwClockSettings {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl PwClockSettings {
+    pub(crate) fn set_metadata(&self, metadata: Option<&wp::pw::Metadata>) {
+        let imp = self.imp();
+        if let (Some(old), Some(handler)) = (imp.metadata.take(), imp.changed_handler.take()) {
+            old.disconnect(handler);
+        }
+
+        if let Some(metadata) = metadata {
+            imp.changed_handler.replace(Some(metadata.connect_changed(clone!(
+                #[weak(rename_to = settings)] self,
+                move |_, subject, key, _, value| {
+                    if subject == 0 {
+                        match key {
+                            Some(key) => settings.imp().update(key, value),
+                            None => KEYS.iter().for_each(|key| settings.imp().update(key, None)),
+                        }
+                    }
+                }
+            ))));
+        }
+        imp.metadata.replace(metadata.cloned());
+
+        for key in KEYS {
+            let value = metadata.and_then(|x| x.find_notype(0, key));
+            imp.update(key, value.as_deref());
+        }
+        self.notify_metadata();
+    }
+
+    fn write(&self, key: &str, value: &str) {
+        match self.metadata() {
+            Some(metadata) => metadata.set(0, Some(key), None, Some(value)),
+            None => pwvucontrol_warning!("Cannot set {key}, no settings metadata"),
+        }
+    }
+
+    /// Forces the graph to run at `rate`, 0 lets PipeWire pick it again.
+    pub fn set_force_rate(&self, rate: u32) {
+        self.write("clock.force-rate", &rate.to_string());
+    }
+
+    /// Forces the graph to use `quantum`, 0 lets PipeWire pick it again.
+    pub fn set_force_quantum(&self, quantum: u32) {
+        self.write("clock.force-quantum", &quantum.to_string());
+    }
+
+    pub fn set_min_quantum(&self, quantum: u32) {
+        self.write("clock.min-quantum", &quantum.to_string());
+    }
+
+    pub fn set_max_quantum(&self, quantum: u32) {
+        self.write("clock.max-quantum", &quantum.to_string());
+    }
+
+    pub fn allowed_rate_list(&self) -> Vec<u32> {
+        parse_rates(&self.allowed_rates())
+    }
+
+    pub fn set_allowed_rates(&self, rates: &[u32]) {
+        self.write("clock.allowed-rates", &format_rates(rates));
+    }
+}
+
+pub(crate) fn parse_rates(value: &str) -> Vec<u32> {
+    spajson::parse_uint_array(value)
+}
+
+pub(crate) fn format_rates(rates: &[u32]) -> String {
+    format!("[ {} ]", rates.iter().map(u32::to_string).collect::<Vec<_>>().join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allowed_rates_round_trip() {
+        assert_eq!(parse_rates("[ 44100 48000 ]"), vec![44100, 48000]);
+        assert_eq!(parse_rates("[48000,96000]"), vec![48000, 96000]);
+        assert_eq!(parse_rates(""), Vec::<u32>::new());
+        assert_eq!(parse_rates(&format_rates(&[44100, 48000, 96000])), vec![44100, 48000, 96000]);
+    }
+}
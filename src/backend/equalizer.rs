@@ -0,0 +1,239 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::spajson;
+use crate::macros::*;
+use gtk::glib;
+use std::path::PathBuf;
+use wireplumber as wp;
+use wp::spa::SpaPodBuilder;
+
+const NODE_NAME_PREFIX: &str = "pwvucontrol.eq.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EqBandType {
+    LowShelf,
+    Peaking,
+    HighShelf,
+}
+
+impl EqBandType {
+    pub const ALL: [EqBandType; 3] = [EqBandType::LowShelf, EqBandType::Peaking, EqBandType::HighShelf];
+
+    /// Label of the filter-chain builtin biquad.
+    fn label(&self) -> &'static str {
+        match self {
+            EqBandType::LowShelf => "bq_lowshelf",
+            EqBandType::Peaking => "bq_peaking",
+            EqBandType::HighShelf => "bq_highshelf",
+        }
+    }
+
+    fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|x| x.label() == label)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EqBand {
+    pub band_type: EqBandType,
+    pub freq: f32,
+    pub gain: f32,
+    pub q: f32,
+}
+
+/// Biquad bands of a sink's equalizer.
+///
+/// The equalizer is a filter-chain that shows up as a sink of its own and plays into
+/// the equalized sink. Changing the band types changes the filter graph, so the
+/// filter-chain has to be reloaded, while frequency, gain and Q are Props controls.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Equalizer {
+    pub enabled: bool,
+    pub bands: Vec<EqBand>,
+}
+
+impl Default for Equalizer {
+    fn default() -> Self {
+        let band = |band_type, freq| EqBand { band_type, freq, gain: 0.0, q: 0.7 };
+        Self {
+            enabled: false,
+            bands: vec![
+                band(EqBandType::LowShelf, 80.0),
+                band(EqBandType::Peaking, 250.0),
+                band(EqBandType::Peaking, 1000.0),
+                band(EqBandType::Peaking, 4000.0),
+                band(EqBandType::HighShelf, 10000.0),
+            ],
+        }
+    }
+}
+
+fn band_name(index: usize) -> String {
+    format!("eq_band_{}", index + 1)
+}
+
+impl Equalizer {
+    /// node.name of the equalizer sink for `sink_name`.
+    pub fn node_name(sink_name: &str) -> String {
+        format!("{NODE_NAME_PREFIX}{sink_name}")
+    }
+
+    pub fn is_equalizer_node(node_name: &str) -> bool {
+        node_name.starts_with(NODE_NAME_PREFIX)
+    }
+
+    /// Arguments for libpipewire-module-filter-chain.
+    pub(crate) fn filter_chain_arguments(&self, sink_name: &str, sink_description: &str) -> String {
+        let nodes: Vec<String> = self
+            .bands
+            .iter()
+            .enumerate()
+            .map(|(i, band)| {
+                format!(
+                    "{{ type = builtin name = {} label = {} control = {{ \"Freq\" = {} \"Q\" = {} \"Gain\" = {} }} }}",
+                    band_name(i),
+                    band.band_type.label(),
+                    band.freq,
+                    band.q,
+                    band.gain
+                )
+            })
+            .collect();
+        let links: Vec<String> =
+            (1..self.bands.len()).map(|i| format!("{{ output = \"{}:Out\" input = \"{}:In\" }}", band_name(i - 1), band_name(i))).collect();
+
+        let description = spajson::quote(&format!("{sink_description} (Equalizer)"));
+        let node_name = Self::node_name(sink_name);
+
+        format!(
+            "{{ node.description = {description} media.name = {description} \
+             filter.graph = {{ nodes = [ {} ] links = [ {} ] }} \
+             audio.channels = 2 audio.position = [ FL FR ] \
+             capture.props = {{ node.name = {} media.class = Audio/Sink }} \
+             playback.props = {{ node.name = {} node.passive = true target.object = {} stream.dont-remix = true }} }}",
+            nodes.join(" "),
+            links.join(" "),
+            spajson::quote(&node_name),
+            spajson::quote(&format!("{node_name}.output")),
+            spajson::quote(sink_name)
+        )
+    }
+
+    /// Props param that updates frequency, gain and Q of all bands on a running filter-chain.
+    pub(crate) fn props_pod(&self) -> Option<wp::spa::SpaPod> {
+        let params = SpaPodBuilder::new_struct();
+        for (i, band) in self.bands.iter().enumerate() {
+            for (control, value) in [("Freq", band.freq), ("Q", band.q), ("Gain", band.gain)] {
+                params.add_string(&format!("{}:{control}", band_name(i)));
+                params.add_float(value);
+            }
+        }
+
+        let podbuilder = SpaPodBuilder::new_object("Spa:Pod:Object:Param:Props", "Props");
+        podbuilder.add_property("params");
+        podbuilder.add_pod(&params.end()?);
+        podbuilder.end()
+    }
+
+    /// Band types in order, used to tell whether the filter graph has to be rebuilt.
+    pub fn band_types(&self) -> Vec<EqBandType> {
+        self.bands.iter().map(|x| x.band_type).collect()
+    }
+
+    pub fn write_to_key_file(&self, keyfile: &glib::KeyFile, sink_name: &str) {
+        let join = |values: Vec<String>| values.into_iter().map(|x| x + ";").collect::<String>();
+        keyfile.set_boolean(sink_name, "enabled", self.enabled);
+        keyfile.set_value(sink_name, "types", &join(self.bands.iter().map(|x| x.band_type.label().to_string()).collect()));
+        keyfile.set_value(sink_name, "frequencies", &join(self.bands.iter().map(|x| x.freq.to_string()).collect()));
+        keyfile.set_value(sink_name, "gains", &join(self.bands.iter().map(|x| x.gain.to_string()).collect()));
+        keyfile.set_value(sink_name, "q", &join(self.bands.iter().map(|x| x.q.to_string()).collect()));
+    }
+
+    pub fn read_from_key_file(keyfile: &glib::KeyFile, sink_name: &str) -> Option<Self> {
+        let types = keyfile.string_list(sink_name, "types").ok()?;
+        let freqs = keyfile.double_list(sink_name, "frequencies").ok()?;
+        let gains = keyfile.double_list(sink_name, "gains").ok()?;
+        let qs = keyfile.double_list(sink_name, "q").ok()?;
+
+        if [freqs.len(), gains.len(), qs.len()].iter().any(|&len| len != types.len()) {
+            pwvucontrol_warning!("Equalizer for {sink_name} has mismatched band lists");
+            return None;
+        }
+
+        let bands = types
+            .iter()
+            .zip(freqs.iter().zip(gains.iter().zip(qs.iter())))
+            .map(|(band_type, (&freq, (&gain, &q)))| {
+                Some(EqBand { band_type: EqBandType::from_label(band_type)?, freq: freq as f32, gain: gain as f32, q: q as f32 })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self { enabled: keyfile.boolean(sink_name, "enabled").unwrap_or_default(), bands })
+    }
+
+    /// Saved settings for the sink, or the default bands if there are none.
+    pub fn load(sink_name: &str) -> Self {
+        let keyfile = glib::KeyFile::new();
+        if keyfile.load_from_file(Self::path(), glib::KeyFileFlags::NONE).is_err() {
+            return Self::default();
+        }
+        Self::read_from_key_file(&keyfile, sink_name).unwrap_or_default()
+    }
+
+    pub fn save(&self, sink_name: &str) {
+        let path = Self::path();
+        let keyfile = glib::KeyFile::new();
+        // A missing file just means this is the first equalizer.
+        let _ = keyfile.load_from_file(&path, glib::KeyFileFlags::KEEP_COMMENTS);
+        self.write_to_key_file(&keyfile, sink_name);
+
+        if let Some(dir) = path.parent() {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                pwvucontrol_warning!("Cannot create config directory: {e}");
+            }
+        }
+        if let Err(e) = keyfile.save_to_file(path) {
+            pwvucontrol_warning!("Cannot save equalizer for {sink_name}: {e}");
+        }
+    }
+
+    fn path() -> PathBuf {
+        glib::user_config_dir().join("pwvucontrol").join("equalizers.conf")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equalizer_survives_key_file_round_trip() {
+        let mut eq = Equalizer { enabled: true, ..Default::default() };
+        eq.bands[2].gain = -3.5;
+        eq.bands[2].q = 1.4;
+        eq.bands[4].band_type = EqBandType::Peaking;
+
+        let keyfile = glib::KeyFile::new();
+        eq.write_to_key_file(&keyfile, "alsa_output.usb-headphones");
+        Equalizer::default().write_to_key_file(&keyfile, "alsa_output.hdmi");
+
+        let parsed = glib::KeyFile::new();
+        parsed.load_from_data(&keyfile.to_data(), glib::KeyFileFlags::NONE).expect("valid key file");
+
+        assert_eq!(Equalizer::read_from_key_file(&parsed, "alsa_output.usb-headphones"), Some(eq));
+        assert_eq!(Equalizer::read_from_key_file(&parsed, "alsa_output.hdmi"), Some(Equalizer::default()));
+        assert_eq!(Equalizer::read_from_key_file(&parsed, "missing"), None);
+    }
+
+    #[test]
+    fn filter_graph_chains_the_bands() {
+        let eq = Equalizer { enabled: true, bands: Equalizer::default().bands[..2].to_vec() };
+        let args = eq.filter_chain_arguments("alsa_output.usb", "USB \"DAC\"");
+
+        assert!(args.contains("{ type = builtin name = eq_band_1 label = bq_lowshelf control = { \"Freq\" = 80 \"Q\" = 0.7 \"Gain\" = 0 } }"));
+        assert!(args.contains("links = [ { output = \"eq_band_1:Out\" input = \"eq_band_2:In\" } ]"));
+        assert!(args.contains("node.description = \"USB \\\"DAC\\\" (Equalizer)\""));
+        assert!(args.contains("node.name = \"pwvucontrol.eq.alsa_output.usb\" media.class = Audio/Sink"));
+        assert!(args.contains("target.object = \"alsa_output.usb\""));
+    }
+}
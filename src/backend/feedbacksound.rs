@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::f32::consts::PI;
+
+/// Feedback sounds peak at -8 dBFS before the sink volume applies.
+const AMPLITUDE: f32 = 0.4;
+/// Fade in and out to avoid clicks, in seconds.
+const FADE: f32 = 0.005;
+
+/// Short sound played through a sink when its volume changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FeedbackSound {
+    #[default]
+    Beep,
+    Chime,
+    Click,
+    Pop,
+}
+
+impl FeedbackSound {
+    /// Name used in the volume-feedback-sound setting.
+    pub fn from_nick(nick: &str) -> Option<Self> {
+        match nick {
+            "beep" => Some(Self::Beep),
+            "chime" => Some(Self::Chime),
+            "click" => Some(Self::Click),
+            "pop" => Some(Self::Pop),
+            _ => None,
+        }
+    }
+
+    /// Renders the sound as mono samples at `rate`.
+    pub fn render(self, rate: u32) -> Vec<f32> {
+        let rate = rate as f32;
+        let (duration, sample): (f32, fn(f32) -> f32) = match self {
+            Self::Beep => (0.12, |t| (2.0 * PI * 880.0 * t).sin()),
+            // Two notes a fifth apart, each dying away.
+            Self::Chime => (0.3, |t| {
+                let (frequency, start) = if t < 0.1 { (660.0, 0.0) } else { (990.0, 0.1) };
+                (2.0 * PI * frequency * t).sin() * (-(t - start) * 15.0).exp()
+            }),
+            Self::Click => (0.02, |t| (2.0 * PI * 2000.0 * t).sin() * (-t * 150.0).exp()),
+            // Falling pitch, the phase being the integral of 1200 * e^(-25t) Hz.
+            Self::Pop => (0.06, |t| (2.0 * PI * 1200.0 * (1.0 - (-25.0 * t).exp()) / 25.0).sin()),
+        };
+
+        let frames = (duration * rate) as usize;
+        (0..frames)
+            .map(|i| {
+                let t = i as f32 / rate;
+                let envelope = (t / FADE).min((duration - t) / FADE).clamp(0.0, 1.0);
+                AMPLITUDE * envelope * sample(t)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_short_sounds() {
+        for (nick, sound) in [("beep", FeedbackSound::Beep), ("chime", FeedbackSound::Chime), ("click", FeedbackSound::Click), ("pop", FeedbackSound::Pop)] {
+            assert_eq!(FeedbackSound::from_nick(nick), Some(sound));
+
+            let samples = sound.render(48000);
+            assert!(!samples.is_empty() && samples.len() <= 48000 / 2, "{sound:?}");
+            let peak = samples.iter().fold(0.0f32, |a, x| a.max(x.abs()));
+            assert!(peak > 0.1 && peak <= AMPLITUDE, "{sound:?} {peak}");
+            assert!(samples[0].abs() < 0.01 && samples[samples.len() - 1].abs() < 0.01, "{sound:?}");
+        }
+    }
+}
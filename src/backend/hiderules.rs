@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::fmt;
+
+/// Hides a node when its PipeWire property `key` is set, or when it matches `value`.
+/// Rules are written as `key` or `key=value`, where `*` in the value matches any text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HideRule {
+    pub key: String,
+    pub value: Option<String>,
+}
+
+impl HideRule {
+    pub fn parse(rule: &str) -> Option<Self> {
+        let (key, value) = match rule.split_once('=') {
+            Some((key, value)) => (key.trim(), Some(value.trim().to_string())),
+            None => (rule.trim(), None),
+        };
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return None;
+        }
+        Some(Self { key: key.to_string(), value })
+    }
+
+    pub fn matches(&self, property: impl Fn(&str) -> Option<String>) -> bool {
+        match (property(&self.key), &self.value) {
+            (Some(actual), Some(pattern)) => wildcard_match(pattern, &actual),
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// Parses a list of rules, skipping the ones that don't parse.
+    pub fn parse_all<'a>(rules: impl IntoIterator<Item = &'a str>) -> Vec<Self> {
+        rules.into_iter().filter_map(Self::parse).collect()
+    }
+}
+
+impl fmt::Display for HideRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{}={}", self.key, value),
+            None => write!(f, "{}", self.key),
+        }
+    }
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let Some((prefix, rest)) = pattern.split_once('*') else {
+        return pattern == text;
+    };
+    let Some(mut text) = text.strip_prefix(prefix) else {
+        return false;
+    };
+
+    let mut parts: Vec<&str> = rest.split('*').collect();
+    let suffix = parts.pop().unwrap_or_default();
+    for part in parts {
+        match text.find(part) {
+            Some(i) => text = &text[i + part.len()..],
+            None => return false,
+        }
+    }
+    text.ends_with(suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rules() {
+        assert_eq!(HideRule::parse("event.id"), Some(HideRule { key: "event.id".into(), value: None }));
+        assert_eq!(
+            HideRule::parse("media.role = Notification"),
+            Some(HideRule { key: "media.role".into(), value: Some("Notification".into()) })
+        );
+        assert_eq!(HideRule::parse("node.name=a=b").and_then(|x| x.value), Some("a=b".into()));
+        assert_eq!(HideRule::parse(" "), None);
+        assert_eq!(HideRule::parse("=value"), None);
+        assert_eq!(HideRule::parse("media.role=Notification").map(|x| x.to_string()).as_deref(), Some("media.role=Notification"));
+    }
+
+    #[test]
+    fn matches_properties() {
+        let props = |key: &str| match key {
+            "node.name" => Some("monitoring-tool.probe-3".to_string()),
+            "stream.monitor" => Some("true".to_string()),
+            _ => None,
+        };
+
+        assert!(HideRule::parse("stream.monitor").unwrap().matches(props));
+        assert!(!HideRule::parse("event.id").unwrap().matches(props));
+        assert!(HideRule::parse("node.name=monitoring-tool.*").unwrap().matches(props));
+        assert!(HideRule::parse("node.name=*probe*").unwrap().matches(props));
+        assert!(HideRule::parse("node.name=*-3").unwrap().matches(props));
+        assert!(!HideRule::parse("node.name=monitoring-tool").unwrap().matches(props));
+        assert!(!HideRule::parse("node.name=*probe").unwrap().matches(props));
+    }
+}
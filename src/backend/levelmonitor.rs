@@ -0,0 +1,448 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{
+    backend::{Loudness, LoudnessMeter, Profiler, ProfilerSample, SpectrumAnalyzer, LOUDNESS_RATE, SPECTRUM_RATE},
+    macros::*,
+};
+use glib::{ControlFlow, SourceId};
+use pipewire::{context::ContextRc, core::CoreRc, loop_::LoopRc, properties::properties, spa::{self, utils::Direction}, stream::*};
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt::Debug,
+    os::fd::AsRawFd,
+    rc::{Rc, Weak},
+};
+
+const PEAK_RATE: u32 = 144;
+
+struct Meter {
+    stream: StreamRc,
+    _listener: Box<dyn Any>,
+    /// Hands the latest reading to the meter's callback, if there is a new one.
+    dispatch: Rc<dyn Fn()>,
+}
+
+/// Runs the peak detection and loudness analysis streams of all meters on a single PipeWire connection.
+///
+/// The streams only record the latest readings, [`LevelMonitor::dispatch`] hands them to the
+/// meters in one go and is meant to be called once per frame.
+pub struct LevelMonitor {
+    _loop: LoopRc,
+    _context: ContextRc,
+    core: CoreRc,
+    sig: Cell<Option<SourceId>>,
+    meters: RefCell<HashMap<u32, Meter>>,
+    next_key: Cell<u32>,
+}
+
+impl Debug for LevelMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("LevelMonitor")
+    }
+}
+
+/// Keeps a meter's stream alive, dropping it stops metering.
+pub struct LevelMeter {
+    monitor: Weak<LevelMonitor>,
+    key: u32,
+}
+
+impl Debug for LevelMeter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("LevelMeter")
+    }
+}
+
+impl LevelMonitor {
+    pub fn new() -> Result<Rc<Self>, anyhow::Error> {
+        let loop_ = LoopRc::new(None)?;
+        let context = ContextRc::new(&loop_, None)?;
+        let core = context.connect_rc(None)?;
+
+        let fd = loop_.fd();
+
+        let sig = glib_unix::unix_fd_add_local(fd.as_raw_fd(), glib::IOCondition::all(), {
+            let loop_ = loop_.clone();
+            move |_, _| {
+                loop_.iterate(pipewire::loop_::Timeout::None);
+
+                ControlFlow::Continue
+            }
+        });
+
+        Ok(Rc::new(Self {
+            _loop: loop_,
+            _context: context,
+            core,
+            sig: Cell::new(Some(sig)),
+            meters: Default::default(),
+            next_key: Default::default(),
+        }))
+    }
+
+    /// Meters node `id`, one level per channel at `positions`, or the downmixed signal when `positions` is empty.
+    pub fn add(self: &Rc<Self>, id: u32, positions: &[u32], callback: impl Fn(&[f32]) + 'static) -> Result<LevelMeter, anyhow::Error> {
+        let positions = if positions.is_empty() { &[spa::sys::SPA_AUDIO_CHANNEL_MONO][..] } else { &positions[..positions.len().min(64)] };
+        let channels = positions.len();
+
+        // We impersonate pavucontrol so we don't get listed as recording source in desktop shell / other volume control programs
+        let props = properties! {
+            "node.rate" => "1/25",
+            "node.latency" => "1/25",
+            "node.name" => "PulseAudio Volume Control",
+            "node.passive" => "true",
+            "media.name" => "Peak detect",
+            "resample.peaks" => "true",
+            "stream.monitor" => "true",
+            "application.id" => "org.PulseAudio.pavucontrol",
+        };
+
+        let stream = StreamRc::new(self.core.clone(), "peakdetect", props)?;
+        let levels: Rc<RefCell<Option<Vec<f32>>>> = Default::default();
+
+        let listener = stream
+            .add_local_listener_with_user_data(vec![0.0f32; channels])
+            .process({
+                let levels = levels.clone();
+                move |stream, last_peaks| {
+                    match stream.dequeue_buffer() {
+                        None => pwvucontrol_debug!("No buffer received"),
+                        Some(mut buffer) => {
+                            let datas = buffer.datas_mut();
+                            let size = datas[0].chunk().size() as usize;
+
+                            if let Some(d) = datas[0].data() {
+                                // Samples are interleaved, keep the highest peak of each channel.
+                                let frame_size = channels * std::mem::size_of::<f32>();
+                                let frames = (size.min(d.len()) / frame_size).max(1);
+                                let mut peaks = vec![0.0f32; channels];
+                                for frame in d.chunks_exact(frame_size).take(frames) {
+                                    for (peak, sample) in peaks.iter_mut().zip(frame.chunks_exact(std::mem::size_of::<f32>())) {
+                                        *peak = peak.max(f32::from_le_bytes(sample.try_into().unwrap()).abs());
+                                    }
+                                }
+
+                                const DECAY_STEP: f32 = 1.0 / PEAK_RATE as f32;
+                                for (peak, last_peak) in peaks.iter_mut().zip(last_peaks.iter_mut()) {
+                                    let mut max = peak.clamp(0.0, 1.0);
+                                    if *last_peak >= DECAY_STEP && max < *last_peak - DECAY_STEP {
+                                        max = *last_peak - DECAY_STEP;
+                                    }
+                                    *last_peak = max;
+                                    *peak = max;
+                                }
+
+                                levels.replace(Some(peaks));
+                            }
+                        }
+                    };
+                }
+            })
+            .state_changed({
+                let levels = levels.clone();
+                move |_stream, _user_data, _oldstate, state| {
+                    if state == StreamState::Paused {
+                        levels.replace(Some(Vec::new()));
+                    }
+                }
+            })
+            .param_changed(move |stream, _, _, _| {
+                let _ = stream.set_control(pipewire::spa::sys::SPA_PROP_channelVolumes, &vec![1.0; channels]);
+            })
+            .register()?;
+
+        let mut buffer: Vec<u8> = Vec::new();
+        let format = create_audio_format_pod(&mut buffer, PEAK_RATE, positions);
+        self.connect(id, stream, listener, Direction::Input, format, deliver(levels, move |x: &Vec<f32>| callback(x)))
+    }
+
+    /// Analyses the loudness of node `id`, see [`Loudness`]. Readings arrive every 100 ms.
+    pub fn add_loudness(self: &Rc<Self>, id: u32, positions: &[u32], callback: impl Fn(&Loudness) + 'static) -> Result<LevelMeter, anyhow::Error> {
+        let positions = if positions.is_empty() {
+            &[spa::sys::SPA_AUDIO_CHANNEL_FL, spa::sys::SPA_AUDIO_CHANNEL_FR][..]
+        } else {
+            &positions[..positions.len().min(64)]
+        };
+
+        let props = properties! {
+            "node.latency" => "4800/48000",
+            "node.name" => "PulseAudio Volume Control",
+            "node.passive" => "true",
+            "media.name" => "Loudness analysis",
+            "stream.monitor" => "true",
+            "application.id" => "org.PulseAudio.pavucontrol",
+        };
+
+        let stream = StreamRc::new(self.core.clone(), "loudness", props)?;
+        let loudness: Rc<RefCell<Option<Loudness>>> = Default::default();
+
+        let listener = stream
+            .add_local_listener_with_user_data(LoudnessMeter::new(LOUDNESS_RATE, positions))
+            .process({
+                let loudness = loudness.clone();
+                move |stream, meter| {
+                    let Some(mut buffer) = stream.dequeue_buffer() else {
+                        return;
+                    };
+                    let datas = buffer.datas_mut();
+                    let size = datas[0].chunk().size() as usize;
+                    if let Some(d) = datas[0].data() {
+                        let samples: Vec<f32> =
+                            d[..size.min(d.len())].chunks_exact(std::mem::size_of::<f32>()).map(|x| f32::from_le_bytes(x.try_into().unwrap())).collect();
+                        if meter.process(&samples) {
+                            loudness.replace(Some(meter.loudness()));
+                        }
+                    }
+                }
+            })
+            .register()?;
+
+        let mut buffer: Vec<u8> = Vec::new();
+        let format = create_audio_format_pod(&mut buffer, LOUDNESS_RATE, positions);
+        self.connect(id, stream, listener, Direction::Input, format, deliver(loudness, callback))
+    }
+
+    /// Analyses the spectrum of node `id` downmixed to mono, see [`SpectrumAnalyzer::spectrum`].
+    pub fn add_spectrum(self: &Rc<Self>, id: u32, callback: impl Fn(&[f32]) + 'static) -> Result<LevelMeter, anyhow::Error> {
+        let props = properties! {
+            "node.latency" => "1024/48000",
+            "node.name" => "PulseAudio Volume Control",
+            "node.passive" => "true",
+            "media.name" => "Spectrum analyzer",
+            "stream.monitor" => "true",
+            "application.id" => "org.PulseAudio.pavucontrol",
+        };
+
+        let stream = StreamRc::new(self.core.clone(), "spectrum", props)?;
+        let spectrum: Rc<RefCell<Option<Vec<f32>>>> = Default::default();
+
+        let listener = stream
+            .add_local_listener_with_user_data(SpectrumAnalyzer::new())
+            .process({
+                let spectrum = spectrum.clone();
+                move |stream, analyzer| {
+                    let Some(mut buffer) = stream.dequeue_buffer() else {
+                        return;
+                    };
+                    let datas = buffer.datas_mut();
+                    let size = datas[0].chunk().size() as usize;
+                    if let Some(d) = datas[0].data() {
+                        let samples: Vec<f32> =
+                            d[..size.min(d.len())].chunks_exact(std::mem::size_of::<f32>()).map(|x| f32::from_le_bytes(x.try_into().unwrap())).collect();
+                        if analyzer.process(&samples) {
+                            spectrum.replace(Some(analyzer.spectrum()));
+                        }
+                    }
+                }
+            })
+            .register()?;
+
+        let mut buffer: Vec<u8> = Vec::new();
+        let format = create_audio_format_pod(&mut buffer, SPECTRUM_RATE, &[spa::sys::SPA_AUDIO_CHANNEL_MONO]);
+        self.connect(id, stream, listener, Direction::Input, format, deliver(spectrum, move |x: &Vec<f32>| callback(x)))
+    }
+
+    /// Passes every captured buffer of node `id` to `process` as interleaved samples at `rate`.
+    ///
+    /// Unlike the meters this identifies as ourselves, the stream is really recording.
+    pub fn add_capture(self: &Rc<Self>, id: u32, positions: &[u32], rate: u32, mut process: impl FnMut(&[f32]) + 'static) -> Result<LevelMeter, anyhow::Error> {
+        let props = properties! {
+            "node.name" => "pwvucontrol-recorder",
+            "media.name" => "Recording",
+            "stream.monitor" => "true",
+            "application.id" => crate::config::APP_ID,
+        };
+
+        let stream = StreamRc::new(self.core.clone(), "recorder", props)?;
+
+        let listener = stream
+            .add_local_listener_with_user_data(())
+            .process(move |stream, _| {
+                let Some(mut buffer) = stream.dequeue_buffer() else {
+                    return;
+                };
+                let datas = buffer.datas_mut();
+                let size = datas[0].chunk().size() as usize;
+                if let Some(d) = datas[0].data() {
+                    let samples: Vec<f32> =
+                        d[..size.min(d.len())].chunks_exact(std::mem::size_of::<f32>()).map(|x| f32::from_le_bytes(x.try_into().unwrap())).collect();
+                    process(&samples);
+                }
+            })
+            .register()?;
+
+        let mut buffer: Vec<u8> = Vec::new();
+        let format = create_audio_format_pod(&mut buffer, rate, positions);
+        self.connect(id, stream, listener, Direction::Input, format, || {})
+    }
+
+    /// Plays what `generate` writes as interleaved samples at `rate` to node `id`.
+    ///
+    /// An `event_id` marks the stream as an event sound, which the default hide rules keep out of the stream list.
+    pub fn add_playback(
+        self: &Rc<Self>,
+        id: u32,
+        positions: &[u32],
+        rate: u32,
+        media_name: &str,
+        event_id: Option<&str>,
+        mut generate: impl FnMut(&mut [f32]) + 'static,
+    ) -> Result<LevelMeter, anyhow::Error> {
+        let channels = positions.len().max(1);
+        let mut props = properties! {
+            "node.name" => "pwvucontrol-playback",
+            "media.name" => media_name,
+            "media.type" => "Audio",
+            "media.category" => "Playback",
+            "stream.dont-remix" => "true",
+            "application.id" => crate::config::APP_ID,
+        };
+        if let Some(event_id) = event_id {
+            props.insert("event.id", event_id);
+        }
+
+        let stream = StreamRc::new(self.core.clone(), "playback", props)?;
+
+        let listener = stream
+            .add_local_listener_with_user_data(())
+            .process(move |stream, _| {
+                let Some(mut buffer) = stream.dequeue_buffer() else {
+                    return;
+                };
+                let datas = buffer.datas_mut();
+                let data = &mut datas[0];
+                let stride = channels * std::mem::size_of::<f32>();
+                let frames = match data.data() {
+                    Some(d) => {
+                        let frames = d.len() / stride;
+                        let mut samples = vec![0.0f32; frames * channels];
+                        generate(&mut samples);
+                        for (chunk, sample) in d.chunks_exact_mut(std::mem::size_of::<f32>()).zip(samples) {
+                            chunk.copy_from_slice(&sample.to_le_bytes());
+                        }
+                        frames
+                    }
+                    None => 0,
+                };
+                let chunk = data.chunk_mut();
+                *chunk.offset_mut() = 0;
+                *chunk.stride_mut() = stride as _;
+                *chunk.size_mut() = (stride * frames) as _;
+            })
+            .register()?;
+
+        let mut buffer: Vec<u8> = Vec::new();
+        let format = create_audio_format_pod(&mut buffer, rate, positions);
+        self.connect(id, stream, listener, Direction::Output, format, || {})
+    }
+
+    /// Reports the timing of every graph cycle, see [`Profiler`].
+    pub fn add_profiler(&self, callback: impl Fn(&[ProfilerSample]) + 'static) -> Result<Profiler, anyhow::Error> {
+        Profiler::new(&self.core, callback)
+    }
+
+    fn connect<D: 'static>(
+        self: &Rc<Self>,
+        id: u32,
+        stream: StreamRc,
+        listener: StreamListener<D>,
+        direction: Direction,
+        format: &spa::pod::Pod,
+        dispatch: impl Fn() + 'static,
+    ) -> Result<LevelMeter, anyhow::Error> {
+        stream.connect(
+            direction,
+            Some(id),
+            StreamFlags::AUTOCONNECT | StreamFlags::MAP_BUFFERS | StreamFlags::RT_PROCESS | StreamFlags::DONT_RECONNECT,
+            &mut [format],
+        )?;
+
+        let key = self.next_key.get();
+        self.next_key.set(key.wrapping_add(1));
+        self.meters.borrow_mut().insert(
+            key,
+            Meter {
+                stream,
+                _listener: Box::new(listener),
+                dispatch: Rc::new(dispatch),
+            },
+        );
+
+        Ok(LevelMeter { monitor: Rc::downgrade(self), key })
+    }
+
+    /// Hands the readings that arrived since the last call to their meters.
+    pub fn dispatch(&self) {
+        // Collect first, a callback may well drop its own meter.
+        let pending: Vec<Rc<dyn Fn()>> = self.meters.borrow().values().map(|meter| meter.dispatch.clone()).collect();
+
+        for dispatch in pending {
+            dispatch();
+        }
+    }
+
+    fn remove(&self, key: u32) {
+        let meter = self.meters.borrow_mut().remove(&key);
+        if let Some(meter) = meter {
+            if let Err(e) = meter.stream.disconnect() {
+                pwvucontrol_warning!("Unable to disconnect level meter stream: {e}");
+            }
+        }
+    }
+}
+
+impl Drop for LevelMonitor {
+    fn drop(&mut self) {
+        for (_, meter) in self.meters.take() {
+            let _ = meter.stream.disconnect();
+        }
+        if let Some(sig) = self.sig.take() {
+            sig.remove();
+        }
+    }
+}
+
+impl Drop for LevelMeter {
+    fn drop(&mut self) {
+        if let Some(monitor) = self.monitor.upgrade() {
+            monitor.remove(self.key);
+        }
+    }
+}
+
+/// Hands a new `reading` to `callback` when dispatching.
+fn deliver<T: 'static>(reading: Rc<RefCell<Option<T>>>, callback: impl Fn(&T) + 'static) -> impl Fn() + 'static {
+    move || {
+        let reading = reading.take();
+        if let Some(reading) = reading {
+            callback(&reading);
+        }
+    }
+}
+
+fn create_audio_format_pod<'a>(buffer: &'a mut Vec<u8>, rate: u32, positions: &[u32]) -> &'a spa::pod::Pod {
+    let mut position = [0; 64];
+    position[..positions.len()].copy_from_slice(positions);
+
+    let mut audio_info = spa::param::audio::AudioInfoRaw::new();
+    audio_info.set_format(spa::param::audio::AudioFormat::F32LE);
+    audio_info.set_rate(rate);
+    audio_info.set_channels(positions.len() as u32);
+    audio_info.set_position(position);
+
+    let values = spa::pod::serialize::PodSerializer::serialize(
+        std::io::Cursor::new(buffer),
+        &spa::pod::Value::Object(pipewire::spa::pod::Object {
+            type_: spa::sys::SPA_TYPE_OBJECT_Format,
+            id: spa::sys::SPA_PARAM_EnumFormat,
+            properties: audio_info.into(),
+        }),
+    )
+    .unwrap()
+    .0
+    .into_inner();
+
+    spa::pod::Pod::from_bytes(values).unwrap()
+}
@@ -0,0 +1,72 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::spajson;
+
+const NODE_NAME_PREFIX: &str = "pwvucontrol.listen.";
+/// Latency is given to PipeWire as a fraction of this rate.
+const LATENCY_RATE: u32 = 48000;
+
+/// Plays a source through a sink so it can be monitored, as "Listen to this device" does on other systems.
+///
+/// Like loopback virtual devices these are modules loaded into our own process, they go away
+/// when we exit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListenLoopback {
+    pub source_name: String,
+    /// node.name of the sink to play through, `None` follows the default sink.
+    pub sink_name: Option<String>,
+    pub latency_ms: u32,
+}
+
+impl ListenLoopback {
+    pub const DEFAULT_LATENCY_MS: u32 = 20;
+
+    /// Prefix of the node.name of the loopback's capture and playback nodes for `source_name`.
+    pub fn node_name(source_name: &str) -> String {
+        format!("{NODE_NAME_PREFIX}{source_name}")
+    }
+
+    /// Arguments for libpipewire-module-loopback.
+    pub(crate) fn loopback_arguments(&self, source_description: &str) -> String {
+        let node_name = Self::node_name(&self.source_name);
+        let latency = spajson::quote(&format!("{}/{LATENCY_RATE}", (self.latency_ms.max(1) * LATENCY_RATE / 1000).max(1)));
+        let target = match &self.sink_name {
+            Some(sink_name) => format!("target.object = {} ", spajson::quote(sink_name)),
+            None => String::new(),
+        };
+
+        format!(
+            "{{ node.description = {} \
+             capture.props = {{ node.name = {} target.object = {} node.dont-reconnect = true node.latency = {latency} }} \
+             playback.props = {{ node.name = {} {target}node.latency = {latency} }} }}",
+            spajson::quote(&format!("{source_description} (Listen)")),
+            spajson::quote(&format!("{node_name}.capture")),
+            spajson::quote(&self.source_name),
+            spajson::quote(&format!("{node_name}.playback")),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loopback_targets_source_and_sink() {
+        let mut loopback = ListenLoopback {
+            source_name: "alsa_input.usb".to_string(),
+            sink_name: Some("alsa_output.usb".to_string()),
+            latency_ms: 10,
+        };
+        let args = loopback.loopback_arguments("USB Mic");
+
+        assert!(args.contains("node.description = \"USB Mic (Listen)\""));
+        assert!(args.contains("node.name = \"pwvucontrol.listen.alsa_input.usb.capture\" target.object = \"alsa_input.usb\""));
+        assert!(args.contains("node.name = \"pwvucontrol.listen.alsa_input.usb.playback\" target.object = \"alsa_output.usb\""));
+        assert!(args.contains("node.latency = \"480/48000\""));
+
+        loopback.sink_name = None;
+        let args = loopback.loopback_arguments("USB Mic");
+        assert_eq!(args.matches("target.object").count(), 1);
+    }
+}
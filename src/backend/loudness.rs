@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use pipewire::spa::sys::{SPA_AUDIO_CHANNEL_LFE, SPA_AUDIO_CHANNEL_RL, SPA_AUDIO_CHANNEL_RR, SPA_AUDIO_CHANNEL_SL, SPA_AUDIO_CHANNEL_SR};
+use std::{collections::VecDeque, f64::consts::PI};
+
+/// Sample rate the loudness analysis runs at.
+pub const LOUDNESS_RATE: u32 = 48000;
+
+/// Gating blocks are 100 ms, momentary loudness spans 4 of them and short-term loudness 30.
+const BLOCKS_PER_SECOND: usize = 10;
+const MOMENTARY_BLOCKS: usize = 4;
+const SHORT_TERM_BLOCKS: usize = 30;
+
+/// Loudness readings, `f64::NEG_INFINITY` when silent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Loudness {
+    /// EBU R128 momentary loudness over the last 400 ms, in LUFS.
+    pub momentary: f64,
+    /// EBU R128 short-term loudness over the last 3 s, in LUFS.
+    pub short_term: f64,
+    /// Unweighted RMS level over the last 400 ms, in dBFS.
+    pub rms: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct BiquadState {
+    z: [f64; 2],
+}
+
+impl BiquadState {
+    fn process(&mut self, filter: &Biquad, x: f64) -> f64 {
+        let y = filter.b[0] * x + self.z[0];
+        self.z[0] = filter.b[1] * x - filter.a[0] * y + self.z[1];
+        self.z[1] = filter.b[2] * x - filter.a[1] * y;
+        y
+    }
+}
+
+/// K-weighting of ITU-R BS.1770: a high shelf modelling the head followed by a high pass.
+fn k_weighting(rate: f64) -> [Biquad; 2] {
+    let f0 = 1681.974450955533;
+    let gain = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (PI * f0 / rate).tan();
+    let vh = 10f64.powf(gain / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+
+    [shelf, high_pass]
+}
+
+/// Channel weight of BS.1770, surround channels count more and LFE is left out.
+fn channel_weight(position: u32) -> f64 {
+    match position {
+        SPA_AUDIO_CHANNEL_LFE => 0.0,
+        SPA_AUDIO_CHANNEL_SL | SPA_AUDIO_CHANNEL_SR | SPA_AUDIO_CHANNEL_RL | SPA_AUDIO_CHANNEL_RR => 1.41,
+        _ => 1.0,
+    }
+}
+
+fn power_to_db(power: f64, offset: f64) -> f64 {
+    if power > 0.0 {
+        offset + 10.0 * power.log10()
+    } else {
+        f64::NEG_INFINITY
+    }
+}
+
+/// Measures interleaved audio, see [`Loudness`].
+#[derive(Debug)]
+pub struct LoudnessMeter {
+    filters: [Biquad; 2],
+    weights: Vec<f64>,
+    states: Vec<[BiquadState; 2]>,
+    block_len: usize,
+    block_pos: usize,
+    /// Sums of the weighted K-filtered and the plain squares in the current block.
+    weighted_sum: f64,
+    raw_sum: f64,
+    /// Mean powers of the most recent blocks, newest last.
+    blocks: VecDeque<(f64, f64)>,
+}
+
+impl LoudnessMeter {
+    pub fn new(rate: u32, positions: &[u32]) -> Self {
+        let weights: Vec<f64> = positions.iter().copied().map(channel_weight).collect();
+        Self {
+            filters: k_weighting(rate as f64),
+            states: vec![Default::default(); weights.len()],
+            weights,
+            block_len: (rate as usize / BLOCKS_PER_SECOND).max(1),
+            block_pos: 0,
+            weighted_sum: 0.0,
+            raw_sum: 0.0,
+            blocks: VecDeque::with_capacity(SHORT_TERM_BLOCKS),
+        }
+    }
+
+    /// Feeds interleaved samples, returns whether a new reading is available.
+    pub fn process(&mut self, samples: &[f32]) -> bool {
+        let channels = self.weights.len();
+        if channels == 0 {
+            return false;
+        }
+
+        let mut completed = false;
+        for frame in samples.chunks_exact(channels) {
+            for (channel, sample) in frame.iter().enumerate() {
+                let x = *sample as f64;
+                let [shelf, high_pass] = &mut self.states[channel];
+                let y = high_pass.process(&self.filters[1], shelf.process(&self.filters[0], x));
+                self.weighted_sum += self.weights[channel] * y * y;
+                self.raw_sum += x * x;
+            }
+
+            self.block_pos += 1;
+            if self.block_pos == self.block_len {
+                if self.blocks.len() == SHORT_TERM_BLOCKS {
+                    self.blocks.pop_front();
+                }
+                let frames = self.block_len as f64;
+                self.blocks.push_back((self.weighted_sum / frames, self.raw_sum / (frames * channels as f64)));
+                self.weighted_sum = 0.0;
+                self.raw_sum = 0.0;
+                self.block_pos = 0;
+                completed = true;
+            }
+        }
+        completed
+    }
+
+    pub fn loudness(&self) -> Loudness {
+        let mean = |count: usize, value: fn(&(f64, f64)) -> f64| {
+            let count = count.min(self.blocks.len());
+            if count == 0 {
+                return 0.0;
+            }
+            self.blocks.iter().rev().take(count).map(value).sum::<f64>() / count as f64
+        };
+
+        Loudness {
+            momentary: power_to_db(mean(MOMENTARY_BLOCKS, |x| x.0), -0.691),
+            short_term: power_to_db(mean(SHORT_TERM_BLOCKS, |x| x.0), -0.691),
+            rms: power_to_db(mean(MOMENTARY_BLOCKS, |x| x.1), 0.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pipewire::spa::sys::{SPA_AUDIO_CHANNEL_FL, SPA_AUDIO_CHANNEL_FR};
+
+    fn sine(amplitude: f32, frequency: f32, seconds: f32, channels: usize) -> Vec<f32> {
+        let frames = (LOUDNESS_RATE as f32 * seconds) as usize;
+        (0..frames)
+            .flat_map(|i| {
+                let x = amplitude * (2.0 * std::f32::consts::PI * frequency * i as f32 / LOUDNESS_RATE as f32).sin();
+                std::iter::repeat_n(x, channels)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn measures_sine() {
+        // A 997 Hz sine at -20 dBFS on both channels of a stereo stream reads -20 LUFS.
+        let mut meter = LoudnessMeter::new(LOUDNESS_RATE, &[SPA_AUDIO_CHANNEL_FL, SPA_AUDIO_CHANNEL_FR]);
+        assert!(meter.process(&sine(0.1, 997.0, 4.0, 2)));
+
+        let loudness = meter.loudness();
+        assert!((loudness.momentary + 20.0).abs() < 0.1, "{loudness:?}");
+        assert!((loudness.short_term + 20.0).abs() < 0.1, "{loudness:?}");
+        assert!((loudness.rms + 23.01).abs() < 0.05, "{loudness:?}");
+    }
+
+    #[test]
+    fn ignores_lfe_and_silence() {
+        let mut meter = LoudnessMeter::new(LOUDNESS_RATE, &[SPA_AUDIO_CHANNEL_LFE]);
+        assert_eq!(meter.loudness().momentary, f64::NEG_INFINITY);
+        meter.process(&sine(0.5, 60.0, 1.0, 1));
+        assert_eq!(meter.loudness().momentary, f64::NEG_INFINITY);
+        assert!(meter.loudness().rms > -10.0);
+    }
+}
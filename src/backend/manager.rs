@@ -1,7 +1,12 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use crate::macros::*;
-use crate::{backend::NodeType, backend::PwDeviceObject, backend::PwNodeFilterModel, backend::PwNodeObject, PwvucontrolApplication};
+use crate::{
+    backend::NodeType, backend::PwDeviceObject, backend::PwLinkObject, backend::PwNodeFilterModel, backend::PwNodeObject, backend::PwPortObject,
+    backend::{pwnodeobject::MetadataExtFix, spajson, Equalizer, HideRule, LevelMeter, LevelMonitor, ListenLoopback, Profiler, ProfilerSample, Loudness, PwClockSettings, Recorder, RecordingStatus, VirtualDevice, VirtualDeviceKind},
+    config::APP_ID,
+    PwvucontrolApplication,
+};
 use gtk::{
     gio,
     glib::{self, clone, Properties},
@@ -9,18 +14,24 @@ use gtk::{
     subclass::prelude::*,
 };
 use std::cell::{OnceCell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::time::Duration;
 use wireplumber as wp;
 use wp::{
+    core::ObjectExt2,
     plugin::*,
-    pw::{MetadataExt, PipewireObjectExt2, ProxyExt},
+    pw::{GlobalProxyExt, MetadataExt, PipewireObjectExt, PipewireObjectExt2, ProxyExt},
     registry::{Constraint, ConstraintType, Interest, ObjectManager},
 };
 
+/// Equalizer changes are written to disk once the spin buttons have been left alone for this long.
+const EQUALIZER_SAVE_DELAY: Duration = Duration::from_millis(500);
+const DEFAULT_NODE_KEYS: [&str; 4] = ["default.audio.sink", "default.audio.source", "default.configured.audio.sink", "default.configured.audio.source"];
+
 mod imp {
     use std::cell::Cell;
 
-    use wireplumber::core::ObjectExt2;
-
     use super::*;
 
     #[derive(Properties)]
@@ -49,10 +60,43 @@ mod imp {
         #[property(get)]
         pub(crate) device_model: gio::ListStore,
 
+        /// Nodes matching any of these are marked hidden, see the hide-rules setting.
+        pub(super) hide_rules: RefCell<Vec<HideRule>>,
+        pub(super) settings: OnceCell<gio::Settings>,
+
+        /// Reveals hidden nodes in the filtered models until switched off again.
+        #[property(get, set = Self::set_show_hidden)]
+        pub(crate) show_hidden: Cell<bool>,
+
         pub metadata_om: OnceCell<wp::registry::ObjectManager>,
         #[property(get)]
         pub metadata: RefCell<Option<wp::pw::Metadata>>,
 
+        /// node.name of the default nodes as chosen by the session manager.
+        #[property(get)]
+        pub(crate) default_sink: RefCell<Option<String>>,
+        #[property(get)]
+        pub(crate) default_source: RefCell<Option<String>>,
+        /// node.name of the default nodes as chosen by the user, which may not be present.
+        #[property(get)]
+        pub(crate) configured_default_sink: RefCell<Option<String>>,
+        #[property(get)]
+        pub(crate) configured_default_source: RefCell<Option<String>>,
+
+        /// Watches the "settings" metadata that holds the clock.* keys.
+        pub settings_metadata_om: OnceCell<wp::registry::ObjectManager>,
+        #[property(get)]
+        pub(crate) clock_settings: PwClockSettings,
+
+        /// Tracks every node, port and link for the graph view.
+        pub graph_om: OnceCell<wp::registry::ObjectManager>,
+        #[property(get)]
+        pub(crate) graph_node_model: gio::ListStore,
+        #[property(get)]
+        pub(crate) port_model: gio::ListStore,
+        #[property(get)]
+        pub(crate) link_model: gio::ListStore,
+
         #[property(get)]
         pub default_nodes_api: OnceCell<Plugin>,
         #[property(get)]
@@ -62,6 +106,36 @@ mod imp {
         application: RefCell<Option<PwvucontrolApplication>>,
 
         pub plugin_count: Cell<u32>,
+
+        /// Set once the object manager has been installed and the initial objects are known.
+        #[property(get)]
+        pub(crate) ready: Cell<bool>,
+
+        /// Virtual devices we created, saved so they can be recreated on startup.
+        pub(super) virtual_devices: RefCell<Vec<VirtualDevice>>,
+        /// Loopback modules live in our process, dropping them unloads the module.
+        pub(super) loopback_modules: RefCell<HashMap<String, wp::pw::ImplModule>>,
+
+        /// Filter-chain modules of the enabled equalizers, keyed by the node.name of the equalized sink.
+        pub(super) equalizer_modules: RefCell<HashMap<String, wp::pw::ImplModule>>,
+        pub(super) equalizers_restored: Cell<bool>,
+        /// Last known equalizer of each sink, so edits don't go through the config file.
+        pub(super) equalizers: RefCell<HashMap<String, Equalizer>>,
+        /// Sinks whose equalizer changed since the last save.
+        pub(super) unsaved_equalizers: RefCell<HashSet<String>>,
+        pub(super) equalizer_save: RefCell<Option<glib::SourceId>>,
+        /// What moves onto an equalizer once its node shows up, keyed by the node.name of the equalizer:
+        /// whether the default sink does, and the ids of the streams.
+        pub(super) equalizer_moves: RefCell<HashMap<String, (bool, Vec<u32>)>>,
+
+        /// Loopbacks of the sources being listened to, keyed by the node.name of the source.
+        pub(super) listen_modules: RefCell<HashMap<String, (ListenLoopback, wp::pw::ImplModule)>>,
+
+        /// Ongoing recordings keyed by bound id, kept here since list rows come and go with filtering.
+        pub(super) recorders: RefCell<HashMap<u32, Recorder>>,
+
+        /// Connection shared by the peak detection streams of all level meters, made on first use.
+        pub(super) level_monitor: OnceCell<Option<Rc<LevelMonitor>>>,
     }
 
     impl Default for PwvucontrolManager {
@@ -76,12 +150,37 @@ mod imp {
                 source_model: PwNodeFilterModel::new(NodeType::Source, Some(node_model.clone())),
                 sink_model: PwNodeFilterModel::new(NodeType::Sink, Some(node_model.clone())),
                 device_model: gio::ListStore::new::<PwDeviceObject>(),
+                hide_rules: Default::default(),
+                settings: Default::default(),
+                show_hidden: Default::default(),
                 metadata_om: Default::default(),
                 metadata: Default::default(),
+                default_sink: Default::default(),
+                default_source: Default::default(),
+                configured_default_sink: Default::default(),
+                configured_default_source: Default::default(),
+                settings_metadata_om: Default::default(),
+                clock_settings: Default::default(),
+                graph_om: Default::default(),
+                graph_node_model: gio::ListStore::new::<wp::pw::Node>(),
+                port_model: gio::ListStore::new::<PwPortObject>(),
+                link_model: gio::ListStore::new::<PwLinkObject>(),
                 default_nodes_api: Default::default(),
                 mixer_api: Default::default(),
                 application: Default::default(),
                 plugin_count: Default::default(),
+                ready: Default::default(),
+                virtual_devices: RefCell::new(VirtualDevice::load_all()),
+                loopback_modules: Default::default(),
+                equalizer_modules: Default::default(),
+                equalizers_restored: Default::default(),
+                equalizers: Default::default(),
+                unsaved_equalizers: Default::default(),
+                equalizer_save: Default::default(),
+                equalizer_moves: Default::default(),
+                listen_modules: Default::default(),
+                recorders: Default::default(),
+                level_monitor: Default::default(),
             }
         }
     }
@@ -97,8 +196,11 @@ mod imp {
         fn constructed(&self) {
             self.parent_constructed();
 
+            self.setup_hide_rules();
             self.setup_wp_connection();
             self.setup_metadata_om();
+            self.setup_settings_metadata_om();
+            self.setup_graph_om();
         }
     }
 
@@ -150,36 +252,20 @@ mod imp {
                 self,
                 move |_, object| {
                 if let Some(node) = object.downcast_ref::<wp::pw::Node>() {
-                    let mut hidden: bool = false;
-                    // Hide ourselves.
-                    if node.name().unwrap_or_default() == "PulseAudio Volume Control" {
-                        hidden = true;
-                    }
-
-                    // Hide any playback from pavucontrol (mainly volume control notification sound).
-                    if node.name().unwrap_or_default() == "pavucontrol" {
-                        hidden = true;
-                    }
-
-                    // Hide any notification sounds.
-                    // The presence of the event.id property means most likely this is an event sound.
-                    if node.pw_property::<String>("event.id").is_ok() {
-                        hidden = true;
-                    }
-                    // Or media.role being Notification.
-                    if node.pw_property::<String>("media.role").unwrap_or_default() == "Notification" {
-                        hidden = true;
-                    }
-
-                    // Hide applications that only record for peak meter.
-                    if node.pw_property::<String>("stream.monitor").is_ok() {
-                        hidden = true;
-                    }
-
                     pwvucontrol_info!("Got node: {} bound id {}", node.name().unwrap_or_default(), node.bound_id());
                     let pwobj = PwNodeObject::new(node);
-                    pwobj.set_hidden(hidden);
+                    pwobj.set_hidden(imp.is_hidden(node));
+                    // Stream titles change all the time, e.g. browser tabs and music players.
+                    let manager = imp.obj();
+                    for property in ["name", "description"] {
+                        pwobj.connect_notify_local(Some(property), clone!(#[weak] manager, move |_, _| {
+                            for model in manager.imp().filter_models() {
+                                model.refilter_search();
+                            }
+                        }));
+                    }
                     imp.node_model.append(&pwobj);
+                    imp.obj().sink_added(&pwobj);
                 } else if let Some(device) = object.downcast_ref::<wp::pw::Device>() {
                     pwvucontrol_info!("Got device: {} bound id {}", device.pw_property::<String>("device.name").unwrap_or_default(), device.bound_id());
                     imp.device_model.append(&PwDeviceObject::new(device));
@@ -195,6 +281,12 @@ mod imp {
                 if let Some(node) = object.downcast_ref::<wp::pw::Node>() {
                     pwvucontrol_info!("removed: {} id: {}", node.name().unwrap_or_default(), node.bound_id());
                     imp.obj().remove_node_by_id(node.bound_id());
+                    // Completes the file.
+                    imp.recorders.borrow_mut().remove(&node.bound_id());
+                    if let Ok(name) = node.pw_property::<String>("node.name") {
+                        imp.equalizer_modules.borrow_mut().remove(&name);
+                        imp.listen_modules.borrow_mut().remove(&name);
+                    }
                 } else if let Some(device) = object.downcast_ref::<wp::pw::Device>() {
                     imp.obj().remove_device_by_id(device.bound_id());
                 } else {
@@ -202,6 +294,14 @@ mod imp {
                 }
             }));
 
+            wp_om.connect_installed(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_| {
+                imp.ready.set(true);
+                imp.obj().notify_ready();
+            }));
+
             glib::MainContext::default().spawn_local(clone!(
                 #[weak(rename_to = manager)]
                 self,
@@ -246,6 +346,41 @@ mod imp {
             self.wp_object_manager.set(wp_om).expect("wp_object_manager should only be set once during application activation");
         }
 
+        fn setup_hide_rules(&self) {
+            let settings = gio::Settings::new(APP_ID);
+            settings.connect_changed(Some("hide-rules"), clone!(#[weak(rename_to = manager)] self, move |settings, _| {
+                manager.hide_rules.replace(hide_rules_from_settings(settings));
+                manager.update_hidden();
+            }));
+            self.hide_rules.replace(hide_rules_from_settings(&settings));
+            self.settings.set(settings).expect("settings set already");
+        }
+
+        fn is_hidden(&self, node: &wp::pw::Node) -> bool {
+            self.hide_rules.borrow().iter().any(|rule| rule.matches(|key| node.pw_property::<String>(key).ok()))
+        }
+
+        fn filter_models(&self) -> [&PwNodeFilterModel; 4] {
+            [&self.stream_output_model, &self.stream_input_model, &self.source_model, &self.sink_model]
+        }
+
+        /// Applies changed hide rules to the nodes we already have.
+        fn update_hidden(&self) {
+            for node in self.node_model.iter::<PwNodeObject>().map_while(Result::ok) {
+                node.set_hidden(self.is_hidden(&node.wpnode()));
+            }
+            for model in self.filter_models() {
+                model.refilter();
+            }
+        }
+
+        fn set_show_hidden(&self, show_hidden: bool) {
+            self.show_hidden.set(show_hidden);
+            for model in self.filter_models() {
+                model.set_show_hidden(show_hidden);
+            }
+        }
+
         fn setup_metadata_om(&self) {
             let metadata_om = ObjectManager::new();
 
@@ -267,25 +402,109 @@ mod imp {
             self.metadata_om.set(metadata_om).expect("metadata object manager set already");
         }
 
-        fn metadata_changed(&self, _subject: u32, key: Option<&str>, type_: Option<&str>, value: Option<&str>) {
-            if let (Some(key), Some(json_str), Some("Spa:String:JSON")) = (key, value, type_) {
-                // Experiment with using SpaJson parser.
-                // let json = wp::spa::SpaJson::from_string(&json_str);
-                // if let Some(name) = json.parse_object().and_then(|obj| obj.into_iter().find(|x| x.0 == "name").and_then(|x| x.1.parse_str())) {
-                //     pwvucontrol_critical!("Property {:?}", name);
-                // }
+        fn setup_settings_metadata_om(&self) {
+            let settings_metadata_om = ObjectManager::new();
 
-                if let Some(node_name) = json_str.split(r#"{"name":""#).nth(1).and_then(|x| x.split('"').next()) {
-                    match key {
-                        "default.audio.sink" => {
-                            pwvucontrol_info!("New default sink: {node_name}")
-                        }
-                        "default.audio.source" => {
-                            pwvucontrol_info!("New default source: {node_name}")
-                        }
-                        _ => {}
-                    }
+            let wp_core = self.wp_core.get().expect("wp_core to be set");
+
+            settings_metadata_om.add_interest(
+                [Constraint::compare(ConstraintType::PwGlobalProperty, "metadata.name", "settings", true)]
+                    .iter()
+                    .collect::<Interest<wp::pw::Metadata>>(),
+            );
+
+            settings_metadata_om.request_object_features(wp::pw::GlobalProxy::static_type(), wp::core::ObjectFeatures::ALL);
+
+            settings_metadata_om.connect_object_added(clone!(#[weak(rename_to = manager)] self, move |_, object| {
+                manager.clock_settings.set_metadata(object.downcast_ref::<wp::pw::Metadata>());
+            }));
+            settings_metadata_om.connect_object_removed(clone!(#[weak(rename_to = manager)] self, move |_, _| {
+                manager.clock_settings.set_metadata(None);
+            }));
+
+            wp_core.install_object_manager(&settings_metadata_om);
+            self.settings_metadata_om.set(settings_metadata_om).expect("settings metadata object manager set already");
+        }
+
+        fn setup_graph_om(&self) {
+            let graph_om = ObjectManager::new();
+
+            let wp_core = self.wp_core.get().expect("wp_core to be set");
+
+            graph_om.add_interest(Interest::<wp::pw::Node>::new());
+            graph_om.add_interest(Interest::<wp::pw::Port>::new());
+            graph_om.add_interest(Interest::<wp::pw::Link>::new());
+
+            graph_om.request_object_features(wp::pw::GlobalProxy::static_type(), wp::core::ObjectFeatures::ALL);
+
+            graph_om.connect_object_added(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, object| {
+                if let Some(node) = object.downcast_ref::<wp::pw::Node>() {
+                    imp.graph_node_model.append(node);
+                } else if let Some(port) = object.downcast_ref::<wp::pw::Port>() {
+                    imp.port_model.append(&PwPortObject::new(port));
+                } else if let Some(link) = object.downcast_ref::<wp::pw::Link>() {
+                    imp.link_model.append(&PwLinkObject::new(link));
+                } else {
+                    unreachable!("Object must be one of the above, but is {:?} instead", object.type_());
+                }
+            }));
+
+            graph_om.connect_object_removed(clone!(
+                #[weak(rename_to = imp)]
+                self,
+                move |_, object| {
+                if let Some(node) = object.downcast_ref::<wp::pw::Node>() {
+                    remove_from_model_by_id(&imp.graph_node_model, node.bound_id(), |x: &wp::pw::Node| x.bound_id());
+                } else if let Some(port) = object.downcast_ref::<wp::pw::Port>() {
+                    remove_from_model_by_id(&imp.port_model, port.bound_id(), PwPortObject::boundid);
+                } else if let Some(link) = object.downcast_ref::<wp::pw::Link>() {
+                    remove_from_model_by_id(&imp.link_model, link.bound_id(), PwLinkObject::boundid);
+                } else {
+                    pwvucontrol_info!("Object must be one of the above, but is {:?} instead", object.type_());
+                }
+            }));
+
+            wp_core.install_object_manager(&graph_om);
+            self.graph_om.set(graph_om).expect("graph object manager set already");
+        }
+
+        fn metadata_changed(&self, subject: u32, key: Option<&str>, type_: Option<&str>, value: Option<&str>) {
+            if subject != 0 {
+                return;
+            }
+
+            // A missing key means all metadata for the subject was cleared.
+            let Some(key) = key else {
+                for key in DEFAULT_NODE_KEYS {
+                    self.set_default_node(key, None);
                 }
+                return;
+            };
+
+            let node_name = match (type_, value) {
+                (Some("Spa:String:JSON"), Some(json)) => spajson::default_node_name(json),
+                _ => None,
+            };
+            self.set_default_node(key, node_name);
+        }
+
+        fn set_default_node(&self, key: &str, node_name: Option<String>) {
+            let obj = self.obj();
+            let (cell, notify): (&RefCell<Option<String>>, fn(&super::PwvucontrolManager)) = match key {
+                "default.audio.sink" => (&self.default_sink, super::PwvucontrolManager::notify_default_sink),
+                "default.audio.source" => (&self.default_source, super::PwvucontrolManager::notify_default_source),
+                "default.configured.audio.sink" => (&self.configured_default_sink, super::PwvucontrolManager::notify_configured_default_sink),
+                "default.configured.audio.source" => (&self.configured_default_source, super::PwvucontrolManager::notify_configured_default_source),
+                _ => return,
+            };
+
+            if *cell.borrow() != node_name {
+                pwvucontrol_info!("{key} is now {node_name:?}");
+                cell.replace(node_name);
+                notify(&obj);
             }
         }
 
@@ -299,6 +518,11 @@ mod imp {
                 // }
 
                 metadataobj.connect_changed(clone!(#[weak(rename_to = manager)] self, move |_,s,k,t,v| manager.metadata_changed(s, k, t, v)));
+
+                for key in DEFAULT_NODE_KEYS {
+                    let value = metadataobj.find_notype(0, key);
+                    self.metadata_changed(0, Some(key), Some("Spa:String:JSON"), value.as_deref());
+                }
             } else {
                 unreachable!("Object must be one of the above, but is {:?} instead", object.type_());
             }
@@ -306,6 +530,16 @@ mod imp {
     }
 }
 
+fn hide_rules_from_settings(settings: &gio::Settings) -> Vec<HideRule> {
+    HideRule::parse_all(settings.strv("hide-rules").iter().map(|x| x.as_str()))
+}
+
+fn remove_from_model_by_id<T: IsA<glib::Object>>(model: &gio::ListStore, id: u32, bound_id: impl Fn(&T) -> u32) {
+    if let Some(pos) = model.iter::<T>().position(|item| item.is_ok_and(|item| bound_id(&item) == id)) {
+        model.remove(pos as u32);
+    }
+}
+
 glib::wrapper! {
     pub struct PwvucontrolManager(ObjectSubclass<imp::PwvucontrolManager>);
 }
@@ -383,16 +617,438 @@ impl PwvucontrolManager {
         }
     }
 
+    /// Links an output port to an input port. The link lingers so it outlives our connection.
+    pub fn create_link(&self, output: &PwPortObject, input: &PwPortObject) {
+        let core = self.wp_core();
+
+        let props = wp::pw::Properties::new_empty();
+        props.set("link.output.node", Some(&output.node_id().to_string()));
+        props.set("link.output.port", Some(&output.boundid().to_string()));
+        props.set("link.input.node", Some(&input.node_id().to_string()));
+        props.set("link.input.port", Some(&input.boundid().to_string()));
+        props.set("object.linger", Some("true"));
+
+        let Some(link) = wp::pw::Link::from_factory(&core, "link-factory", Some(props)) else {
+            pwvucontrol_warning!("Cannot create link from port {} to port {}", output.boundid(), input.boundid());
+            return;
+        };
+
+        glib::MainContext::default().spawn_local(async move {
+            if let Err(e) = link.activate_future(wp::core::ObjectFeatures::ALL).await {
+                pwvucontrol_warning!("Cannot activate link: {e}");
+            }
+        });
+    }
+
+    pub fn get_graph_node_by_id(&self, id: u32) -> Option<wp::pw::Node> {
+        self.imp().graph_node_model.iter::<wp::pw::Node>().map_while(Result::ok).find(|node| node.bound_id() == id)
+    }
+
+    /// Makes the node the configured default for its media class, or clears the configured default.
+    pub fn set_default_configured_node(&self, node: &PwNodeObject, is_default: bool) -> bool {
+        let node_name: String = if is_default { node.node_property("node.name").unwrap_or_default() } else { "".to_string() };
+
+        let type_name = match node.nodetype() {
+            NodeType::Sink => "Audio/Sink",
+            NodeType::Source => match node.is_virtual() {
+                true => "Audio/Source/Virtual",
+                false => "Audio/Source",
+            },
+            _ => {
+                pwvucontrol_warning!("Only sinks and sources can be set as default");
+                return false;
+            }
+        };
+
+        let result: bool = self.default_nodes_api().emit_by_name("set-default-configured-node-name", &[&type_name, &node_name]);
+        pwvucontrol_info!("set-default-configured-node-name result: {result:?}");
+        result
+    }
+
+    pub fn virtual_devices(&self) -> Vec<VirtualDevice> {
+        self.imp().virtual_devices.borrow().clone()
+    }
+
+    pub fn add_virtual_device(&self, device: VirtualDevice) {
+        self.create_virtual_device(&device);
+
+        let imp = self.imp();
+        imp.virtual_devices.borrow_mut().push(device);
+        VirtualDevice::save_all(&imp.virtual_devices.borrow());
+    }
+
+    pub fn remove_virtual_device(&self, name: &str) {
+        let imp = self.imp();
+
+        if imp.loopback_modules.borrow_mut().remove(name).is_none() {
+            for node in self.node_model().iter::<PwNodeObject>().map_while(Result::ok) {
+                if node.node_property::<String>("node.name").as_deref() == Some(name) {
+                    node.wpnode().request_destroy();
+                }
+            }
+        }
+
+        imp.virtual_devices.borrow_mut().retain(|x| x.name != name);
+        VirtualDevice::save_all(&imp.virtual_devices.borrow());
+    }
+
+    /// Recreates saved virtual devices that are missing, e.g. after the PipeWire server restarted.
+    pub fn restore_virtual_devices(&self) {
+        self.when_ready(|manager| {
+            let existing: Vec<String> =
+                manager.node_model().iter::<PwNodeObject>().map_while(Result::ok).filter_map(|x| x.node_property("node.name")).collect();
+            for device in manager.virtual_devices() {
+                if device.kind == VirtualDeviceKind::Loopback || !existing.contains(&device.name) {
+                    manager.create_virtual_device(&device);
+                }
+            }
+        });
+    }
+
+    /// Loads the enabled equalizers, now and whenever their sink shows up later on.
+    pub fn restore_equalizers(&self) {
+        self.when_ready(|manager| {
+            manager.imp().equalizers_restored.set(true);
+            for node in manager.sink_model().iter::<PwNodeObject>().map_while(Result::ok) {
+                manager.sink_added(&node);
+            }
+        });
+    }
+
+    fn when_ready(&self, f: impl Fn(&Self) + 'static) {
+        if self.ready() {
+            f(self);
+            return;
+        }
+
+        let handler: Rc<RefCell<Option<glib::SignalHandlerId>>> = Default::default();
+        handler.replace(Some(self.connect_ready_notify(clone!(
+            #[strong] handler,
+            move |manager| {
+                if let Some(handler) = handler.take() {
+                    manager.disconnect(handler);
+                }
+                f(manager);
+            }
+        ))));
+    }
+
+    fn create_virtual_device(&self, device: &VirtualDevice) {
+        let core = self.wp_core();
+
+        if device.kind == VirtualDeviceKind::Loopback {
+            if self.imp().loopback_modules.borrow().contains_key(&device.name) {
+                return;
+            }
+            match wp::pw::ImplModule::load(&core, "libpipewire-module-loopback", Some(&device.loopback_arguments()), None) {
+                Some(module) => {
+                    self.imp().loopback_modules.borrow_mut().insert(device.name.clone(), module);
+                }
+                None => pwvucontrol_warning!("Cannot load loopback module for {}", device.name),
+            }
+            return;
+        }
+
+        let Some(node) = wp::pw::Node::from_factory(&core, "adapter", Some(device.node_properties())) else {
+            pwvucontrol_warning!("Cannot create virtual device {}", device.name);
+            return;
+        };
+
+        glib::MainContext::default().spawn_local(async move {
+            if let Err(e) = node.activate_future(wp::core::ObjectFeatures::ALL).await {
+                pwvucontrol_warning!("Cannot activate virtual device: {e}");
+            }
+        });
+    }
+
+    pub fn equalizer(&self, sink: &PwNodeObject) -> Equalizer {
+        self.equalizer_by_name(&sink.node_property::<String>("node.name").unwrap_or_default())
+    }
+
+    fn equalizer_by_name(&self, sink_name: &str) -> Equalizer {
+        self.imp().equalizers.borrow_mut().entry(sink_name.to_string()).or_insert_with(|| Equalizer::load(sink_name)).clone()
+    }
+
+    /// Applies the equalizer, live if the band types are unchanged, and saves it shortly after.
+    pub fn set_equalizer(&self, sink: &PwNodeObject, equalizer: &Equalizer) {
+        let Some(sink_name) = sink.node_property::<String>("node.name") else {
+            pwvucontrol_warning!("{} has no node.name, cannot equalize it", sink.name());
+            return;
+        };
+        let previous = self.equalizer_by_name(&sink_name);
+        self.imp().equalizers.borrow_mut().insert(sink_name.clone(), equalizer.clone());
+        self.imp().unsaved_equalizers.borrow_mut().insert(sink_name.clone());
+        self.schedule_equalizer_save();
+
+        let node_name = Equalizer::node_name(&sink_name);
+        let eq_node = self.get_node_by_name(&node_name);
+
+        if !equalizer.enabled {
+            // Move everything back before the equalizer goes away.
+            self.imp().equalizer_moves.borrow_mut().remove(&node_name);
+            if let Some(eq_node) = eq_node {
+                if self.configured_default_sink().as_ref() == Some(&node_name) {
+                    self.set_default_configured_node(sink, true);
+                }
+                for stream in self.streams_targeting(&eq_node) {
+                    stream.set_default_target(sink);
+                }
+            }
+            self.imp().equalizer_modules.borrow_mut().remove(&sink_name);
+            return;
+        }
+
+        let loaded = self.imp().equalizer_modules.borrow().contains_key(&sink_name);
+        if !loaded || previous.band_types() != equalizer.band_types() {
+            // The equalizer node is new either way, so what played into the sink, or into the
+            // previous equalizer, is moved once it shows up. The default follows the node name.
+            let (moves_default, streams) = match (loaded, &eq_node) {
+                (false, _) => (self.configured_default_sink().as_ref() == Some(&sink_name), self.streams_targeting(sink)),
+                (true, Some(eq_node)) => (false, self.streams_targeting(eq_node)),
+                (true, None) => (false, Vec::new()),
+            };
+            let streams = streams.iter().map(PwNodeObject::boundid).collect();
+            self.imp().equalizer_moves.borrow_mut().insert(node_name, (moves_default, streams));
+            self.load_equalizer(sink, equalizer);
+            return;
+        }
+
+        match (eq_node, equalizer.props_pod()) {
+            (Some(eq_node), Some(pod)) => eq_node.wpnode().set_param("Props", 0, pod),
+            _ => pwvucontrol_warning!("Cannot update equalizer {node_name}"),
+        }
+    }
+
+    fn schedule_equalizer_save(&self) {
+        if let Some(source) = self.imp().equalizer_save.take() {
+            source.remove();
+        }
+        let source = glib::timeout_add_local_once(EQUALIZER_SAVE_DELAY, clone!(#[weak(rename_to = manager)] self, move || {
+            manager.imp().equalizer_save.take();
+            manager.save_equalizers();
+        }));
+        self.imp().equalizer_save.replace(Some(source));
+    }
+
+    /// Writes the equalizers changed since the last save, called on exit so pending changes aren't lost.
+    pub fn save_equalizers(&self) {
+        if let Some(source) = self.imp().equalizer_save.take() {
+            source.remove();
+        }
+        let equalizers = self.imp().equalizers.borrow();
+        for sink_name in self.imp().unsaved_equalizers.take() {
+            if let Some(equalizer) = equalizers.get(&sink_name) {
+                equalizer.save(&sink_name);
+            }
+        }
+    }
+
+    fn load_equalizer(&self, sink: &PwNodeObject, equalizer: &Equalizer) {
+        let sink_name = sink.node_property::<String>("node.name").unwrap_or_default();
+        let mut modules = self.imp().equalizer_modules.borrow_mut();
+
+        // Unload first so the new filter-chain can reuse the node names.
+        modules.remove(&sink_name);
+
+        let arguments = equalizer.filter_chain_arguments(&sink_name, &sink.name());
+        match wp::pw::ImplModule::load(&self.wp_core(), "libpipewire-module-filter-chain", Some(&arguments), None) {
+            Some(module) => {
+                modules.insert(sink_name, module);
+            }
+            None => pwvucontrol_warning!("Cannot load equalizer for {sink_name}"),
+        }
+    }
+
+    /// The loopback playing `source`, `None` when not listening to it.
+    pub fn listen_loopback(&self, source: &PwNodeObject) -> Option<ListenLoopback> {
+        let source_name = source.node_property::<String>("node.name")?;
+        self.imp().listen_modules.borrow().get(&source_name).map(|(loopback, _)| loopback.clone())
+    }
+
+    /// Starts playing `source` as described by `loopback`, or stops when `None`.
+    pub fn set_listen_loopback(&self, source: &PwNodeObject, loopback: Option<&ListenLoopback>) -> Result<(), anyhow::Error> {
+        let source_name = source.node_property::<String>("node.name").ok_or_else(|| anyhow::anyhow!("{} has no node.name", source.name()))?;
+        let mut modules = self.imp().listen_modules.borrow_mut();
+
+        // Unload first so the new loopback can reuse the node names.
+        modules.remove(&source_name);
+        let Some(loopback) = loopback else {
+            return Ok(());
+        };
+
+        let arguments = loopback.loopback_arguments(&source.name());
+        let module = wp::pw::ImplModule::load(&self.wp_core(), "libpipewire-module-loopback", Some(&arguments), None)
+            .ok_or_else(|| anyhow::anyhow!("Cannot load loopback to listen to {source_name}"))?;
+        modules.insert(source_name, (loopback.clone(), module));
+        Ok(())
+    }
+
+    /// Unloads all listen loopbacks, called on exit.
+    pub fn stop_listening(&self) {
+        self.imp().listen_modules.borrow_mut().clear();
+    }
+
+    /// Starts recording `node` to `path`, see [`Recorder::start`].
+    pub fn start_recording(&self, node: &PwNodeObject, path: &std::path::Path) -> Result<(), anyhow::Error> {
+        let recorder = Recorder::start(node, path)?;
+        self.imp().recorders.borrow_mut().insert(node.boundid(), recorder);
+        Ok(())
+    }
+
+    /// Progress of the recording of `node`, `None` when not recording it.
+    pub fn recording_status(&self, node: &PwNodeObject) -> Option<RecordingStatus> {
+        self.imp().recorders.borrow().get(&node.boundid()).map(Recorder::status)
+    }
+
+    /// Hands out the recording of `node` so the caller can complete it and report the result.
+    pub fn stop_recording(&self, node: &PwNodeObject) -> Option<Recorder> {
+        self.imp().recorders.borrow_mut().remove(&node.boundid())
+    }
+
+    /// Completes all recordings, called on exit.
+    pub fn stop_recordings(&self) {
+        self.imp().recorders.borrow_mut().clear();
+    }
+
+    fn sink_added(&self, node: &PwNodeObject) {
+        if !self.imp().equalizers_restored.get() || node.nodetype() != NodeType::Sink {
+            return;
+        }
+        let Some(sink_name) = node.node_property::<String>("node.name") else {
+            return;
+        };
+        if Equalizer::is_equalizer_node(&sink_name) {
+            self.equalizer_added(node, &sink_name);
+            return;
+        }
+        if self.imp().equalizer_modules.borrow().contains_key(&sink_name) {
+            return;
+        }
+
+        let equalizer = self.equalizer_by_name(&sink_name);
+        if equalizer.enabled {
+            self.load_equalizer(node, &equalizer);
+        }
+    }
+
+    /// Moves the default sink and the streams onto a just enabled equalizer.
+    fn equalizer_added(&self, eq_node: &PwNodeObject, node_name: &str) {
+        let Some((moves_default, streams)) = self.imp().equalizer_moves.borrow_mut().remove(node_name) else {
+            return;
+        };
+        if moves_default {
+            self.set_default_configured_node(eq_node, true);
+        }
+        for stream in streams.into_iter().filter_map(|id| self.get_node_by_id(id)) {
+            stream.set_default_target(eq_node);
+        }
+    }
+
+    /// Playback streams that were moved to `sink`, rather than following the default.
+    /// Leaves out the equalizers' own output, which must keep playing into its sink.
+    fn streams_targeting(&self, sink: &PwNodeObject) -> Vec<PwNodeObject> {
+        self.node_model()
+            .iter::<PwNodeObject>()
+            .map_while(Result::ok)
+            .filter(|node| node.nodetype() == NodeType::StreamOutput && node.default_target().as_ref() == Some(sink))
+            .filter(|node| !node.node_property::<String>("node.name").is_some_and(|name| Equalizer::is_equalizer_node(&name)))
+            .collect()
+    }
+
+    pub fn get_node_by_name(&self, name: &str) -> Option<PwNodeObject> {
+        self.node_model().iter::<PwNodeObject>().map_while(Result::ok).find(|node| node.node_property::<String>("node.name").as_deref() == Some(name))
+    }
+
     pub fn default_configured_sink_node(&self) -> Option<PwNodeObject> {
-        let api = self.imp().default_nodes_api.get().expect("default_nodes_api");
-        let id = api.emit_by_name("get-default-node", &[&"Audio/Sink"]);
-        self.get_node_by_id(id)
+        self.default_sink().and_then(|name| self.get_node_by_name(&name))
     }
 
     pub fn default_configured_source_node(&self) -> Option<PwNodeObject> {
-        let api = self.imp().default_nodes_api.get().expect("default_nodes_api");
-        let id = api.emit_by_name("get-default-node", &[&"Audio/Source"]);
-        self.get_node_by_id(id)
+        self.default_source().and_then(|name| self.get_node_by_name(&name))
+    }
+
+    fn level_monitor(&self) -> Option<&Rc<LevelMonitor>> {
+        self.imp()
+            .level_monitor
+            .get_or_init(|| match LevelMonitor::new() {
+                Ok(monitor) => Some(monitor),
+                Err(e) => {
+                    pwvucontrol_warning!("Unable to connect to PipeWire for level meters: {e}");
+                    None
+                }
+            })
+            .as_ref()
+    }
+
+    /// Meters node `id`, see [`LevelMonitor::add`]. Levels arrive through [`Self::dispatch_levels`].
+    pub fn add_level_meter(&self, id: u32, positions: &[u32], callback: impl Fn(&[f32]) + 'static) -> Option<LevelMeter> {
+        match self.level_monitor()?.add(id, positions, callback) {
+            Ok(meter) => Some(meter),
+            Err(e) => {
+                pwvucontrol_warning!("Unable to meter node {id}: {e}");
+                None
+            }
+        }
+    }
+
+    /// Analyses the loudness of node `id`, see [`LevelMonitor::add_loudness`].
+    pub fn add_loudness_meter(&self, id: u32, positions: &[u32], callback: impl Fn(&Loudness) + 'static) -> Option<LevelMeter> {
+        match self.level_monitor()?.add_loudness(id, positions, callback) {
+            Ok(meter) => Some(meter),
+            Err(e) => {
+                pwvucontrol_warning!("Unable to analyse loudness of node {id}: {e}");
+                None
+            }
+        }
+    }
+
+    /// Analyses the spectrum of node `id`, see [`LevelMonitor::add_spectrum`].
+    pub fn add_spectrum_meter(&self, id: u32, callback: impl Fn(&[f32]) + 'static) -> Option<LevelMeter> {
+        match self.level_monitor()?.add_spectrum(id, callback) {
+            Ok(meter) => Some(meter),
+            Err(e) => {
+                pwvucontrol_warning!("Unable to analyse spectrum of node {id}: {e}");
+                None
+            }
+        }
+    }
+
+    /// Captures node `id`, see [`LevelMonitor::add_capture`].
+    pub fn add_capture(&self, id: u32, positions: &[u32], rate: u32, process: impl FnMut(&[f32]) + 'static) -> Result<LevelMeter, anyhow::Error> {
+        let monitor = self.level_monitor().ok_or_else(|| anyhow::anyhow!("Not connected to PipeWire"))?;
+        monitor.add_capture(id, positions, rate, process)
+    }
+
+    /// Plays to node `id`, see [`LevelMonitor::add_playback`].
+    pub fn add_playback(
+        &self,
+        id: u32,
+        positions: &[u32],
+        rate: u32,
+        media_name: &str,
+        event_id: Option<&str>,
+        generate: impl FnMut(&mut [f32]) + 'static,
+    ) -> Result<LevelMeter, anyhow::Error> {
+        let monitor = self.level_monitor().ok_or_else(|| anyhow::anyhow!("Not connected to PipeWire"))?;
+        monitor.add_playback(id, positions, rate, media_name, event_id, generate)
+    }
+
+    /// Profiles the graph, see [`LevelMonitor::add_profiler`].
+    pub fn add_profiler(&self, callback: impl Fn(&[ProfilerSample]) + 'static) -> Option<Profiler> {
+        match self.level_monitor()?.add_profiler(callback) {
+            Ok(profiler) => Some(profiler),
+            Err(e) => {
+                pwvucontrol_warning!("Unable to profile the graph: {e}");
+                None
+            }
+        }
+    }
+
+    /// Hands the levels gathered since the last call to all meters, called once per frame.
+    pub fn dispatch_levels(&self) {
+        if let Some(Some(monitor)) = self.imp().level_monitor.get() {
+            monitor.dispatch();
+        }
     }
 }
 
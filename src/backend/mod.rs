@@ -1,21 +1,55 @@
+mod audiofile;
+mod clocksettings;
+mod equalizer;
+mod feedbacksound;
+mod hiderules;
+mod levelmonitor;
+mod listenloopback;
+mod loudness;
 mod manager;
 mod paramavailability;
+mod profiler;
 mod pwchannelobject;
 mod pwdeviceobject;
+mod pwlinkobject;
 mod pwnodefiltermodel;
 mod pwnodeobject;
+mod pwportobject;
 mod pwprofileobject;
 mod pwroutefiltermodel;
 mod pwrouteobject;
+mod recorder;
 mod routedirection;
+mod scene;
+mod spajson;
+mod spapod;
+mod speakertest;
+mod spectrum;
+mod virtualdevice;
 
+pub use audiofile::{AudioFileFormat, AudioFileWriter};
+pub use clocksettings::PwClockSettings;
+pub use equalizer::{EqBand, EqBandType, Equalizer};
+pub use feedbacksound::FeedbackSound;
+pub use hiderules::HideRule;
+pub use levelmonitor::{LevelMeter, LevelMonitor};
+pub use listenloopback::ListenLoopback;
+pub use loudness::{Loudness, LoudnessMeter, LOUDNESS_RATE};
 pub use manager::PwvucontrolManager;
 pub use paramavailability::ParamAvailability;
+pub use profiler::{NodeTiming, Profiler, ProfilerBlock, ProfilerSample, ProfilerStats};
 pub use pwchannelobject::PwChannelObject;
 pub use pwdeviceobject::PwDeviceObject;
+pub use pwlinkobject::PwLinkObject;
 pub use pwnodefiltermodel::PwNodeFilterModel;
 pub use pwnodeobject::{NodeType, PwNodeObject};
+pub use pwportobject::{PortDirection, PwPortObject};
 pub use pwprofileobject::PwProfileObject;
 pub use pwroutefiltermodel::PwRouteFilterModel;
 pub use pwrouteobject::PwRouteObject;
+pub use recorder::{Recorder, RecordingStatus};
 pub use routedirection::RouteDirection;
+pub use scene::Scene;
+pub use speakertest::{SignalGenerator, TestSignal};
+pub use spectrum::{bin_frequency, SpectrumAnalyzer, SPECTRUM_RATE, SPECTRUM_SIZE};
+pub use virtualdevice::{VirtualDevice, VirtualDeviceKind};
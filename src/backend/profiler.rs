@@ -0,0 +1,498 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::macros::*;
+use pipewire::{
+    core::CoreRc,
+    proxy::{Proxy, ProxyT},
+    registry::{Listener, RegistryRc},
+    spa::{
+        self,
+        pod::{deserialize::PodDeserializer, Value},
+    },
+    types::ObjectType,
+};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    ffi::c_void,
+    pin::Pin,
+    rc::Rc,
+};
+
+/// Status of a node that completed its cycle, anything else at the time of the report is an xrun.
+const STATUS_FINISHED: i32 = 3;
+
+/// Timing of one node in one graph cycle, times are in nanoseconds on the monotonic clock.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProfilerBlock {
+    pub id: i32,
+    pub name: String,
+    pub prev_signal: i64,
+    pub signal: i64,
+    pub awake: i64,
+    pub finish: i64,
+    pub status: i32,
+    /// Quantum and rate the node asked for.
+    pub latency: (u32, u32),
+    /// Xruns counted by PipeWire, only reported by newer versions.
+    pub xrun_count: Option<i32>,
+}
+
+impl ProfilerBlock {
+    /// Time from being signalled until starting to process.
+    pub fn wait(&self) -> i64 {
+        (self.awake - self.signal).max(0)
+    }
+
+    /// Time spent processing.
+    pub fn busy(&self) -> i64 {
+        (self.finish - self.awake).max(0)
+    }
+}
+
+/// One graph cycle of a driver as reported by libpipewire-module-profiler.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProfilerSample {
+    /// DSP load of the driver's data loop averaged over a short, medium and long time.
+    pub cpu_load: [f32; 3],
+    pub xrun_count: i32,
+    /// Samples processed in this cycle.
+    pub quantum: i64,
+    pub rate: u32,
+    pub driver: ProfilerBlock,
+    pub followers: Vec<ProfilerBlock>,
+}
+
+impl ProfilerSample {
+    /// Parses the pod of a profile event, which holds one object per reported cycle.
+    pub fn parse(value: &Value) -> Vec<ProfilerSample> {
+        match value {
+            Value::Struct(values) => values.iter().filter_map(Self::parse_object).collect(),
+            value => Self::parse_object(value).into_iter().collect(),
+        }
+    }
+
+    fn parse_object(value: &Value) -> Option<ProfilerSample> {
+        let Value::Object(object) = value else {
+            return None;
+        };
+
+        let mut sample = ProfilerSample::default();
+        let mut has_driver = false;
+        for property in &object.properties {
+            let Value::Struct(fields) = &property.value else {
+                continue;
+            };
+            match property.key {
+                spa::sys::SPA_PROFILER_info => {
+                    for (i, load) in sample.cpu_load.iter_mut().enumerate() {
+                        *load = float(fields, i + 1).unwrap_or_default();
+                    }
+                    sample.xrun_count = int(fields, 4).unwrap_or_default();
+                }
+                spa::sys::SPA_PROFILER_clock => {
+                    sample.rate = fraction(fields, 4).map(|x| x.1).unwrap_or_default();
+                    sample.quantum = long(fields, 6).unwrap_or_default();
+                }
+                spa::sys::SPA_PROFILER_driverBlock => {
+                    sample.driver = parse_block(fields)?;
+                    has_driver = true;
+                }
+                spa::sys::SPA_PROFILER_followerBlock => sample.followers.extend(parse_block(fields)),
+                _ => {}
+            }
+        }
+        has_driver.then_some(sample)
+    }
+
+    /// Length of the cycle in nanoseconds.
+    pub fn period(&self) -> i64 {
+        if self.rate == 0 {
+            return 0;
+        }
+        self.quantum * 1_000_000_000 / self.rate as i64
+    }
+}
+
+fn parse_block(fields: &[Value]) -> Option<ProfilerBlock> {
+    Some(ProfilerBlock {
+        id: int(fields, 0)?,
+        name: match fields.get(1)? {
+            Value::String(name) => name.clone(),
+            _ => return None,
+        },
+        prev_signal: long(fields, 2)?,
+        signal: long(fields, 3)?,
+        awake: long(fields, 4)?,
+        finish: long(fields, 5)?,
+        status: int(fields, 6)?,
+        latency: fraction(fields, 7).unwrap_or_default(),
+        xrun_count: int(fields, 8),
+    })
+}
+
+fn int(fields: &[Value], index: usize) -> Option<i32> {
+    match fields.get(index)? {
+        Value::Int(x) => Some(*x),
+        _ => None,
+    }
+}
+
+fn long(fields: &[Value], index: usize) -> Option<i64> {
+    match fields.get(index)? {
+        Value::Long(x) => Some(*x),
+        _ => None,
+    }
+}
+
+fn float(fields: &[Value], index: usize) -> Option<f32> {
+    match fields.get(index)? {
+        Value::Float(x) => Some(*x),
+        Value::Double(x) => Some(*x as f32),
+        _ => None,
+    }
+}
+
+fn fraction(fields: &[Value], index: usize) -> Option<(u32, u32)> {
+    match fields.get(index)? {
+        Value::Fraction(x) => Some((x.num, x.denom)),
+        _ => None,
+    }
+}
+
+/// Latest timing of a node, as shown in one row of the performance table.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeTiming {
+    pub id: i32,
+    pub name: String,
+    /// Id of the driver the node follows, its own id for drivers.
+    pub driver: i32,
+    pub quantum: i64,
+    pub rate: u32,
+    pub wait: i64,
+    pub busy: i64,
+    /// Fraction of the cycle the node takes, for drivers the DSP load of the whole graph.
+    pub load: f64,
+    /// Xruns since the counts were last reset.
+    pub xruns: u64,
+    /// PipeWire's own cumulative xrun count, when it reports one.
+    pub reported_xruns: Option<u64>,
+}
+
+impl NodeTiming {
+    pub fn is_driver(&self) -> bool {
+        self.id == self.driver
+    }
+}
+
+/// Collects profiler samples into the latest timing of every node, counting xruns on the way.
+#[derive(Debug, Default)]
+pub struct ProfilerStats {
+    nodes: BTreeMap<i32, NodeTiming>,
+    /// Nodes reported since the last [`ProfilerStats::expire`].
+    seen: BTreeSet<i32>,
+    /// PipeWire's xrun count of each node at the last [`ProfilerStats::reset_xruns`].
+    xrun_baselines: BTreeMap<i32, u64>,
+}
+
+impl ProfilerStats {
+    pub fn update(&mut self, samples: &[ProfilerSample]) {
+        for sample in samples {
+            let driver = sample.driver.id;
+            let period = sample.period();
+            self.seen.insert(driver);
+            self.seen.extend(sample.followers.iter().map(|x| x.id));
+
+            let timing = self.nodes.entry(driver).or_default();
+            let reported = reported_xruns(&sample.driver, sample.xrun_count);
+            let xruns = count_xruns(timing, &sample.driver, reported, self.xrun_baselines.get(&driver).copied().unwrap_or_default());
+            *timing = NodeTiming {
+                id: driver,
+                name: sample.driver.name.clone(),
+                driver,
+                quantum: sample.quantum,
+                rate: sample.rate,
+                wait: sample.driver.wait(),
+                busy: sample.driver.busy(),
+                load: sample.cpu_load[0] as f64,
+                xruns,
+                reported_xruns: reported,
+            };
+
+            for follower in &sample.followers {
+                let timing = self.nodes.entry(follower.id).or_default();
+                let reported = reported_xruns(follower, follower.xrun_count.unwrap_or_default());
+                let xruns = count_xruns(timing, follower, reported, self.xrun_baselines.get(&follower.id).copied().unwrap_or_default());
+                let (quantum, rate) = if follower.latency.1 > 0 { (follower.latency.0 as i64, follower.latency.1) } else { (sample.quantum, sample.rate) };
+                *timing = NodeTiming {
+                    id: follower.id,
+                    name: follower.name.clone(),
+                    driver,
+                    quantum,
+                    rate,
+                    wait: follower.wait(),
+                    busy: follower.busy(),
+                    load: if period > 0 { follower.busy() as f64 / period as f64 } else { 0.0 },
+                    xruns,
+                    reported_xruns: reported,
+                };
+            }
+        }
+    }
+
+    /// Drivers, each followed by its followers.
+    pub fn grouped(&self) -> Vec<NodeTiming> {
+        let mut rows = Vec::with_capacity(self.nodes.len());
+        for driver in self.nodes.values().filter(|x| x.is_driver()) {
+            rows.push(driver.clone());
+            rows.extend(self.nodes.values().filter(|x| x.driver == driver.id && !x.is_driver()).cloned());
+        }
+        rows
+    }
+
+    /// Forgets nodes that were not reported since the last call, they left the graph or stopped running.
+    pub fn expire(&mut self) {
+        let seen = std::mem::take(&mut self.seen);
+        self.nodes.retain(|id, _| seen.contains(id));
+    }
+
+    /// Counts xruns from zero again. PipeWire's counts can't be reset, so they are remembered and subtracted.
+    pub fn reset_xruns(&mut self) {
+        for timing in self.nodes.values_mut() {
+            if let Some(reported) = timing.reported_xruns {
+                self.xrun_baselines.insert(timing.id, reported);
+            }
+            timing.xruns = 0;
+        }
+    }
+}
+
+fn reported_xruns(block: &ProfilerBlock, reported: i32) -> Option<u64> {
+    (reported > 0 || block.xrun_count.is_some()).then_some(reported.max(0) as u64)
+}
+
+/// Uses PipeWire's xrun count when reported, otherwise counts cycles the node did not finish.
+fn count_xruns(previous: &NodeTiming, block: &ProfilerBlock, reported: Option<u64>, baseline: u64) -> u64 {
+    match reported {
+        Some(reported) => previous.xruns.max(reported.saturating_sub(baseline)),
+        None => previous.xruns + u64::from(block.status != STATUS_FINISHED),
+    }
+}
+
+/// Events of PipeWire:Interface:Profiler, which pipewire-rs has no binding for.
+#[repr(C)]
+struct ProfilerEvents {
+    version: u32,
+    profile: Option<unsafe extern "C" fn(data: *mut c_void, pod: *const spa::sys::spa_pod)>,
+}
+
+static PROFILER_EVENTS: ProfilerEvents = ProfilerEvents {
+    version: 0,
+    profile: Some(on_profile),
+};
+
+type ProfileCallback = Box<dyn Fn(&[ProfilerSample])>;
+
+unsafe extern "C" fn on_profile(data: *mut c_void, pod: *const spa::sys::spa_pod) {
+    let callback = &*(data as *const ProfileCallback);
+    let pod = spa::pod::Pod::from_raw(pod);
+    match PodDeserializer::deserialize_any_from(pod.as_bytes()) {
+        Ok((_, value)) => callback(&ProfilerSample::parse(&value)),
+        Err(_) => pwvucontrol_warning!("Unable to parse profiler data"),
+    }
+}
+
+struct ProfilerProxy {
+    proxy: Proxy,
+}
+
+impl ProxyT for ProfilerProxy {
+    fn type_() -> ObjectType {
+        ObjectType::Profiler
+    }
+
+    fn upcast(self) -> Proxy {
+        self.proxy
+    }
+
+    fn upcast_ref(&self) -> &Proxy {
+        &self.proxy
+    }
+
+    unsafe fn from_proxy_unchecked(proxy: Proxy) -> Self {
+        Self { proxy }
+    }
+}
+
+struct BoundProfiler {
+    _proxy: ProfilerProxy,
+    hook: Pin<Box<spa::sys::spa_hook>>,
+}
+
+impl Drop for BoundProfiler {
+    fn drop(&mut self) {
+        spa::utils::hook::remove(*self.hook);
+    }
+}
+
+/// Receives the timing of every graph cycle from the profiler objects of libpipewire-module-profiler,
+/// which the PipeWire daemon loads by default. Dropping it stops profiling.
+pub struct Profiler {
+    _registry: RegistryRc,
+    _listener: Listener,
+    bound: Rc<RefCell<Vec<BoundProfiler>>>,
+    _callback: Rc<ProfileCallback>,
+}
+
+impl Profiler {
+    pub(crate) fn new(core: &CoreRc, callback: impl Fn(&[ProfilerSample]) + 'static) -> Result<Self, anyhow::Error> {
+        let callback: Rc<ProfileCallback> = Rc::new(Box::new(callback));
+        let bound: Rc<RefCell<Vec<BoundProfiler>>> = Default::default();
+        let registry = core.get_registry_rc()?;
+
+        let listener = registry
+            .add_listener_local()
+            .global({
+                let registry = registry.downgrade();
+                let bound = bound.clone();
+                let callback = callback.clone();
+                move |global| {
+                    if global.type_ != ObjectType::Profiler {
+                        return;
+                    }
+                    let Some(registry) = registry.upgrade() else {
+                        return;
+                    };
+                    let proxy = match registry.bind::<ProfilerProxy, _>(global) {
+                        Ok(proxy) => proxy,
+                        Err(e) => {
+                            pwvucontrol_warning!("Unable to bind profiler {}: {e}", global.id);
+                            return;
+                        }
+                    };
+
+                    // SAFETY: The hook is pinned and removed before the proxy and the callback go away.
+                    let mut hook: Pin<Box<spa::sys::spa_hook>> = Box::pin(unsafe { std::mem::zeroed() });
+                    unsafe {
+                        pipewire::sys::pw_proxy_add_object_listener(
+                            proxy.upcast_ref().as_ptr(),
+                            hook.as_mut().get_unchecked_mut(),
+                            &PROFILER_EVENTS as *const ProfilerEvents as *const c_void,
+                            Rc::as_ptr(&callback) as *mut c_void,
+                        );
+                    }
+                    bound.borrow_mut().push(BoundProfiler { _proxy: proxy, hook });
+                }
+            })
+            .register();
+
+        Ok(Self {
+            _registry: registry,
+            _listener: listener,
+            bound,
+            _callback: callback,
+        })
+    }
+}
+
+impl Drop for Profiler {
+    fn drop(&mut self) {
+        self.bound.borrow_mut().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spa::{
+        pod::{Object, Property, PropertyFlags},
+        utils::Fraction,
+    };
+
+    fn block(id: i32, name: &str, signal: i64, status: i32) -> Value {
+        Value::Struct(vec![
+            Value::Int(id),
+            Value::String(name.to_string()),
+            Value::Long(signal - 1_000_000),
+            Value::Long(signal),
+            Value::Long(signal + 10_000),
+            Value::Long(signal + 60_000),
+            Value::Int(status),
+            Value::Fraction(Fraction { num: 256, denom: 48000 }),
+        ])
+    }
+
+    fn property(key: u32, value: Value) -> Property {
+        Property {
+            key,
+            flags: PropertyFlags::empty(),
+            value,
+        }
+    }
+
+    fn cycle(signal: i64, follower_status: i32, driver_xruns: i32) -> Value {
+        Value::Object(Object {
+            type_: spa::sys::SPA_TYPE_OBJECT_Profiler,
+            id: 0,
+            properties: vec![
+                property(
+                    spa::sys::SPA_PROFILER_info,
+                    Value::Struct(vec![Value::Long(1), Value::Float(0.25), Value::Float(0.2), Value::Float(0.1), Value::Int(driver_xruns)]),
+                ),
+                property(
+                    spa::sys::SPA_PROFILER_clock,
+                    Value::Struct(vec![
+                        Value::Int(0),
+                        Value::Int(30),
+                        Value::String("alsa_output".to_string()),
+                        Value::Long(signal),
+                        Value::Fraction(Fraction { num: 1, denom: 48000 }),
+                        Value::Long(0),
+                        Value::Long(1024),
+                    ]),
+                ),
+                property(spa::sys::SPA_PROFILER_driverBlock, block(30, "alsa_output", signal, STATUS_FINISHED)),
+                property(spa::sys::SPA_PROFILER_followerBlock, block(42, "firefox", signal, follower_status)),
+            ],
+        })
+    }
+
+    #[test]
+    fn groups_followers_under_drivers() {
+        let samples = ProfilerSample::parse(&Value::Struct(vec![cycle(1_000_000_000, STATUS_FINISHED, 0), cycle(1_021_333_333, 1, 0)]));
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].quantum, 1024);
+        assert_eq!(samples[0].rate, 48000);
+        assert_eq!(samples[0].followers[0].busy(), 50_000);
+
+        let mut stats = ProfilerStats::default();
+        stats.update(&samples);
+        let rows = stats.grouped();
+        assert_eq!(rows.iter().map(|x| x.id).collect::<Vec<_>>(), [30, 42]);
+        assert!(rows[0].is_driver() && !rows[1].is_driver());
+        assert_eq!((rows[1].quantum, rows[1].rate), (256, 48000));
+        assert!((rows[0].load - 0.25).abs() < 1e-6);
+        assert_eq!((rows[0].xruns, rows[1].xruns), (0, 1));
+
+        stats.expire();
+        assert_eq!(stats.grouped().len(), 2);
+        stats.expire();
+        assert!(stats.grouped().is_empty());
+    }
+
+    #[test]
+    fn resets_reported_and_counted_xruns() {
+        let mut stats = ProfilerStats::default();
+        let xruns = |stats: &ProfilerStats| stats.grouped().iter().map(|x| x.xruns).collect::<Vec<_>>();
+
+        stats.update(&ProfilerSample::parse(&Value::Struct(vec![cycle(1_000_000_000, 1, 5)])));
+        assert_eq!(xruns(&stats), [5, 1]);
+
+        stats.reset_xruns();
+        assert_eq!(xruns(&stats), [0, 0]);
+        stats.update(&ProfilerSample::parse(&Value::Struct(vec![cycle(1_021_333_333, STATUS_FINISHED, 5)])));
+        assert_eq!(xruns(&stats), [0, 0]);
+        stats.update(&ProfilerSample::parse(&Value::Struct(vec![cycle(1_042_666_666, 1, 7)])));
+        assert_eq!(xruns(&stats), [2, 1]);
+    }
+}
@@ -40,6 +40,14 @@ pub mod imp {
         #[property(get, set)]
         pub(super) route_index_output: Cell<u32>,
 
+        /// Latency offset of the active input route in nanoseconds.
+        #[property(get)]
+        pub(super) latency_offset_input: Cell<i64>,
+
+        /// Latency offset of the active output route in nanoseconds.
+        #[property(get)]
+        pub(super) latency_offset_output: Cell<i64>,
+
         #[property(get, set, construct_only)]
         pub(super) wpdevice: OnceCell<wp::pw::Device>,
 
@@ -53,6 +61,18 @@ pub mod imp {
         pub(super) routemodel_output: PwRouteFilterModel,
 
         pub(super) routemodel: gio::ListStore,
+
+        /// Codec id and description of every codec a Bluetooth device offers in its current profile.
+        pub(super) codecs: RefCell<Vec<(u32, String)>>,
+
+        #[property(get)]
+        pub(super) codec: Cell<u32>,
+
+        /// Battery percentage reported by BlueZ, -1 if unknown.
+        #[property(get)]
+        pub(super) battery: Cell<i32>,
+
+        pub(super) battery_proxy: OnceCell<gio::DBusProxy>,
     }
 
     impl Default for PwDeviceObject {
@@ -63,11 +83,17 @@ pub mod imp {
                 profile_index: Default::default(),
                 route_index_input: Default::default(),
                 route_index_output: Default::default(),
+                latency_offset_input: Default::default(),
+                latency_offset_output: Default::default(),
                 wpdevice: Default::default(),
                 profilemodel: gio::ListStore::new::<PwProfileObject>(),
                 routemodel_input: PwRouteFilterModel::new(RouteDirection::Input, gio::ListModel::NONE),
                 routemodel_output: PwRouteFilterModel::new(RouteDirection::Output, gio::ListModel::NONE),
                 routemodel: gio::ListStore::new::<PwRouteObject>(),
+                codecs: Default::default(),
+                codec: Default::default(),
+                battery: Cell::new(-1),
+                battery_proxy: Default::default(),
             }
         }
     }
@@ -100,6 +126,7 @@ pub mod imp {
                     Signal::builder("post-update-profile").build(),
                     Signal::builder("pre-update-route").build(),
                     Signal::builder("post-update-route").build(),
+                    Signal::builder("post-update-codecs").build(),
                 ]
             })
         }
@@ -121,6 +148,11 @@ pub mod imp {
 
             obj.update_routes();
 
+            if obj.is_bluetooth() {
+                obj.update_codecs();
+                obj.setup_battery();
+            }
+
             obj.wpdevice().connect_properties_notify(clone!(#[weak] obj, move |device| {
                 pwvucontrol_debug!("properties changed! id: {}", device.object_id().unwrap());
 
@@ -146,6 +178,9 @@ pub mod imp {
                     "Route" => {
                         obj.update_current_route_index();
                     },
+                    "PropInfo" | "Props" if obj.is_bluetooth() => {
+                        obj.update_codecs();
+                    },
                     _ => {},
                 }
 
@@ -237,6 +272,11 @@ impl PwDeviceObject {
         None
     }
 
+    /// The index of the active profile as known to PipeWire, not the model index.
+    pub(crate) fn current_profile(&self) -> Option<u32> {
+        self.profilemodel().item(self.profile_index()).and_downcast::<PwProfileObject>().map(|profile| profile.index())
+    }
+
     pub(crate) fn set_profile(&self, index: i32) {
         let device = self.wpdevice();
 
@@ -305,6 +345,28 @@ impl PwDeviceObject {
         );
     }
 
+    /// Index and card profile device of every active route.
+    pub(crate) fn active_routes(&self) -> Vec<(u32, i32)> {
+        let mut routes = Vec::new();
+
+        if let Some(params) = self.wpdevice().enum_params_sync("Route", None) {
+            for a in params {
+                let pod: wp::spa::SpaPod = a.get().unwrap();
+                if !pod.is_object() {
+                    continue;
+                }
+
+                let index: Option<i32> = pod.spa_property(&wp::spa::ffi::SPA_PARAM_ROUTE_index);
+                let device: Option<i32> = pod.spa_property(&wp::spa::ffi::SPA_PARAM_ROUTE_device);
+                if let (Some(index), Some(device)) = (index, device) {
+                    routes.push((index as u32, device));
+                }
+            }
+        }
+
+        routes
+    }
+
     fn find_struct_key(input: &wireplumber::spa::SpaPod, key: &str) -> Option<String> {
         let mut iter = input.iterator().into_iter();
 
@@ -328,6 +390,10 @@ impl PwDeviceObject {
         let index_key = keys.find_value_from_short_name("index").expect("index key");
         let description_key = keys.find_value_from_short_name("description").expect("decription key");
         let direction_key = keys.find_value_from_short_name("direction").expect("direction key");
+        let props_key = keys.find_value_from_short_name("props").expect("props key");
+        let latency_offset_key = wp::spa::SpaIdTable::from_name("Spa:Pod:Object:Param:Props")
+            .and_then(|x| x.find_value_from_short_name("latencyOffsetNsec"))
+            .expect("latencyOffsetNsec key");
 
         if let Some(params) = device.enum_params_sync("Route", None) {
             for a in params {
@@ -349,6 +415,13 @@ impl PwDeviceObject {
                 }
                 pwvucontrol_debug!("Current route #{} {}", index, description);
 
+                let latency_offset = pod
+                    .find_spa_property(&props_key)
+                    .and_then(|props| props.find_spa_property(&latency_offset_key))
+                    .and_then(|x| x.long())
+                    .unwrap_or_default();
+                self.update_latency_offset(direction, latency_offset);
+
                 if let Some(modelindex) = self.get_model_index_from_route_index(direction, index) {
                     match direction {
                         RouteDirection::Input => {
@@ -404,6 +477,75 @@ impl PwDeviceObject {
         }
     }
 
+    fn update_latency_offset(&self, direction: RouteDirection, latency_offset: i64) {
+        let imp = self.imp();
+        match direction {
+            RouteDirection::Input => {
+                if imp.latency_offset_input.replace(latency_offset) != latency_offset {
+                    self.notify_latency_offset_input();
+                }
+            }
+            RouteDirection::Output => {
+                if imp.latency_offset_output.replace(latency_offset) != latency_offset {
+                    self.notify_latency_offset_output();
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Index and card profile device of the active route in the given direction.
+    fn active_route(&self, direction: RouteDirection) -> Option<(i32, i32)> {
+        for a in self.wpdevice().enum_params_sync("Route", None)? {
+            let pod: wp::spa::SpaPod = a.get().unwrap();
+            if !pod.is_object() {
+                continue;
+            }
+
+            let route_direction: Option<RouteDirection> = pod.spa_property(&wp::spa::ffi::SPA_PARAM_ROUTE_direction);
+            if route_direction != Some(direction) {
+                continue;
+            }
+
+            let index: Option<i32> = pod.spa_property(&wp::spa::ffi::SPA_PARAM_ROUTE_index);
+            let device: Option<i32> = pod.spa_property(&wp::spa::ffi::SPA_PARAM_ROUTE_device);
+            if let (Some(index), Some(device)) = (index, device) {
+                return Some((index, device));
+            }
+        }
+        None
+    }
+
+    /// Sets the latency offset of the active route in the given direction, like pactl set-port-latency-offset.
+    pub(crate) fn set_latency_offset(&self, direction: RouteDirection, latency_offset: i64) {
+        let Some((index, device_index)) = self.active_route(direction) else {
+            pwvucontrol_warning!("{direction:?} No active route to set latency offset on");
+            return;
+        };
+
+        let propsbuilder = SpaPodBuilder::new_object("Spa:Pod:Object:Param:Props", "Props");
+        propsbuilder.add_property("latencyOffsetNsec");
+        propsbuilder.add_long(latency_offset);
+        let Some(props) = propsbuilder.end() else {
+            return;
+        };
+
+        let podbuilder = SpaPodBuilder::new_object("Spa:Pod:Object:Param:Route", "Route");
+
+        podbuilder.add_property("index");
+        podbuilder.add_int(index);
+        podbuilder.add_property("device");
+        podbuilder.add_int(device_index);
+        podbuilder.add_property("props");
+        podbuilder.add_pod(&props);
+        podbuilder.add_property("save");
+        podbuilder.add_boolean(true);
+
+        if let Some(pod) = podbuilder.end() {
+            self.wpdevice().set_param("Route", 0, pod);
+        }
+    }
+
     fn get_route_model_for_direction(&self, direction: RouteDirection) -> PwRouteFilterModel {
         match direction {
             RouteDirection::Input => self.routemodel_input(),
@@ -412,6 +554,115 @@ impl PwDeviceObject {
         }
     }
 
+    pub(crate) fn is_bluetooth(&self) -> bool {
+        self.wpdevice().pw_property::<String>("device.api").is_ok_and(|api| api == "bluez5")
+    }
+
+    /// Codec id and description of the codecs offered in the current profile.
+    pub(crate) fn codecs(&self) -> Vec<(u32, String)> {
+        self.imp().codecs.borrow().clone()
+    }
+
+    /// Reads the codecs from the bluetoothAudioCodec prop info, which labels every codec id with a description.
+    pub(crate) fn update_codecs(&self) {
+        let device = self.wpdevice();
+        let mut codecs = Vec::new();
+
+        if let Some(params) = device.enum_params_sync("PropInfo", None) {
+            for a in params {
+                let pod: wp::spa::SpaPod = a.get().unwrap();
+                if !pod.is_object() {
+                    continue;
+                }
+
+                let id = pod.find_spa_property(&wp::spa::ffi::SPA_PROP_INFO_id).and_then(|x| x.id());
+                if id != Some(wp::spa::ffi::SPA_PROP_bluetoothAudioCodec) {
+                    continue;
+                }
+
+                if let Some(labels) = pod.find_spa_property(&wp::spa::ffi::SPA_PROP_INFO_labels) {
+                    let mut iter = labels.iterator().into_iter();
+                    while let (Some(codec), Some(description)) = (iter.next(), iter.next()) {
+                        if let (Some(codec), Some(description)) = (codec.int(), description.string()) {
+                            codecs.push((codec as u32, description.to_string()));
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut codec = 0;
+        if let Some(params) = device.enum_params_sync("Props", None) {
+            for a in params {
+                let pod: wp::spa::SpaPod = a.get().unwrap();
+                if let Some(id) = pod.find_spa_property(&wp::spa::ffi::SPA_PROP_bluetoothAudioCodec).and_then(|x| x.id()) {
+                    codec = id;
+                }
+            }
+        }
+
+        pwvucontrol_debug!("Bluetooth codecs {codecs:?}, current {codec}");
+        self.imp().codecs.replace(codecs);
+        if self.imp().codec.replace(codec) != codec {
+            self.notify_codec();
+        }
+        self.emit_by_name::<()>("post-update-codecs", &[]);
+    }
+
+    pub(crate) fn set_codec(&self, codec: u32) {
+        let podbuilder = SpaPodBuilder::new_object("Spa:Pod:Object:Param:Props", "Props");
+
+        podbuilder.add_property("bluetoothAudioCodec");
+        podbuilder.add_id(codec);
+
+        if let Some(pod) = podbuilder.end() {
+            self.wpdevice().set_param("Props", 0, pod);
+        }
+    }
+
+    /// PipeWire doesn't expose the battery level itself, so ask BlueZ for it.
+    fn setup_battery(&self) {
+        let Ok(path) = self.wpdevice().pw_property::<String>("api.bluez5.path") else {
+            return;
+        };
+
+        gio::DBusProxy::for_bus(
+            gio::BusType::System,
+            gio::DBusProxyFlags::DO_NOT_AUTO_START,
+            None,
+            "org.bluez",
+            &path,
+            "org.bluez.Battery1",
+            gio::Cancellable::NONE,
+            clone!(#[weak(rename_to = obj)] self, move |res| {
+                let proxy = match res {
+                    Ok(proxy) => proxy,
+                    Err(e) => {
+                        pwvucontrol_info!("No battery information for {path}: {e}");
+                        return;
+                    }
+                };
+
+                proxy.connect_local("g-properties-changed", false, clone!(#[weak] obj, #[upgrade_or] None, move |values| {
+                    if let Ok(proxy) = values[0].get::<gio::DBusProxy>() {
+                        obj.update_battery(&proxy);
+                    }
+
+                    None
+                }));
+                obj.update_battery(&proxy);
+                let _ = obj.imp().battery_proxy.set(proxy);
+            }),
+        );
+    }
+
+    fn update_battery(&self, proxy: &gio::DBusProxy) {
+        let battery = proxy.cached_property("Percentage").and_then(|x| x.get::<u8>()).map_or(-1, i32::from);
+        if self.imp().battery.replace(battery) != battery {
+            self.notify_battery();
+        }
+    }
+
     fn label_set_name(&self) {
         let description: String = self.wpdevice().pw_property("device.description").expect("device description");
         self.set_name(description);
@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gtk::{
+    glib::{self, Properties},
+    prelude::*,
+    subclass::prelude::*,
+};
+use std::cell::{Cell, OnceCell};
+use wireplumber as wp;
+use wp::pw::{GlobalProxyExt, PipewireObjectExt2, ProxyExt};
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, Properties)]
+    #[properties(wrapper_type = super::PwLinkObject)]
+    pub struct PwLinkObject {
+        #[property(get)]
+        pub(super) boundid: Cell<u32>,
+        #[property(get)]
+        pub(super) output_node: Cell<u32>,
+        #[property(get)]
+        pub(super) output_port: Cell<u32>,
+        #[property(get)]
+        pub(super) input_node: Cell<u32>,
+        #[property(get)]
+        pub(super) input_port: Cell<u32>,
+
+        #[property(get, set, construct_only)]
+        pub(super) wplink: OnceCell<wp::pw::Link>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwLinkObject {
+        const NAME: &'static str = "PwLinkObject";
+        type Type = super::PwLinkObject;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwLinkObject {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let link = self.wplink.get().expect("Link set on PwLinkObject");
+
+            self.boundid.set(link.bound_id());
+            self.output_node.set(link.pw_property("link.output.node").unwrap_or_default());
+            self.output_port.set(link.pw_property("link.output.port").unwrap_or_default());
+            self.input_node.set(link.pw_property("link.input.node").unwrap_or_default());
+            self.input_port.set(link.pw_property("link.input.port").unwrap_or_default());
+        }
+    }
+
+    impl PwLinkObject {}
+}
+
+glib::wrapper! {
+    pub struct PwLinkObject(ObjectSubclass<imp::PwLinkObject>);
+}
+
+impl PwLinkObject {
+    pub(crate) fn new(link: &wp::pw::Link) -> Self {
+        glib::Object::builder().property("wplink", link).build()
+    }
+
+    /// Asks the PipeWire server to destroy the link.
+    pub(crate) fn destroy(&self) {
+        self.wplink().request_destroy();
+    }
+}
@@ -17,6 +17,14 @@ mod imp {
         #[property(get, set, construct_only, builder(NodeType::Undefined))]
         pub(super) nodetype: Cell<NodeType>,
 
+        /// Also let through nodes marked as hidden.
+        #[property(get, set = Self::set_show_hidden)]
+        pub(super) show_hidden: Cell<bool>,
+
+        /// Only let through nodes where the name, description, application.name or media.name contains this text.
+        #[property(get, set = Self::set_search)]
+        pub(super) search: RefCell<String>,
+
         /// The model we are filtering.
         #[property(get, set = Self::set_model, nullable)]
         pub(super) model: RefCell<Option<gio::ListModel>>,
@@ -37,10 +45,14 @@ mod imp {
             self.parent_constructed();
 
             let nodetype = self.nodetype.get();
+            let model = self.obj().downgrade();
 
             let filter = gtk::CustomFilter::new(move |obj| {
                 let node: &PwNodeObject = obj.downcast_ref().expect("PwNodeObject");
-                node.nodetype() == nodetype && !node.hidden()
+                let Some(model) = model.upgrade() else {
+                    return false;
+                };
+                node.nodetype() == nodetype && (model.show_hidden() || !node.hidden()) && matches_search(node, &model.imp().search.borrow())
             });
 
             self.filtered_model
@@ -87,6 +99,20 @@ mod imp {
             }
         }
 
+        fn set_show_hidden(&self, show_hidden: bool) {
+            if self.show_hidden.replace(show_hidden) != show_hidden {
+                self.obj().refilter();
+            }
+        }
+
+        fn set_search(&self, search: String) {
+            let search = search.trim().to_lowercase();
+            if *self.search.borrow() != search {
+                self.search.replace(search);
+                self.obj().refilter();
+            }
+        }
+
         fn disconnect(&self) {
             let filtered_model = self.filtered_model.get().expect("Filtered model");
             filtered_model.set_model(gio::ListModel::NONE);
@@ -97,6 +123,18 @@ mod imp {
     }
 }
 
+/// `search` is expected to be lowercase already.
+fn matches_search(node: &PwNodeObject, search: &str) -> bool {
+    if search.is_empty() {
+        return true;
+    }
+
+    [Some(node.name()), Some(node.description()), node.node_property::<String>("application.name"), node.node_property::<String>("media.name")]
+        .into_iter()
+        .flatten()
+        .any(|text| text.to_lowercase().contains(search))
+}
+
 glib::wrapper! {
     pub struct PwNodeFilterModel(ObjectSubclass<imp::PwNodeFilterModel>) @implements gio::ListModel;
 }
@@ -106,6 +144,20 @@ impl PwNodeFilterModel {
         glib::Object::builder().property("model", &model).property("nodetype", nodetype).build()
     }
 
+    /// Runs the filter again, e.g. after the hidden flag of some nodes changed.
+    pub(crate) fn refilter(&self) {
+        if let Some(filter) = self.imp().filtered_model.get().and_then(|x| x.filter()) {
+            filter.changed(gtk::FilterChange::Different);
+        }
+    }
+
+    /// Runs the filter again while searching, for when the searched text of a node changed.
+    pub(crate) fn refilter_search(&self) {
+        if !self.imp().search.borrow().is_empty() {
+            self.refilter();
+        }
+    }
+
     pub fn get_node_pos_from_id(&self, id: u32) -> Option<u32> {
         let pos: Option<usize> = self
             .iter::<PwNodeObject>()
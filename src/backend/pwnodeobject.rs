@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use super::{PwChannelObject, PwDeviceObject, PwRouteObject, PwvucontrolManager};
+use super::{spapod, PwChannelObject, PwDeviceObject, PwRouteObject, PwvucontrolManager};
 use glib::{clone, subclass::Signal, ParamSpec, Properties, Value};
 use gtk::{gio, prelude::*, subclass::prelude::*};
 use std::cell::OnceCell;
@@ -31,6 +31,7 @@ pub enum NodeType {
 
 #[derive(Copy, Clone, Debug)]
 pub struct AudioFormat {
+    pub rate: u32,
     pub positions: [u32; 64],
 }
 
@@ -76,6 +77,11 @@ pub mod imp {
         #[property(get)]
         is_virtual: Cell<bool>,
 
+        #[property(get, builder(wp::pw::NodeState::Creating))]
+        state: Cell<wp::pw::NodeState>,
+        /// Reason given by PipeWire when the state is `Error`.
+        pub(super) state_error: RefCell<Option<String>>,
+
         #[property(get)]
         pub(super) channelmodel: RefCell<gio::ListStore>,
 
@@ -118,6 +124,8 @@ pub mod imp {
                 hidden: Default::default(),
                 device: Default::default(),
                 is_virtual: Default::default(),
+                state: Cell::new(wp::pw::NodeState::Creating),
+                state_error: Default::default(),
             }
         }
     }
@@ -208,7 +216,12 @@ pub mod imp {
                 obj.imp().block.set(false);
             }));
 
+            node.connect_state_changed(clone!(#[weak] obj, move |_, _, _| {
+                obj.update_state();
+            }));
+
             obj.label_set_description();
+            obj.update_state();
             obj.update_props();
             obj.update_format();
             obj.label_set_name();
@@ -396,7 +409,7 @@ impl PwNodeObject {
 
                     widget.set_formatstr(format!("{}ch {}Hz {}", channels, rate, formatname));
 
-                    widget.set_format(AudioFormat { positions: a });
+                    widget.set_format(AudioFormat { rate: rate as u32, positions: a });
 
                     // Must be done here since EnumFormat is async
                     widget.update_channelmodel();
@@ -496,10 +509,42 @@ impl PwNodeObject {
         self.emit_by_name::<()>("format", &[]);
     }
 
+    fn update_state(&self) {
+        let (state, error) = self.wpnode().state();
+        self.imp().state_error.replace(error.map(|x| x.to_string()).filter(|_| state == wp::pw::NodeState::Error));
+        self.imp().state.set(state);
+        self.notify_state();
+    }
+
+    pub(crate) fn state_error(&self) -> Option<String> {
+        self.imp().state_error.borrow().clone()
+    }
+
+    /// Makes the node close its device until something plays to or records from it again.
+    pub(crate) fn suspend(&self) {
+        self.wpnode().send_command("Suspend");
+    }
+
     pub(crate) fn format(&self) -> Option<AudioFormat> {
         self.imp().format.get()
     }
 
+    /// Channel positions in the order of the channel volumes, empty until the format is known.
+    pub(crate) fn channel_positions(&self) -> Vec<u32> {
+        match self.format() {
+            Some(format) => format.positions.iter().take(self.channel_volumes_vec().len()).copied().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Whether `target_node` can be the target of this stream, playback streams go to sinks and recording streams to sources.
+    pub(crate) fn can_target(&self, target_node: &PwNodeObject) -> bool {
+        matches!(
+            (self.nodetype(), target_node.nodetype()),
+            (NodeType::StreamOutput, NodeType::Sink) | (NodeType::StreamInput, NodeType::Source)
+        )
+    }
+
     pub(crate) fn set_default_target(&self, target_node: &PwNodeObject) {
         let manager = PwvucontrolManager::default();
 
@@ -616,9 +661,35 @@ impl PwNodeObject {
         let node = self.imp().wpnode.get().expect("node");
         node.pw_property(property).ok()
     }
+
+    /// All global properties, or all node properties if `global` is false, sorted by key.
+    pub(crate) fn property_list(&self, global: bool) -> Vec<(String, String)> {
+        let node = self.imp().wpnode.get().expect("node");
+        let props = if global { node.global_properties() } else { node.properties() };
+        let Some(props) = props else {
+            return Vec::new();
+        };
+
+        let mut list: Vec<(String, String)> = props
+            .iter()
+            .map(|item| (item.key().map(|x| x.to_string()).unwrap_or_default(), item.value().map(|x| x.to_string()).unwrap_or_default()))
+            .collect();
+        list.sort();
+        list
+    }
+
+    /// Params of the given kind, e.g. "Props" or "EnumFormat", rendered as text.
+    pub(crate) fn params_text(&self, id: &str) -> String {
+        let node = self.imp().wpnode.get().expect("node");
+        let Some(params) = node.enum_params_sync(id, None) else {
+            return String::new();
+        };
+
+        params.into_iter().filter_map(|value| value.get::<wp::spa::SpaPod>().ok()).map(|pod| spapod::describe(&pod)).collect::<Vec<_>>().join("\n\n")
+    }
 }
 
-trait MetadataExtFix: 'static {
+pub(crate) trait MetadataExtFix: 'static {
     fn find_notype(&self, subject: u32, key: &str) -> Option<glib::GString>;
 }
 
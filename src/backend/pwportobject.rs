@@ -0,0 +1,89 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gtk::{
+    glib::{self, Properties},
+    prelude::*,
+    subclass::prelude::*,
+};
+use std::cell::{Cell, OnceCell, RefCell};
+use wireplumber as wp;
+use wp::pw::{PipewireObjectExt2, ProxyExt};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, glib::Enum, Default)]
+#[enum_type(name = "PortDirection")]
+pub enum PortDirection {
+    #[default]
+    Unknown,
+    Input,
+    Output,
+}
+
+impl From<&str> for PortDirection {
+    fn from(value: &str) -> Self {
+        match value {
+            "in" => PortDirection::Input,
+            "out" => PortDirection::Output,
+            _ => PortDirection::Unknown,
+        }
+    }
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, Properties)]
+    #[properties(wrapper_type = super::PwPortObject)]
+    pub struct PwPortObject {
+        #[property(get)]
+        pub(super) boundid: Cell<u32>,
+        #[property(get)]
+        pub(super) node_id: Cell<u32>,
+        #[property(get)]
+        pub(super) name: RefCell<String>,
+        #[property(get, builder(PortDirection::Unknown))]
+        pub(super) direction: Cell<PortDirection>,
+        #[property(get)]
+        pub(super) is_monitor: Cell<bool>,
+
+        #[property(get, set, construct_only)]
+        pub(super) wpport: OnceCell<wp::pw::Port>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwPortObject {
+        const NAME: &'static str = "PwPortObject";
+        type Type = super::PwPortObject;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwPortObject {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let port = self.wpport.get().expect("Port set on PwPortObject");
+
+            self.boundid.set(port.bound_id());
+            self.node_id.set(port.pw_property("node.id").unwrap_or_default());
+            self.direction.set(port.pw_property::<String>("port.direction").unwrap_or_default().as_str().into());
+            self.is_monitor.set(port.pw_property::<bool>("port.monitor").unwrap_or_default());
+
+            let name: String = port
+                .pw_property("port.alias")
+                .or_else(|_| port.pw_property("port.name"))
+                .unwrap_or_else(|_| port.bound_id().to_string());
+            self.name.replace(name);
+        }
+    }
+
+    impl PwPortObject {}
+}
+
+glib::wrapper! {
+    pub struct PwPortObject(ObjectSubclass<imp::PwPortObject>);
+}
+
+impl PwPortObject {
+    pub(crate) fn new(port: &wp::pw::Port) -> Self {
+        glib::Object::builder().property("wpport", port).build()
+    }
+}
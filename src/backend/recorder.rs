@@ -0,0 +1,142 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::backend::{AudioFileFormat, AudioFileWriter, LevelMeter, PwNodeObject, PwvucontrolManager};
+use pipewire::spa::sys::{SPA_AUDIO_CHANNEL_FL, SPA_AUDIO_CHANNEL_FR};
+use std::{
+    cell::RefCell,
+    io,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Duration,
+};
+
+/// Used when the node has not told its rate yet.
+const FALLBACK_RATE: u32 = 48000;
+
+#[derive(Default)]
+struct State {
+    writer: Option<AudioFileWriter>,
+    frames: u64,
+    bytes: u64,
+    error: Option<io::Error>,
+}
+
+/// Progress of a recording at one point in time.
+#[derive(Debug, Clone)]
+pub struct RecordingStatus {
+    pub path: PathBuf,
+    pub elapsed: Duration,
+    /// Size of the file so far.
+    pub bytes: u64,
+    /// Set when writing failed, recording stops then.
+    pub error: Option<String>,
+}
+
+/// Records a node to a WAV or FLAC file until stopped.
+pub struct Recorder {
+    path: PathBuf,
+    rate: u32,
+    state: Rc<RefCell<State>>,
+    capture: Option<LevelMeter>,
+}
+
+impl Recorder {
+    /// Starts recording `node` to `path` in the node's own rate and channels, the format follows the file extension.
+    pub fn start(node: &PwNodeObject, path: &Path) -> Result<Self, anyhow::Error> {
+        let format = AudioFileFormat::from_path(path).ok_or_else(|| anyhow::anyhow!("Only .wav and .flac files are supported"))?;
+
+        let mut positions = node.channel_positions();
+        if positions.is_empty() {
+            positions = vec![SPA_AUDIO_CHANNEL_FL, SPA_AUDIO_CHANNEL_FR];
+        }
+        positions.truncate(format.max_channels());
+        let rate = node.format().map(|x| x.rate).filter(|x| *x > 0).unwrap_or(FALLBACK_RATE);
+
+        let writer = AudioFileWriter::create(path, format, rate, &positions)?;
+        let state = Rc::new(RefCell::new(State {
+            bytes: writer.bytes(),
+            writer: Some(writer),
+            ..Default::default()
+        }));
+
+        let capture = PwvucontrolManager::default().add_capture(node.boundid(), &positions, rate, {
+            let state = state.clone();
+            move |samples| {
+                let mut state = state.borrow_mut();
+                let Some(writer) = state.writer.as_mut() else {
+                    return;
+                };
+                match writer.write(samples) {
+                    Ok(()) => {
+                        let (frames, bytes) = (writer.frames(), writer.bytes());
+                        state.frames = frames;
+                        state.bytes = bytes;
+                    }
+                    Err(e) => {
+                        // Keep what was written so far playable.
+                        if let Some(Err(e)) = state.writer.take().map(|x| x.finish()) {
+                            crate::pwvucontrol_warning!("Unable to finish recording: {e}");
+                        }
+                        state.error = Some(e);
+                    }
+                }
+            }
+        })?;
+
+        Ok(Self {
+            path: path.to_owned(),
+            rate,
+            state,
+            capture: Some(capture),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_secs_f64(self.state.borrow().frames as f64 / self.rate as f64)
+    }
+
+    /// Size of the file so far.
+    pub fn bytes(&self) -> u64 {
+        self.state.borrow().bytes
+    }
+
+    /// Set when writing failed, recording stops then.
+    pub fn error(&self) -> Option<String> {
+        self.state.borrow().error.as_ref().map(|e| e.to_string())
+    }
+
+    pub fn status(&self) -> RecordingStatus {
+        RecordingStatus {
+            path: self.path.clone(),
+            elapsed: self.elapsed(),
+            bytes: self.bytes(),
+            error: self.error(),
+        }
+    }
+
+    /// Stops capturing and completes the file.
+    pub fn stop(mut self) -> io::Result<()> {
+        self.finish()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.capture.take();
+        let writer = self.state.borrow_mut().writer.take();
+        match writer {
+            Some(writer) => writer.finish().map(|_| ()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            crate::pwvucontrol_warning!("Unable to finish recording {}: {e}", self.path.display());
+        }
+    }
+}
@@ -0,0 +1,363 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::{PwDeviceObject, PwNodeObject, PwvucontrolManager};
+use crate::macros::*;
+use gtk::{
+    glib::{self, clone},
+    prelude::*,
+};
+use std::{cell::RefCell, collections::HashSet, path::PathBuf, rc::Rc, time::Duration};
+use wireplumber as wp;
+use wp::pw::PipewireObjectExt2;
+
+const NODE_GROUP_PREFIX: &str = "node:";
+const DEVICE_GROUP_PREFIX: &str = "device:";
+const SCENE_FILE_EXTENSION: &str = "scene";
+/// How long to wait for the nodes of a card to come back after switching its profile.
+const PROFILE_SWITCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NodeState {
+    pub name: String,
+    pub volume: f32,
+    pub mute: bool,
+    pub channel_volumes: Vec<f32>,
+    /// node.name of the stream's target, if it has one.
+    pub target: Option<String>,
+}
+
+impl NodeState {
+    /// Applies the state to the nodes named by it, leaving out `stale` ones. Returns false while the node,
+    /// or its target on a card being switched, isn't there yet.
+    fn apply(&self, manager: &PwvucontrolManager, stale: &[PwNodeObject]) -> bool {
+        let nodes: Vec<PwNodeObject> = manager.node_model().iter::<PwNodeObject>().map_while(Result::ok).filter(|node| !stale.contains(node)).collect();
+        let find_node = |name: &str| nodes.iter().find(|node| node.node_property::<String>("node.name").as_deref() == Some(name));
+
+        let target = self.target.as_deref().map(find_node);
+        let matching: Vec<&PwNodeObject> = nodes.iter().filter(|node| node.node_property::<String>("node.name").as_deref() == Some(self.name.as_str())).collect();
+        if matching.is_empty() || (!stale.is_empty() && matches!(target, Some(None))) {
+            return false;
+        }
+
+        for node in matching {
+            if !self.channel_volumes.is_empty() && node.channel_volumes_vec().len() == self.channel_volumes.len() {
+                node.set_channel_volumes_vec(&self.channel_volumes);
+            } else {
+                node.set_volume(self.volume);
+            }
+            node.set_mute(self.mute);
+
+            match target {
+                Some(Some(target)) => node.set_default_target(target),
+                Some(None) => pwvucontrol_info!("Scene target for {} is not present", self.name),
+                None => {
+                    if node.default_target().is_some() {
+                        node.unset_default_target();
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RouteState {
+    pub index: u32,
+    pub device: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DeviceState {
+    pub name: String,
+    pub profile: Option<u32>,
+    pub routes: Vec<RouteState>,
+}
+
+/// A snapshot of volumes, stream targets, card profiles and routes.
+///
+/// Nodes and devices are keyed by node.name and device.name since bound ids
+/// and serials do not survive a restart of the PipeWire server.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Scene {
+    pub nodes: Vec<NodeState>,
+    pub devices: Vec<DeviceState>,
+}
+
+fn join_list<T: ToString>(values: impl IntoIterator<Item = T>) -> String {
+    values.into_iter().map(|x| x.to_string() + ";").collect()
+}
+
+impl Scene {
+    pub fn capture(manager: &PwvucontrolManager) -> Self {
+        let mut scene = Scene::default();
+
+        for node in manager.node_model().iter::<PwNodeObject>().map_while(Result::ok) {
+            let Some(name) = node.node_property::<String>("node.name") else {
+                continue;
+            };
+            if node.hidden() || scene.nodes.iter().any(|x| x.name == name) {
+                continue;
+            }
+
+            scene.nodes.push(NodeState {
+                name,
+                volume: node.volume(),
+                mute: node.mute(),
+                channel_volumes: node.channel_volumes_vec(),
+                target: node.default_target().and_then(|target| target.node_property("node.name")),
+            });
+        }
+
+        for device in manager.device_model().iter::<PwDeviceObject>().map_while(Result::ok) {
+            let Ok(name) = device.wpdevice().pw_property::<String>("device.name") else {
+                continue;
+            };
+
+            scene.devices.push(DeviceState {
+                name,
+                profile: device.current_profile(),
+                routes: device.active_routes().into_iter().map(|(index, device)| RouteState { index, device }).collect(),
+            });
+        }
+
+        scene
+    }
+
+    /// Profiles go first since switching them recreates the card's nodes and routes.
+    ///
+    /// The switch finishes asynchronously, so the state of nodes on switched cards is applied
+    /// to the new nodes once they show up.
+    pub fn apply(&self, manager: &PwvucontrolManager) {
+        let mut switched = HashSet::new();
+        for state in self.devices.iter() {
+            let Some(device) = manager
+                .device_model()
+                .iter::<PwDeviceObject>()
+                .map_while(Result::ok)
+                .find(|device| device.wpdevice().pw_property::<String>("device.name").is_ok_and(|name| name == state.name))
+            else {
+                pwvucontrol_info!("Scene device {} is not present", state.name);
+                continue;
+            };
+
+            if let Some(profile) = state.profile {
+                if device.current_profile() != Some(profile) {
+                    device.set_profile(profile as i32);
+                    switched.insert(device.wpdevice().bound_id());
+                }
+            }
+            for route in state.routes.iter() {
+                device.set_route(route.index, route.device);
+            }
+        }
+
+        // These nodes are about to be destroyed, their state goes to the ones replacing them.
+        // Compared as objects since PipeWire may hand the new nodes the same ids.
+        let stale: Vec<PwNodeObject> = manager
+            .node_model()
+            .iter::<PwNodeObject>()
+            .map_while(Result::ok)
+            .filter(|node| node.wpnode().device_id().ok().flatten().is_some_and(|id| switched.contains(&id)))
+            .collect();
+
+        let pending: Vec<NodeState> = self.nodes.iter().filter(|state| !state.apply(manager, &stale)).cloned().collect();
+        if !stale.is_empty() && !pending.is_empty() {
+            Self::apply_when_added(manager, pending, stale);
+        }
+    }
+
+    fn apply_when_added(manager: &PwvucontrolManager, pending: Vec<NodeState>, stale: Vec<PwNodeObject>) {
+        let pending = Rc::new(RefCell::new(pending));
+        let handler: Rc<RefCell<Option<glib::SignalHandlerId>>> = Default::default();
+        let timeout: Rc<RefCell<Option<glib::SourceId>>> = Default::default();
+
+        handler.replace(Some(manager.node_model().connect_items_changed(clone!(
+            #[weak] manager,
+            #[strong] pending,
+            #[strong] handler,
+            #[strong] timeout,
+            move |model, _, _, added| {
+                if added == 0 {
+                    return;
+                }
+                pending.borrow_mut().retain(|state| !state.apply(&manager, &stale));
+                if pending.borrow().is_empty() {
+                    if let Some(handler) = handler.take() {
+                        model.disconnect(handler);
+                    }
+                    if let Some(timeout) = timeout.take() {
+                        timeout.remove();
+                    }
+                }
+            }
+        ))));
+
+        timeout.replace(Some(glib::timeout_add_local_once(PROFILE_SWITCH_TIMEOUT, clone!(
+            #[weak] manager,
+            #[strong] timeout,
+            move || {
+                timeout.take();
+                if let Some(handler) = handler.take() {
+                    manager.node_model().disconnect(handler);
+                }
+                for state in pending.borrow().iter() {
+                    pwvucontrol_info!("Scene node {} did not come back after the profile switch", state.name);
+                }
+            }
+        ))));
+    }
+
+    pub fn to_key_file(&self) -> glib::KeyFile {
+        let keyfile = glib::KeyFile::new();
+
+        for node in self.nodes.iter() {
+            let group = format!("{NODE_GROUP_PREFIX}{}", node.name);
+            keyfile.set_double(&group, "volume", node.volume as f64);
+            keyfile.set_boolean(&group, "mute", node.mute);
+            if !node.channel_volumes.is_empty() {
+                keyfile.set_value(&group, "channel-volumes", &join_list(node.channel_volumes.iter()));
+            }
+            if let Some(target) = node.target.as_ref() {
+                keyfile.set_string(&group, "target", target);
+            }
+        }
+
+        for device in self.devices.iter() {
+            let group = format!("{DEVICE_GROUP_PREFIX}{}", device.name);
+            if let Some(profile) = device.profile {
+                keyfile.set_integer(&group, "profile", profile as i32);
+            }
+            if !device.routes.is_empty() {
+                keyfile.set_value(&group, "routes", &join_list(device.routes.iter().flat_map(|route| [route.index as i32, route.device])));
+            }
+        }
+
+        keyfile
+    }
+
+    pub fn from_key_file(keyfile: &glib::KeyFile) -> Self {
+        let mut scene = Scene::default();
+
+        for group in keyfile.groups().iter() {
+            let group = group.as_str();
+            if let Some(name) = group.strip_prefix(NODE_GROUP_PREFIX) {
+                scene.nodes.push(NodeState {
+                    name: name.to_string(),
+                    volume: keyfile.double(group, "volume").unwrap_or(1.0) as f32,
+                    mute: keyfile.boolean(group, "mute").unwrap_or_default(),
+                    channel_volumes: keyfile
+                        .double_list(group, "channel-volumes")
+                        .map(|list| list.into_iter().map(|x| x as f32).collect())
+                        .unwrap_or_default(),
+                    target: keyfile.string(group, "target").ok().map(|x| x.to_string()),
+                });
+            } else if let Some(name) = group.strip_prefix(DEVICE_GROUP_PREFIX) {
+                scene.devices.push(DeviceState {
+                    name: name.to_string(),
+                    profile: keyfile.integer(group, "profile").ok().map(|x| x as u32),
+                    routes: keyfile
+                        .integer_list(group, "routes")
+                        .unwrap_or_default()
+                        .chunks_exact(2)
+                        .map(|pair| RouteState { index: pair[0] as u32, device: pair[1] })
+                        .collect(),
+                });
+            }
+        }
+
+        scene
+    }
+
+    pub fn load(name: &str) -> Result<Self, glib::Error> {
+        let keyfile = glib::KeyFile::new();
+        keyfile.load_from_file(Self::path(name), glib::KeyFileFlags::NONE)?;
+        Ok(Self::from_key_file(&keyfile))
+    }
+
+    pub fn save(&self, name: &str) -> Result<(), glib::Error> {
+        if let Err(e) = std::fs::create_dir_all(Self::dir()) {
+            pwvucontrol_warning!("Cannot create scenes directory: {e}");
+        }
+        self.to_key_file().save_to_file(Self::path(name))
+    }
+
+    pub fn delete(name: &str) -> std::io::Result<()> {
+        std::fs::remove_file(Self::path(name))
+    }
+
+    /// Names of the saved scenes, sorted alphabetically.
+    pub fn list() -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(Self::dir()) else {
+            return Vec::new();
+        };
+
+        let mut names: Vec<String> = entries
+            .map_while(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == SCENE_FILE_EXTENSION))
+            .filter_map(|path| path.file_stem().and_then(|x| x.to_str()).map(str::to_string))
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Scene names end up as file names, so path separators and leading dots are not allowed.
+    pub fn is_valid_name(name: &str) -> bool {
+        !name.trim().is_empty() && !name.starts_with('.') && !name.contains(['/', '\\'])
+    }
+
+    fn dir() -> PathBuf {
+        glib::user_data_dir().join("pwvucontrol").join("scenes")
+    }
+
+    fn path(name: &str) -> PathBuf {
+        Self::dir().join(format!("{name}.{SCENE_FILE_EXTENSION}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scene_survives_key_file_round_trip() {
+        let scene = Scene {
+            nodes: vec![
+                NodeState {
+                    name: "alsa_output.pci-0000_00_1f.3.analog-stereo".to_string(),
+                    volume: 0.5,
+                    mute: false,
+                    channel_volumes: vec![0.25, 0.5],
+                    target: None,
+                },
+                NodeState { name: "Firefox".to_string(), volume: 1.0, mute: true, channel_volumes: vec![], target: Some("bluez_output.headset".to_string()) },
+            ],
+            devices: vec![
+                DeviceState {
+                    name: "alsa_card.pci-0000_00_1f.3".to_string(),
+                    profile: Some(3),
+                    routes: vec![RouteState { index: 4, device: 6 }, RouteState { index: 1, device: 2 }],
+                },
+                DeviceState { name: "bluez_card.00_11_22_33_44_55".to_string(), profile: None, routes: vec![] },
+            ],
+        };
+
+        let keyfile = scene.to_key_file();
+        let parsed = glib::KeyFile::new();
+        parsed.load_from_data(&keyfile.to_data(), glib::KeyFileFlags::NONE).expect("valid key file");
+
+        assert_eq!(Scene::from_key_file(&parsed), scene);
+    }
+
+    #[test]
+    fn scene_names_must_be_plain_file_names() {
+        assert!(Scene::is_valid_name("Meeting"));
+        assert!(Scene::is_valid_name("music production"));
+        assert!(!Scene::is_valid_name(""));
+        assert!(!Scene::is_valid_name("  "));
+        assert!(!Scene::is_valid_name("../evil"));
+        assert!(!Scene::is_valid_name(".hidden"));
+        assert!(!Scene::is_valid_name("a/b"));
+    }
+}
@@ -0,0 +1,46 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Helpers for SPA-JSON, the relaxed JSON dialect PipeWire uses for metadata values
+//! and configuration. Parsing is left to WirePlumber's [`wp::spa::SpaJson`].
+
+use wireplumber as wp;
+
+/// Quotes a string for use in SPA-JSON, e.g. module arguments.
+pub(crate) fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Extracts the node name from a default node metadata value like `{"name":"alsa_output.foo"}`.
+pub(crate) fn default_node_name(value: &str) -> Option<String> {
+    let json = wp::spa::SpaJson::from_string(value);
+    json.parse_object()?.into_iter().find(|(key, _)| key == "name")?.1.parse_str().map(|name| name.to_string())
+}
+
+/// Positive integers of an array like `[ 44100 48000 ]`, other items are skipped.
+pub(crate) fn parse_uint_array(value: &str) -> Vec<u32> {
+    let json = wp::spa::SpaJson::from_string(value);
+    match json.parse_array() {
+        Some(items) => items.into_iter().filter_map(|item| item.parse_int()).filter(|x| *x > 0).map(|x| x as u32).collect(),
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_default_node_metadata() {
+        assert_eq!(default_node_name(r#"{"name":"alsa_output.pci-0000_00_1f.3.analog-stereo"}"#).as_deref(), Some("alsa_output.pci-0000_00_1f.3.analog-stereo"));
+        assert_eq!(default_node_name(r#"{ "name": "with \"quotes\"" }"#).as_deref(), Some("with \"quotes\""));
+        assert_eq!(default_node_name(r#"{"other":"x"}"#), None);
+        assert_eq!(default_node_name(""), None);
+    }
+
+    #[test]
+    fn quoted_strings_parse_back() {
+        let original = r#"Say "hi" \o/"#;
+        let parsed = wp::spa::SpaJson::from_string(&quote(original)).parse_str().map(|x| x.to_string());
+        assert_eq!(parsed.as_deref(), Some(original));
+    }
+}
@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use wireplumber as wp;
+use wp::spa::SpaPod;
+
+/// Renders a param pod as indented text, roughly the way pw-dump shows it.
+pub(crate) fn describe(pod: &SpaPod) -> String {
+    let mut out = String::new();
+    write_pod(&mut out, pod, 0);
+    out
+}
+
+fn write_pod(out: &mut String, pod: &SpaPod, depth: usize) {
+    let indent = "  ".repeat(depth + 1);
+
+    if pod.is_object() {
+        out.push_str(&format!("{} {{\n", pod.type_name()));
+        for property in pod.iterator() {
+            if let Some((key, value)) = property.property() {
+                out.push_str(&format!("{indent}{key}: "));
+                write_pod(out, &value, depth + 1);
+                out.push('\n');
+            }
+        }
+        out.push_str(&format!("{}}}", "  ".repeat(depth)));
+    } else if pod.is_struct() || pod.is_array() {
+        out.push_str("[\n");
+        for item in pod.iterator() {
+            out.push_str(&indent);
+            write_pod(out, &item, depth + 1);
+            out.push('\n');
+        }
+        out.push_str(&format!("{}]", "  ".repeat(depth)));
+    } else if pod.is_choice() {
+        out.push_str("Choice of ");
+        match pod.choice_child() {
+            Some(child) => write_pod(out, &child, depth),
+            None => out.push('?'),
+        }
+    } else if let Some(value) = pod.boolean() {
+        out.push_str(&value.to_string());
+    } else if let Some(value) = pod.id() {
+        out.push_str(&format!("Id {value}"));
+    } else if let Some(value) = pod.int() {
+        out.push_str(&value.to_string());
+    } else if let Some(value) = pod.long() {
+        out.push_str(&value.to_string());
+    } else if let Some(value) = pod.float() {
+        out.push_str(&value.to_string());
+    } else if let Some(value) = pod.double() {
+        out.push_str(&value.to_string());
+    } else if let Some(value) = pod.string() {
+        out.push_str(&format!("\"{value}\""));
+    } else {
+        out.push_str(&format!("<{}>", pod.type_name()));
+    }
+}
@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::f32::consts::PI;
+
+const TONE_FREQUENCY: f32 = 440.0;
+/// Test signals play at -12 dBFS to not startle anyone.
+const AMPLITUDE: f32 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TestSignal {
+    #[default]
+    Tone,
+    /// Pink noise, sounds alike on every kind of speaker.
+    Noise,
+}
+
+/// Generates a test signal on a single channel of interleaved audio, the other channels stay silent.
+#[derive(Debug)]
+pub struct SignalGenerator {
+    rate: f32,
+    channels: usize,
+    channel: Option<usize>,
+    signal: TestSignal,
+    phase: f32,
+    rng: u32,
+    /// State of the pink noise filter.
+    pink: [f32; 7],
+}
+
+impl SignalGenerator {
+    pub fn new(rate: u32, channels: usize) -> Self {
+        Self {
+            rate: rate as f32,
+            channels,
+            channel: None,
+            signal: TestSignal::default(),
+            phase: 0.0,
+            rng: 0x2545f491,
+            pink: [0.0; 7],
+        }
+    }
+
+    /// Selects the channel to play on, `None` for silence.
+    pub fn set_channel(&mut self, channel: Option<usize>) {
+        self.channel = channel.filter(|x| *x < self.channels);
+    }
+
+    pub fn set_signal(&mut self, signal: TestSignal) {
+        self.signal = signal;
+    }
+
+    /// Fills `samples` with whole frames.
+    pub fn fill(&mut self, samples: &mut [f32]) {
+        samples.fill(0.0);
+        let Some(channel) = self.channel else {
+            return;
+        };
+        for frame in samples.chunks_exact_mut(self.channels) {
+            frame[channel] = match self.signal {
+                TestSignal::Tone => self.tone(),
+                TestSignal::Noise => self.noise(),
+            };
+        }
+    }
+
+    fn tone(&mut self) -> f32 {
+        let sample = AMPLITUDE * (2.0 * PI * self.phase).sin();
+        self.phase = (self.phase + TONE_FREQUENCY / self.rate).fract();
+        sample
+    }
+
+    /// Paul Kellet's refined pink noise filter over xorshift white noise.
+    fn noise(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        let white = self.rng as f32 / u32::MAX as f32 * 2.0 - 1.0;
+
+        let b = &mut self.pink;
+        b[0] = 0.99886 * b[0] + white * 0.0555179;
+        b[1] = 0.99332 * b[1] + white * 0.0750759;
+        b[2] = 0.96900 * b[2] + white * 0.1538520;
+        b[3] = 0.86650 * b[3] + white * 0.3104856;
+        b[4] = 0.55000 * b[4] + white * 0.5329522;
+        b[5] = -0.7616 * b[5] - white * 0.0168980;
+        let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+        b[6] = white * 0.115926;
+
+        // The filter gains about 11 dB.
+        (pink * AMPLITUDE * 0.28).clamp(-1.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plays_selected_channel_only() {
+        let mut generator = SignalGenerator::new(48000, 3);
+        let mut samples = vec![1.0; 48000 * 3];
+        generator.fill(&mut samples);
+        assert!(samples.iter().all(|x| *x == 0.0));
+
+        for signal in [TestSignal::Tone, TestSignal::Noise] {
+            generator.set_signal(signal);
+            generator.set_channel(Some(1));
+            generator.fill(&mut samples);
+            let channel = |n: usize| samples.iter().skip(n).step_by(3).copied();
+            assert!(channel(0).chain(channel(2)).all(|x| x == 0.0));
+            // Both signals are about equally loud.
+            let rms = (channel(1).map(|x| x * x).sum::<f32>() / 48000.0).sqrt();
+            assert!((rms - AMPLITUDE / 2f32.sqrt()).abs() < 0.06, "{signal:?} {rms}");
+        }
+    }
+}
@@ -0,0 +1,136 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::f32::consts::PI;
+
+/// Sample rate the spectrum analysis runs at.
+pub const SPECTRUM_RATE: u32 = 48000;
+/// FFT length, giving bins about 12 Hz apart at [`SPECTRUM_RATE`].
+pub const SPECTRUM_SIZE: usize = 4096;
+/// A new spectrum is computed every this many samples.
+const HOP: usize = SPECTRUM_SIZE / 2;
+
+/// Computes the magnitude spectrum of the most recent [`SPECTRUM_SIZE`] samples of a mono signal.
+#[derive(Debug)]
+pub struct SpectrumAnalyzer {
+    window: Vec<f32>,
+    /// Ring buffer of the latest samples, `pos` being the oldest.
+    samples: Vec<f32>,
+    pos: usize,
+    since_last: usize,
+}
+
+impl Default for SpectrumAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpectrumAnalyzer {
+    pub fn new() -> Self {
+        // Hann window
+        let window = (0..SPECTRUM_SIZE).map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / SPECTRUM_SIZE as f32).cos()).collect();
+        Self {
+            window,
+            samples: vec![0.0; SPECTRUM_SIZE],
+            pos: 0,
+            since_last: 0,
+        }
+    }
+
+    /// Feeds samples, returns whether a new spectrum is due.
+    pub fn process(&mut self, samples: &[f32]) -> bool {
+        for sample in samples {
+            self.samples[self.pos] = *sample;
+            self.pos = (self.pos + 1) % SPECTRUM_SIZE;
+        }
+        self.since_last += samples.len();
+        if self.since_last >= HOP {
+            self.since_last %= HOP;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Level of each of the [`SPECTRUM_SIZE`] / 2 bins in dBFS, a full scale sine reading 0 dB.
+    pub fn spectrum(&self) -> Vec<f32> {
+        let mut re: Vec<f32> = (0..SPECTRUM_SIZE).map(|i| self.samples[(self.pos + i) % SPECTRUM_SIZE] * self.window[i]).collect();
+        let mut im = vec![0.0; SPECTRUM_SIZE];
+        fft(&mut re, &mut im);
+
+        // The Hann window halves the amplitude, a real sine splits over both halves of the spectrum.
+        let scale = 4.0 / SPECTRUM_SIZE as f32;
+        re.iter()
+            .zip(im.iter())
+            .take(SPECTRUM_SIZE / 2)
+            .map(|(re, im)| {
+                let magnitude = (re * re + im * im).sqrt() * scale;
+                20.0 * magnitude.max(1e-10).log10()
+            })
+            .collect()
+    }
+}
+
+/// Frequency at the center of `bin`.
+pub fn bin_frequency(bin: usize) -> f32 {
+    bin as f32 * SPECTRUM_RATE as f32 / SPECTRUM_SIZE as f32
+}
+
+/// In-place radix-2 FFT, the length must be a power of two.
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    debug_assert!(n.is_power_of_two() && im.len() == n);
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (w_im, w_re) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_sine() {
+        let bin = 100;
+        let frequency = bin_frequency(bin);
+        let samples: Vec<f32> = (0..SPECTRUM_SIZE).map(|i| 0.5 * (2.0 * PI * frequency * i as f32 / SPECTRUM_RATE as f32).sin()).collect();
+
+        let mut analyzer = SpectrumAnalyzer::new();
+        assert!(analyzer.process(&samples));
+        let spectrum = analyzer.spectrum();
+
+        assert_eq!(spectrum.len(), SPECTRUM_SIZE / 2);
+        assert!((spectrum[bin] + 6.02).abs() < 0.1, "{}", spectrum[bin]);
+        assert!(spectrum[bin + 10] < -60.0);
+        assert!(spectrum[bin - 10] < -60.0);
+    }
+}
@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::spajson;
+use crate::macros::*;
+use gtk::glib;
+use std::path::PathBuf;
+use wireplumber as wp;
+
+const NODE_NAME_PREFIX: &str = "pwvucontrol";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VirtualDeviceKind {
+    NullSink,
+    VirtualSource,
+    Loopback,
+}
+
+impl VirtualDeviceKind {
+    pub const ALL: [VirtualDeviceKind; 3] = [VirtualDeviceKind::NullSink, VirtualDeviceKind::VirtualSource, VirtualDeviceKind::Loopback];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            VirtualDeviceKind::NullSink => "null-sink",
+            VirtualDeviceKind::VirtualSource => "virtual-source",
+            VirtualDeviceKind::Loopback => "loopback",
+        }
+    }
+
+    fn from_str(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == value)
+    }
+}
+
+/// A sink, source or loopback created by us.
+///
+/// Null sinks and virtual sources are created through the adapter factory and linger on the
+/// server, loopbacks are modules loaded into our own process and go away when we exit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VirtualDevice {
+    /// node.name of the created node, or the prefix of the loopback's capture and playback nodes.
+    pub name: String,
+    pub description: String,
+    pub kind: VirtualDeviceKind,
+}
+
+impl VirtualDevice {
+    /// Creates a device with a node name derived from the description that doesn't clash with `existing`.
+    pub fn new(kind: VirtualDeviceKind, description: &str, existing: &[VirtualDevice]) -> Self {
+        let slug: String = description
+            .trim()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+            .collect();
+        let base = format!("{NODE_NAME_PREFIX}.{}.{slug}", kind.as_str());
+
+        let mut name = base.clone();
+        let mut n = 2;
+        while existing.iter().any(|x| x.name == name) {
+            name = format!("{base}_{n}");
+            n += 1;
+        }
+
+        Self { name, description: description.trim().to_string(), kind }
+    }
+
+    /// Properties for the adapter factory, not used for loopbacks.
+    pub(crate) fn node_properties(&self) -> wp::pw::Properties {
+        let props = wp::pw::Properties::new_empty();
+        props.set("factory.name", Some("support.null-audio-sink"));
+        props.set("node.name", Some(&self.name));
+        props.set("node.description", Some(&self.description));
+        props.set("audio.position", Some("FL,FR"));
+        props.set("monitor.channel-volumes", Some("true"));
+        props.set("object.linger", Some("true"));
+        props.set(
+            "media.class",
+            Some(match self.kind {
+                VirtualDeviceKind::VirtualSource => "Audio/Source/Virtual",
+                _ => "Audio/Sink",
+            }),
+        );
+        props
+    }
+
+    /// Arguments for libpipewire-module-loopback.
+    pub(crate) fn loopback_arguments(&self) -> String {
+        format!(
+            "{{ node.description = {} capture.props = {{ node.name = {} }} playback.props = {{ node.name = {} }} }}",
+            spajson::quote(&self.description),
+            spajson::quote(&format!("{}.capture", self.name)),
+            spajson::quote(&format!("{}.playback", self.name))
+        )
+    }
+
+    pub fn to_key_file(devices: &[VirtualDevice]) -> glib::KeyFile {
+        let keyfile = glib::KeyFile::new();
+        for device in devices.iter() {
+            keyfile.set_string(&device.name, "kind", device.kind.as_str());
+            keyfile.set_string(&device.name, "description", &device.description);
+        }
+        keyfile
+    }
+
+    pub fn from_key_file(keyfile: &glib::KeyFile) -> Vec<VirtualDevice> {
+        keyfile
+            .groups()
+            .iter()
+            .filter_map(|group| {
+                let kind = keyfile.string(group, "kind").ok().and_then(|x| VirtualDeviceKind::from_str(&x));
+                let Some(kind) = kind else {
+                    pwvucontrol_warning!("Ignoring virtual device {group} of unknown kind");
+                    return None;
+                };
+                Some(VirtualDevice {
+                    name: group.to_string(),
+                    description: keyfile.string(group, "description").map(|x| x.to_string()).unwrap_or_else(|_| group.to_string()),
+                    kind,
+                })
+            })
+            .collect()
+    }
+
+    pub fn load_all() -> Vec<VirtualDevice> {
+        let keyfile = glib::KeyFile::new();
+        match keyfile.load_from_file(Self::path(), glib::KeyFileFlags::NONE) {
+            Ok(()) => Self::from_key_file(&keyfile),
+            Err(e) if e.matches(glib::FileError::Noent) => Vec::new(),
+            Err(e) => {
+                pwvucontrol_warning!("Cannot load virtual devices: {e}");
+                Vec::new()
+            }
+        }
+    }
+
+    pub fn save_all(devices: &[VirtualDevice]) {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                pwvucontrol_warning!("Cannot create config directory: {e}");
+            }
+        }
+        if let Err(e) = Self::to_key_file(devices).save_to_file(path) {
+            pwvucontrol_warning!("Cannot save virtual devices: {e}");
+        }
+    }
+
+    fn path() -> PathBuf {
+        glib::user_config_dir().join("pwvucontrol").join("virtual-devices.conf")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_names_are_unique_and_sanitized() {
+        let first = VirtualDevice::new(VirtualDeviceKind::NullSink, " Game / Chat ", &[]);
+        assert_eq!(first.name, "pwvucontrol.null-sink.game___chat");
+        assert_eq!(first.description, "Game / Chat");
+
+        let second = VirtualDevice::new(VirtualDeviceKind::NullSink, "Game / Chat", std::slice::from_ref(&first));
+        assert_eq!(second.name, "pwvucontrol.null-sink.game___chat_2");
+    }
+
+    #[test]
+    fn devices_survive_key_file_round_trip() {
+        let devices = vec![
+            VirtualDevice::new(VirtualDeviceKind::NullSink, "Stream mix", &[]),
+            VirtualDevice::new(VirtualDeviceKind::VirtualSource, "Virtual mic", &[]),
+            VirtualDevice::new(VirtualDeviceKind::Loopback, "Monitor \"me\"", &[]),
+        ];
+
+        let keyfile = VirtualDevice::to_key_file(&devices);
+        let parsed = glib::KeyFile::new();
+        parsed.load_from_data(&keyfile.to_data(), glib::KeyFileFlags::NONE).expect("valid key file");
+
+        assert_eq!(VirtualDevice::from_key_file(&parsed), devices);
+    }
+
+    #[test]
+    fn loopback_arguments_are_escaped() {
+        let device = VirtualDevice::new(VirtualDeviceKind::Loopback, "Say \"hi\"", &[]);
+        assert_eq!(
+            device.loopback_arguments(),
+            "{ node.description = \"Say \\\"hi\\\"\" capture.props = { node.name = \"pwvucontrol.loopback.say__hi_.capture\" } playback.props = { node.name = \"pwvucontrol.loopback.say__hi_.playback\" } }"
+        );
+    }
+}
@@ -0,0 +1,350 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{
+    backend::{NodeType, PwDeviceObject, PwNodeObject, PwProfileObject, PwvucontrolManager},
+    config::APP_ID,
+    ui::MAX_OVERAMPLIFIED_VOLUME,
+};
+use anyhow::{anyhow, bail, Context};
+use glib::clone;
+use gtk::{gio, prelude::*};
+use std::time::Duration;
+use wireplumber as wp;
+use wp::pw::{PipewireObjectExt2, ProxyExt};
+
+/// How long to wait for the initial objects before giving up.
+const READY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub const USAGE: &str = "\
+Commands:
+  list                              List sinks, sources, streams and cards
+  set-volume <node> <volume>        Set volume, e.g. 80%, +5% or -5%
+  mute <node> [on|off|toggle]       Change mute state (default: toggle)
+  move-stream <stream> <target>     Move a stream to a sink/source, or \"default\"
+  set-default <node>                Make a sink or source the default
+  set-profile <card> <profile>      Switch card profile by index or description
+
+Nodes and cards can be given by id, name or description.
+@DEFAULT_SINK@ and @DEFAULT_SOURCE@ refer to the current defaults.";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VolumeChange {
+    /// Slider position as shown in the GUI, 1.0 is 100%.
+    Absolute(f32),
+    Relative(f32),
+}
+
+impl VolumeChange {
+    /// Percentages follow the volume slider, which is cubic, so the result is converted back to linear.
+    /// Like the slider, the result stops at `max_volume`, a slider position.
+    pub fn apply(&self, linear_volume: f32, max_volume: f32) -> f32 {
+        let cubic = match *self {
+            VolumeChange::Absolute(value) => value,
+            VolumeChange::Relative(delta) => linear_volume.cbrt() + delta,
+        };
+        cubic.clamp(0.0, max_volume).powi(3)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MuteChange {
+    On,
+    Off,
+    Toggle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    List,
+    SetVolume { node: String, volume: VolumeChange },
+    Mute { node: String, change: MuteChange },
+    MoveStream { stream: String, target: Option<String> },
+    SetDefault { node: String },
+    SetProfile { card: String, profile: String },
+}
+
+pub fn parse_volume(value: &str) -> anyhow::Result<VolumeChange> {
+    let number = value.strip_suffix('%').ok_or_else(|| anyhow!("Volume must be a percentage, e.g. 80%"))?;
+    let parsed: f32 = number.parse().with_context(|| format!("Invalid volume \"{value}\""))?;
+    if !parsed.is_finite() {
+        bail!("Invalid volume \"{value}\"");
+    }
+
+    if number.starts_with(['+', '-']) {
+        Ok(VolumeChange::Relative(parsed / 100.0))
+    } else {
+        Ok(VolumeChange::Absolute(parsed / 100.0))
+    }
+}
+
+impl Command {
+    pub fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let arg = |n: usize| args.get(n).cloned().ok_or_else(|| anyhow!("Missing argument for \"{}\"\n\n{USAGE}", args[0]));
+
+        let command = match args.first().map(String::as_str) {
+            Some("list") => Command::List,
+            Some("set-volume") => Command::SetVolume { node: arg(1)?, volume: parse_volume(&arg(2)?)? },
+            Some("mute") => {
+                let change = match args.get(2).map(String::as_str) {
+                    None | Some("toggle") => MuteChange::Toggle,
+                    Some("on" | "1" | "true") => MuteChange::On,
+                    Some("off" | "0" | "false") => MuteChange::Off,
+                    Some(other) => bail!("Invalid mute state \"{other}\""),
+                };
+                Command::Mute { node: arg(1)?, change }
+            }
+            Some("move-stream") => {
+                let target = arg(2)?;
+                Command::MoveStream { stream: arg(1)?, target: (target != "default").then_some(target) }
+            }
+            Some("set-default") => Command::SetDefault { node: arg(1)? },
+            Some("set-profile") => Command::SetProfile { card: arg(1)?, profile: arg(2)? },
+            Some(other) => bail!("Unknown command \"{other}\"\n\n{USAGE}"),
+            None => bail!("{USAGE}"),
+        };
+
+        if args.len() > Self::max_args(&command) {
+            bail!("Too many arguments for \"{}\"\n\n{USAGE}", args[0]);
+        }
+
+        Ok(command)
+    }
+
+    fn max_args(command: &Command) -> usize {
+        match command {
+            Command::List => 1,
+            Command::SetDefault { .. } => 2,
+            _ => 3,
+        }
+    }
+
+    pub fn run(&self, manager: &PwvucontrolManager) -> anyhow::Result<()> {
+        match self {
+            Command::List => list(manager),
+            Command::SetVolume { node, volume } => {
+                let node = find_node(manager, node)?;
+                let max_volume = if gio::Settings::new(APP_ID).boolean("enable-overamplification") { MAX_OVERAMPLIFIED_VOLUME as f32 } else { 1.0 };
+                node.set_volume(volume.apply(node.volume(), max_volume));
+            }
+            Command::Mute { node, change } => {
+                let node = find_node(manager, node)?;
+                node.set_mute(match change {
+                    MuteChange::On => true,
+                    MuteChange::Off => false,
+                    MuteChange::Toggle => !node.mute(),
+                });
+            }
+            Command::MoveStream { stream, target } => {
+                let stream = find_node(manager, stream)?;
+                if !matches!(stream.nodetype(), NodeType::StreamInput | NodeType::StreamOutput) {
+                    bail!("\"{}\" is not a stream", stream.name());
+                }
+                match target {
+                    Some(target) => stream.set_default_target(&find_node(manager, target)?),
+                    None => stream.unset_default_target(),
+                }
+            }
+            Command::SetDefault { node } => {
+                let node = find_node(manager, node)?;
+                if !manager.set_default_configured_node(&node, true) {
+                    bail!("Unable to make \"{}\" the default", node.name());
+                }
+            }
+            Command::SetProfile { card, profile } => {
+                let device = find_device(manager, card)?;
+                let profile = device
+                    .profilemodel()
+                    .iter::<PwProfileObject>()
+                    .map_while(Result::ok)
+                    .find(|x| x.index().to_string() == *profile || x.description().eq_ignore_ascii_case(profile))
+                    .ok_or_else(|| anyhow!("No profile \"{profile}\" on \"{}\"", device.name().unwrap_or_default()))?;
+                device.set_profile(profile.index() as i32);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn volume_percent(volume: f32) -> f32 {
+    volume.cbrt() * 100.0
+}
+
+fn list(manager: &PwvucontrolManager) {
+    let default_sink = manager.default_configured_sink_node().map(|x| x.boundid());
+    let default_source = manager.default_configured_source_node().map(|x| x.boundid());
+
+    for (title, nodetype) in [
+        ("Sinks", NodeType::Sink),
+        ("Sources", NodeType::Source),
+        ("Playback streams", NodeType::StreamOutput),
+        ("Recording streams", NodeType::StreamInput),
+    ] {
+        println!("{title}:");
+        for node in manager.get_model_for_nodetype(nodetype).iter::<PwNodeObject>().map_while(Result::ok) {
+            let is_default = [default_sink, default_source].contains(&Some(node.boundid()));
+            let target = node.default_target().map(|x| format!(" -> {}", x.name())).unwrap_or_default();
+            println!(
+                " {} {:>4}. {} [{}] {:.0}%{}{}",
+                if is_default { '*' } else { ' ' },
+                node.boundid(),
+                node.name(),
+                node.node_property::<String>("node.name").unwrap_or_default(),
+                volume_percent(node.volume()),
+                if node.mute() { " MUTED" } else { "" },
+                target,
+            );
+        }
+        println!();
+    }
+
+    println!("Cards:");
+    for device in manager.device_model().iter::<PwDeviceObject>().map_while(Result::ok) {
+        println!("   {:>4}. {}", device.wpdevice().bound_id(), device.name().unwrap_or_default());
+        let current = device.current_profile();
+        for profile in device.profilemodel().iter::<PwProfileObject>().map_while(Result::ok) {
+            let marker = if current == Some(profile.index()) { '*' } else { ' ' };
+            println!("        {marker} {:>3}. {}", profile.index(), profile.description());
+        }
+    }
+}
+
+fn find_node(manager: &PwvucontrolManager, spec: &str) -> anyhow::Result<PwNodeObject> {
+    let node = match spec {
+        "@DEFAULT_SINK@" => manager.default_configured_sink_node(),
+        "@DEFAULT_SOURCE@" => manager.default_configured_source_node(),
+        _ => {
+            let nodes: Vec<PwNodeObject> = manager.node_model().iter::<PwNodeObject>().map_while(Result::ok).collect();
+            nodes
+                .iter()
+                .find(|node| node.boundid().to_string() == spec)
+                .or_else(|| nodes.iter().find(|node| node.node_property::<String>("node.name").as_deref() == Some(spec)))
+                .or_else(|| nodes.iter().find(|node| node.name().eq_ignore_ascii_case(spec)))
+                .cloned()
+        }
+    };
+
+    node.ok_or_else(|| anyhow!("No node matching \"{spec}\""))
+}
+
+fn find_device(manager: &PwvucontrolManager, spec: &str) -> anyhow::Result<PwDeviceObject> {
+    let devices: Vec<PwDeviceObject> = manager.device_model().iter::<PwDeviceObject>().map_while(Result::ok).collect();
+    devices
+        .iter()
+        .find(|device| device.wpdevice().bound_id().to_string() == spec)
+        .or_else(|| devices.iter().find(|device| device.wpdevice().pw_property::<String>("device.name").is_ok_and(|name| name == spec)))
+        .or_else(|| devices.iter().find(|device| device.name().is_some_and(|name| name.eq_ignore_ascii_case(spec))))
+        .cloned()
+        .ok_or_else(|| anyhow!("No card matching \"{spec}\""))
+}
+
+/// Runs a command without creating a window, iterating the main context until it's done.
+pub fn run_headless(manager: &PwvucontrolManager, command: Command) -> glib::ExitCode {
+    let main_loop = glib::MainLoop::new(None, false);
+    let result = std::rc::Rc::new(std::cell::RefCell::new(Err(anyhow!("Timed out waiting for PipeWire"))));
+
+    glib::MainContext::default().spawn_local(clone!(
+        #[strong] manager,
+        #[strong] main_loop,
+        #[strong] result,
+        async move {
+            if !manager.ready() {
+                let (sender, receiver) = futures::channel::oneshot::channel::<()>();
+                let sender = std::cell::Cell::new(Some(sender));
+                let handler = manager.connect_ready_notify(move |_| {
+                    if let Some(sender) = sender.take() {
+                        let _ = sender.send(());
+                    }
+                });
+                let ready = futures::future::select(receiver, glib::timeout_future(READY_TIMEOUT)).await;
+                manager.disconnect(handler);
+                if matches!(ready, futures::future::Either::Right(_)) {
+                    main_loop.quit();
+                    return;
+                }
+            }
+
+            let outcome = command.run(&manager);
+            if outcome.is_ok() {
+                // Make sure the server has processed our requests before exiting.
+                if let Err(e) = manager.wp_core().sync_future().await {
+                    result.replace(Err(e.into()));
+                    main_loop.quit();
+                    return;
+                }
+            }
+            result.replace(outcome);
+            main_loop.quit();
+        }
+    ));
+
+    main_loop.run();
+
+    match result.replace(Ok(())) {
+        Ok(()) => glib::ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            glib::ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_volumes() {
+        assert_eq!(parse_volume("80%").unwrap(), VolumeChange::Absolute(0.8));
+        assert_eq!(parse_volume("+5%").unwrap(), VolumeChange::Relative(0.05));
+        assert_eq!(parse_volume("-5%").unwrap(), VolumeChange::Relative(-0.05));
+        assert!(parse_volume("80").is_err());
+        assert!(parse_volume("loud%").is_err());
+        assert!(parse_volume("inf%").is_err());
+    }
+
+    #[test]
+    fn volume_changes_follow_the_cubic_slider() {
+        assert!((VolumeChange::Absolute(0.5).apply(1.0, 1.0) - 0.125).abs() < 1e-6);
+        assert!((VolumeChange::Relative(0.5).apply(0.125, 1.0) - 1.0).abs() < 1e-6);
+        assert_eq!(VolumeChange::Relative(-2.0).apply(1.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn volume_changes_stop_at_the_slider_end() {
+        assert_eq!(parse_volume("1000%").unwrap().apply(0.0, 1.0), 1.0);
+        assert_eq!(parse_volume("+50%").unwrap().apply(1.0, 1.0), 1.0);
+        assert!((parse_volume("120%").unwrap().apply(0.0, 1.525) - 1.728).abs() < 1e-5);
+        assert!((parse_volume("1000%").unwrap().apply(0.0, 1.525) - 1.525f32.powi(3)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(Command::parse(&args("list")).unwrap(), Command::List);
+        assert_eq!(
+            Command::parse(&args("set-volume 42 80%")).unwrap(),
+            Command::SetVolume { node: "42".to_string(), volume: VolumeChange::Absolute(0.8) }
+        );
+        assert_eq!(Command::parse(&args("mute 42")).unwrap(), Command::Mute { node: "42".to_string(), change: MuteChange::Toggle });
+        assert_eq!(Command::parse(&args("mute 42 off")).unwrap(), Command::Mute { node: "42".to_string(), change: MuteChange::Off });
+        assert_eq!(Command::parse(&args("move-stream 50 default")).unwrap(), Command::MoveStream { stream: "50".to_string(), target: None });
+        assert_eq!(
+            Command::parse(&args("move-stream 50 @DEFAULT_SINK@")).unwrap(),
+            Command::MoveStream { stream: "50".to_string(), target: Some("@DEFAULT_SINK@".to_string()) }
+        );
+        assert_eq!(
+            Command::parse(&args("set-profile 40 3")).unwrap(),
+            Command::SetProfile { card: "40".to_string(), profile: "3".to_string() }
+        );
+
+        assert!(Command::parse(&args("")).is_err());
+        assert!(Command::parse(&args("frobnicate")).is_err());
+        assert!(Command::parse(&args("set-volume 42")).is_err());
+        assert!(Command::parse(&args("mute 42 maybe")).is_err());
+        assert!(Command::parse(&args("list extra")).is_err());
+    }
+}
@@ -0,0 +1,335 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::backend::{NodeType, PwDeviceObject, PwNodeObject, PwvucontrolManager};
+use crate::pwvucontrol_warning;
+use glib::clone;
+use gtk::{gio, prelude::*};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wireplumber as wp;
+use wp::pw::{PipewireObjectExt2, ProxyExt};
+
+pub const INTERFACE_NAME: &str = "org.saivert.pwvucontrol";
+
+const ERROR_NOT_FOUND: &str = "org.saivert.pwvucontrol.Error.NotFound";
+const ERROR_FAILED: &str = "org.saivert.pwvucontrol.Error.Failed";
+const ERROR_INVALID_ARGS: &str = "org.freedesktop.DBus.Error.InvalidArgs";
+
+/// Volumes are slider positions as shown in the window, 1.0 being 100%.
+/// Node types are one of "sink", "source", "playback" and "recording".
+/// A stream target of 0 moves the stream back to the default node.
+const INTROSPECTION_XML: &str = r#"
+<node>
+  <interface name="org.saivert.pwvucontrol">
+    <method name="ListNodes">
+      <arg type="a(ussdbs)" name="nodes" direction="out"/>
+    </method>
+    <method name="ListDevices">
+      <arg type="a(usu)" name="devices" direction="out"/>
+    </method>
+    <method name="GetVolume">
+      <arg type="u" name="id" direction="in"/>
+      <arg type="d" name="volume" direction="out"/>
+    </method>
+    <method name="SetVolume">
+      <arg type="u" name="id" direction="in"/>
+      <arg type="d" name="volume" direction="in"/>
+    </method>
+    <method name="GetMute">
+      <arg type="u" name="id" direction="in"/>
+      <arg type="b" name="mute" direction="out"/>
+    </method>
+    <method name="SetMute">
+      <arg type="u" name="id" direction="in"/>
+      <arg type="b" name="mute" direction="in"/>
+    </method>
+    <method name="GetDefaults">
+      <arg type="u" name="sink" direction="out"/>
+      <arg type="u" name="source" direction="out"/>
+    </method>
+    <method name="SetDefault">
+      <arg type="u" name="id" direction="in"/>
+    </method>
+    <method name="MoveStream">
+      <arg type="u" name="stream" direction="in"/>
+      <arg type="u" name="target" direction="in"/>
+    </method>
+    <signal name="NodeAdded">
+      <arg type="u" name="id"/>
+    </signal>
+    <signal name="NodeRemoved">
+      <arg type="u" name="id"/>
+    </signal>
+    <signal name="VolumeChanged">
+      <arg type="u" name="id"/>
+      <arg type="d" name="volume"/>
+      <arg type="b" name="mute"/>
+    </signal>
+    <signal name="DefaultsChanged">
+      <arg type="u" name="sink"/>
+      <arg type="u" name="source"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+type MethodResult = Result<Option<glib::Variant>, (&'static str, String)>;
+
+fn nodetype_name(nodetype: NodeType) -> &'static str {
+    match nodetype {
+        NodeType::Sink => "sink",
+        NodeType::Source => "source",
+        NodeType::StreamOutput => "playback",
+        NodeType::StreamInput => "recording",
+        _ => "unknown",
+    }
+}
+
+fn slider_volume(node: &PwNodeObject) -> f64 {
+    node.volume().cbrt() as f64
+}
+
+/// A node in the node model with the handlers that forward its volume changes.
+struct WatchedNode {
+    node: PwNodeObject,
+    handlers: Vec<glib::SignalHandlerId>,
+}
+
+impl WatchedNode {
+    fn unwatch(self) -> PwNodeObject {
+        for handler in self.handlers {
+            self.node.disconnect(handler);
+        }
+        self.node
+    }
+}
+
+/// Exports the control interface and forwards changes in the manager's models as signals.
+pub struct DBusInterface {
+    registration_id: gio::RegistrationId,
+    manager: PwvucontrolManager,
+    handlers: Vec<glib::SignalHandlerId>,
+    manager_handlers: Vec<glib::SignalHandlerId>,
+    watched: Rc<RefCell<Vec<WatchedNode>>>,
+}
+
+impl DBusInterface {
+    pub fn register(connection: &gio::DBusConnection, object_path: &str, manager: &PwvucontrolManager) -> Result<Self, glib::Error> {
+        let node_info = gio::DBusNodeInfo::for_xml(INTROSPECTION_XML)?;
+        let interface_info = node_info.lookup_interface(INTERFACE_NAME).expect("Interface in introspection data");
+
+        let registration_id = connection
+            .register_object(object_path, &interface_info)
+            .method_call(clone!(
+                #[weak] manager,
+                move |_, _, _, _, method, parameters, invocation| {
+                    match handle_method(&manager, method, &parameters) {
+                        Ok(value) => invocation.return_value(value.as_ref()),
+                        Err((name, message)) => invocation.return_dbus_error(name, &message),
+                    }
+                }
+            ))
+            .build()?;
+
+        let emit = {
+            let connection = connection.downgrade();
+            let object_path = object_path.to_string();
+            Rc::new(move |signal: &str, parameters: glib::Variant| {
+                let Some(connection) = connection.upgrade() else {
+                    return;
+                };
+                if let Err(e) = connection.emit_signal(None, &object_path, INTERFACE_NAME, signal, Some(&parameters)) {
+                    pwvucontrol_warning!("Cannot emit D-Bus signal {signal}: {e}");
+                }
+            })
+        };
+
+        let mut handlers = Vec::new();
+
+        // The removed items are gone by the time items-changed fires, so keep our own list of nodes.
+        let node_model = manager.node_model();
+        let watched: Rc<RefCell<Vec<WatchedNode>>> = Default::default();
+        let on_items_changed = clone!(
+            #[strong] emit,
+            #[strong] watched,
+            move |model: &gio::ListStore, position: u32, removed: u32, added: u32| {
+                let added_nodes: Vec<WatchedNode> =
+                    (position..position + added).filter_map(|i| model.item(i).and_downcast()).map(|node| watch_node(node, emit.clone())).collect();
+                let added_ids: Vec<(u32, bool)> = added_nodes.iter().map(|x| (x.node.boundid(), x.node.hidden())).collect();
+                let removed_nodes: Vec<WatchedNode> = watched.borrow_mut().splice(position as usize..(position + removed) as usize, added_nodes).collect();
+
+                for node in removed_nodes.into_iter().map(WatchedNode::unwatch) {
+                    if !node.hidden() {
+                        emit("NodeRemoved", (node.boundid(),).to_variant());
+                    }
+                }
+                for (id, hidden) in added_ids {
+                    if !hidden {
+                        emit("NodeAdded", (id,).to_variant());
+                    }
+                }
+            }
+        );
+        on_items_changed(&node_model, 0, 0, node_model.n_items());
+        handlers.push(node_model.connect_items_changed(on_items_changed));
+
+        let manager_handlers = ["default-sink", "default-source"]
+            .into_iter()
+            .map(|property| {
+                manager.connect_notify_local(
+                    Some(property),
+                    clone!(
+                        #[strong] emit,
+                        move |manager, _| emit("DefaultsChanged", defaults(manager).to_variant())
+                    ),
+                )
+            })
+            .collect();
+
+        Ok(Self { registration_id, manager: manager.clone(), handlers, manager_handlers, watched })
+    }
+
+    pub fn unregister(self, connection: &gio::DBusConnection) {
+        for handler in self.handlers {
+            self.manager.node_model().disconnect(handler);
+        }
+        for handler in self.manager_handlers {
+            self.manager.disconnect(handler);
+        }
+        for watched in self.watched.take() {
+            watched.unwatch();
+        }
+        if let Err(e) = connection.unregister_object(self.registration_id) {
+            pwvucontrol_warning!("Cannot unregister D-Bus object: {e}");
+        }
+    }
+}
+
+fn watch_node(node: PwNodeObject, emit: Rc<dyn Fn(&str, glib::Variant)>) -> WatchedNode {
+    let notify = move |node: &PwNodeObject| {
+        if !node.hidden() {
+            emit("VolumeChanged", (node.boundid(), slider_volume(node), node.mute()).to_variant());
+        }
+    };
+    let handlers = vec![node.connect_volume_notify(notify.clone()), node.connect_mute_notify(notify)];
+    WatchedNode { node, handlers }
+}
+
+fn defaults(manager: &PwvucontrolManager) -> (u32, u32) {
+    (
+        manager.default_configured_sink_node().map(|x| x.boundid()).unwrap_or_default(),
+        manager.default_configured_source_node().map(|x| x.boundid()).unwrap_or_default(),
+    )
+}
+
+fn find_node(manager: &PwvucontrolManager, id: u32) -> Result<PwNodeObject, (&'static str, String)> {
+    manager.get_node_by_id(id).ok_or_else(|| (ERROR_NOT_FOUND, format!("No node with id {id}")))
+}
+
+fn handle_method(manager: &PwvucontrolManager, method: &str, parameters: &glib::Variant) -> MethodResult {
+    fn args<T: FromVariant>(parameters: &glib::Variant) -> Result<T, (&'static str, String)> {
+        parameters.get::<T>().ok_or_else(|| (ERROR_INVALID_ARGS, format!("Unexpected arguments {}", parameters.type_())))
+    }
+
+    if !manager.ready() {
+        return Err((ERROR_FAILED, "Not connected to PipeWire yet".to_string()));
+    }
+
+    match method {
+        "ListNodes" => {
+            let nodes: Vec<(u32, String, String, f64, bool, String)> = manager
+                .node_model()
+                .iter::<PwNodeObject>()
+                .map_while(Result::ok)
+                .filter(|node| !node.hidden())
+                .map(|node| {
+                    (
+                        node.boundid(),
+                        node.node_property::<String>("node.name").unwrap_or_default(),
+                        node.name(),
+                        slider_volume(&node),
+                        node.mute(),
+                        nodetype_name(node.nodetype()).to_string(),
+                    )
+                })
+                .collect();
+            Ok(Some((nodes,).to_variant()))
+        }
+        "ListDevices" => {
+            let devices: Vec<(u32, String, u32)> = manager
+                .device_model()
+                .iter::<PwDeviceObject>()
+                .map_while(Result::ok)
+                .map(|device| {
+                    (
+                        device.wpdevice().bound_id(),
+                        device.wpdevice().pw_property::<String>("device.name").unwrap_or_default(),
+                        device.current_profile().unwrap_or(u32::MAX),
+                    )
+                })
+                .collect();
+            Ok(Some((devices,).to_variant()))
+        }
+        "GetVolume" => {
+            let (id,) = args::<(u32,)>(parameters)?;
+            Ok(Some((slider_volume(&find_node(manager, id)?),).to_variant()))
+        }
+        "SetVolume" => {
+            let (id, volume) = args::<(u32, f64)>(parameters)?;
+            if !volume.is_finite() || volume < 0.0 {
+                return Err((ERROR_INVALID_ARGS, format!("Invalid volume {volume}")));
+            }
+            find_node(manager, id)?.set_volume((volume as f32).powi(3));
+            Ok(None)
+        }
+        "GetMute" => {
+            let (id,) = args::<(u32,)>(parameters)?;
+            Ok(Some((find_node(manager, id)?.mute(),).to_variant()))
+        }
+        "SetMute" => {
+            let (id, mute) = args::<(u32, bool)>(parameters)?;
+            find_node(manager, id)?.set_mute(mute);
+            Ok(None)
+        }
+        "GetDefaults" => Ok(Some(defaults(manager).to_variant())),
+        "SetDefault" => {
+            let (id,) = args::<(u32,)>(parameters)?;
+            let node = find_node(manager, id)?;
+            if !manager.set_default_configured_node(&node, true) {
+                return Err((ERROR_FAILED, format!("Cannot make node {id} the default")));
+            }
+            Ok(None)
+        }
+        "MoveStream" => {
+            let (stream, target) = args::<(u32, u32)>(parameters)?;
+            let stream = find_node(manager, stream)?;
+            if !matches!(stream.nodetype(), NodeType::StreamInput | NodeType::StreamOutput) {
+                return Err((ERROR_INVALID_ARGS, format!("Node {} is not a stream", stream.boundid())));
+            }
+            match target {
+                0 => stream.unset_default_target(),
+                id => stream.set_default_target(&find_node(manager, id)?),
+            }
+            Ok(None)
+        }
+        _ => Err(("org.freedesktop.DBus.Error.UnknownMethod", format!("Unknown method {method}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn introspection_data_is_valid() {
+        let node_info = gio::DBusNodeInfo::for_xml(INTROSPECTION_XML).expect("valid introspection XML");
+        let interface = node_info.lookup_interface(INTERFACE_NAME).expect("interface present");
+
+        for method in ["ListNodes", "ListDevices", "GetVolume", "SetVolume", "GetMute", "SetMute", "GetDefaults", "SetDefault", "MoveStream"] {
+            assert!(interface.lookup_method(method).is_some(), "{method} missing");
+        }
+        for signal in ["NodeAdded", "NodeRemoved", "VolumeChanged", "DefaultsChanged"] {
+            assert!(interface.lookup_signal(signal).is_some(), "{signal} missing");
+        }
+    }
+}
@@ -31,6 +31,8 @@ log_topic! {
 
 mod application;
 mod backend;
+mod cli;
+mod dbus;
 mod ui;
 
 use std::{
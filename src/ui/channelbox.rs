@@ -1,6 +1,9 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use crate::{backend::PwChannelObject, ui::PwVolumeScale};
+use crate::{
+    backend::PwChannelObject,
+    ui::{PwPeakMeter, PwVolumeScale, PwvucontrolWindow},
+};
 use gtk::{prelude::*, subclass::prelude::*};
 use std::cell::RefCell;
 
@@ -19,6 +22,8 @@ mod imp {
         pub label: TemplateChild<gtk::Label>,
         #[template_child]
         pub scale: TemplateChild<PwVolumeScale>,
+        #[template_child]
+        pub peak_meter: TemplateChild<PwPeakMeter>,
     }
 
     #[glib::object_subclass]
@@ -28,6 +33,7 @@ mod imp {
         type ParentType = gtk::ListBoxRow;
 
         fn class_init(klass: &mut Self::Class) {
+            PwPeakMeter::ensure_type();
             klass.bind_template();
         }
 
@@ -50,6 +56,10 @@ mod imp {
                 .build();
 
             item.bind_property("name", &self.label.get(), "label").sync_create().build();
+
+            let settings = &PwvucontrolWindow::default().imp().settings;
+            self.peak_meter.bind_settings(settings);
+            settings.bind("peakmeter-per-channel", &self.peak_meter.get(), "visible").get_only().build();
         }
     }
     impl WidgetImpl for PwChannelBox {}
@@ -69,4 +79,8 @@ impl PwChannelBox {
     pub(crate) fn new(channelobj: &PwChannelObject) -> Self {
         glib::Object::builder().property("channel-object", channelobj).build()
     }
+
+    pub(crate) fn set_level(&self, level: f32) {
+        self.imp().peak_meter.set_level(level);
+    }
 }
@@ -0,0 +1,202 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::backend::{PwClockSettings, PwvucontrolManager};
+use adw::{prelude::*, subclass::prelude::*};
+use gettextrs::gettext;
+use glib::{clone, SignalHandlerId};
+use std::cell::{Cell, OnceCell, RefCell};
+
+const COMMON_RATES: [u32; 6] = [44100, 48000, 88200, 96000, 176400, 192000];
+const COMMON_QUANTA: [u32; 9] = [32, 64, 128, 256, 512, 1024, 2048, 4096, 8192];
+
+struct Rows {
+    page: adw::PreferencesPage,
+    defaults: adw::ActionRow,
+    force_rate: adw::ComboRow,
+    force_quantum: adw::ComboRow,
+    allowed_rates: adw::EntryRow,
+    min_quantum: adw::SpinRow,
+    max_quantum: adw::SpinRow,
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Default)]
+    pub struct PwClockSettingsWindow {
+        pub(super) rows: OnceCell<Rows>,
+        /// The values offered by the force rate and force quantum rows, after "Not forced".
+        pub(super) rate_choices: RefCell<Vec<u32>>,
+        pub(super) quantum_choices: RefCell<Vec<u32>>,
+        pub(super) handlers: RefCell<Vec<SignalHandlerId>>,
+        pub(super) block: Cell<bool>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwClockSettingsWindow {
+        const NAME: &'static str = "PwClockSettingsWindow";
+        type Type = super::PwClockSettingsWindow;
+        type ParentType = adw::Window;
+    }
+
+    impl ObjectImpl for PwClockSettingsWindow {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            obj.set_title(Some(&gettext("Sample Rate and Quantum")));
+            obj.set_default_size(450, -1);
+
+            let rows = Rows {
+                page: adw::PreferencesPage::new(),
+                defaults: adw::ActionRow::builder().title(gettext("Defaults")).build(),
+                force_rate: adw::ComboRow::builder().title(gettext("Force sample rate")).build(),
+                force_quantum: adw::ComboRow::builder()
+                    .title(gettext("Force quantum"))
+                    .subtitle(gettext("Buffer size in samples, lower means less latency"))
+                    .build(),
+                allowed_rates: adw::EntryRow::builder().title(gettext("Allowed sample rates")).show_apply_button(true).build(),
+                min_quantum: adw::SpinRow::with_range(16.0, 8192.0, 16.0),
+                max_quantum: adw::SpinRow::with_range(16.0, 8192.0, 16.0),
+            };
+            rows.min_quantum.set_title(&gettext("Minimum quantum"));
+            rows.max_quantum.set_title(&gettext("Maximum quantum"));
+
+            let forced = adw::PreferencesGroup::builder()
+                .title(gettext("Clock"))
+                .description(gettext("Forced values apply to the whole graph until they are reset"))
+                .build();
+            forced.add(&rows.defaults);
+            forced.add(&rows.force_rate);
+            forced.add(&rows.force_quantum);
+
+            let limits = adw::PreferencesGroup::builder().title(gettext("Limits")).build();
+            limits.add(&rows.allowed_rates);
+            limits.add(&rows.min_quantum);
+            limits.add(&rows.max_quantum);
+
+            rows.page.add(&forced);
+            rows.page.add(&limits);
+
+            let toolbar_view = adw::ToolbarView::new();
+            toolbar_view.add_top_bar(&adw::HeaderBar::new());
+            toolbar_view.set_content(Some(&rows.page));
+            obj.set_content(Some(&toolbar_view));
+
+            rows.force_rate.connect_selected_notify(clone!(#[weak(rename_to = widget)] self, move |row| {
+                if !widget.block.get() {
+                    let rate = choice(&widget.rate_choices.borrow(), row.selected());
+                    widget.settings().set_force_rate(rate);
+                }
+            }));
+            rows.force_quantum.connect_selected_notify(clone!(#[weak(rename_to = widget)] self, move |row| {
+                if !widget.block.get() {
+                    let quantum = choice(&widget.quantum_choices.borrow(), row.selected());
+                    widget.settings().set_force_quantum(quantum);
+                }
+            }));
+            rows.min_quantum.connect_value_notify(clone!(#[weak(rename_to = widget)] self, move |row| {
+                if !widget.block.get() {
+                    widget.settings().set_min_quantum(row.value() as u32);
+                }
+            }));
+            rows.max_quantum.connect_value_notify(clone!(#[weak(rename_to = widget)] self, move |row| {
+                if !widget.block.get() {
+                    widget.settings().set_max_quantum(row.value() as u32);
+                }
+            }));
+            rows.allowed_rates.connect_apply(clone!(#[weak(rename_to = widget)] self, move |row| {
+                let rates: Vec<u32> = row.text().split(|c: char| !c.is_ascii_digit()).filter_map(|x| x.parse().ok()).filter(|x| *x > 0).collect();
+                if rates.is_empty() {
+                    row.add_css_class("error");
+                } else {
+                    row.remove_css_class("error");
+                    widget.settings().set_allowed_rates(&rates);
+                }
+            }));
+
+            let _ = self.rows.set(rows);
+
+            let settings = self.settings();
+            let handler = settings.connect_notify_local(None, clone!(#[weak(rename_to = widget)] self, move |_, _| widget.update()));
+            self.handlers.borrow_mut().push(handler);
+            self.update();
+        }
+
+        fn dispose(&self) {
+            let settings = self.settings();
+            for handler in self.handlers.take() {
+                settings.disconnect(handler);
+            }
+        }
+    }
+
+    impl WidgetImpl for PwClockSettingsWindow {}
+    impl WindowImpl for PwClockSettingsWindow {}
+    impl AdwWindowImpl for PwClockSettingsWindow {}
+
+    impl PwClockSettingsWindow {
+        fn settings(&self) -> PwClockSettings {
+            PwvucontrolManager::default().clock_settings()
+        }
+
+        fn update(&self) {
+            let Some(rows) = self.rows.get() else {
+                return;
+            };
+            let settings = self.settings();
+            self.block.set(true);
+
+            rows.page.set_sensitive(settings.metadata().is_some());
+
+            rows.defaults.set_subtitle(&formatx::formatx!(gettext("{} Hz, quantum {}"), settings.rate(), settings.quantum()).unwrap_or_default());
+
+            let allowed = settings.allowed_rate_list();
+            let rate_choices = choices(COMMON_RATES.iter().chain(&allowed).copied(), settings.force_rate());
+            set_choices(&rows.force_rate, &rate_choices, settings.force_rate(), |rate| format!("{rate} Hz"));
+            self.rate_choices.replace(rate_choices);
+
+            let quantum_choices = choices(COMMON_QUANTA.iter().copied(), settings.force_quantum());
+            set_choices(&rows.force_quantum, &quantum_choices, settings.force_quantum(), |quantum| quantum.to_string());
+            self.quantum_choices.replace(quantum_choices);
+
+            if !rows.allowed_rates.has_css_class("error") {
+                rows.allowed_rates.set_text(&allowed.iter().map(u32::to_string).collect::<Vec<_>>().join(", "));
+            }
+            rows.min_quantum.set_value(settings.min_quantum() as f64);
+            rows.max_quantum.set_value(settings.max_quantum() as f64);
+
+            self.block.set(false);
+        }
+    }
+}
+
+fn choices(values: impl Iterator<Item = u32>, current: u32) -> Vec<u32> {
+    let mut choices: Vec<u32> = values.chain(Some(current)).filter(|x| *x > 0).collect();
+    choices.sort_unstable();
+    choices.dedup();
+    choices
+}
+
+/// Maps a combo row position back to a value, position 0 being "Not forced".
+fn choice(choices: &[u32], selected: u32) -> u32 {
+    selected.checked_sub(1).and_then(|i| choices.get(i as usize)).copied().unwrap_or_default()
+}
+
+fn set_choices(row: &adw::ComboRow, choices: &[u32], current: u32, label: impl Fn(u32) -> String) {
+    let labels: Vec<String> = Some(gettext("Not forced")).into_iter().chain(choices.iter().map(|x| label(*x))).collect();
+    row.set_model(Some(&gtk::StringList::new(&labels.iter().map(String::as_str).collect::<Vec<_>>())));
+    row.set_selected(choices.iter().position(|x| *x == current).map(|i| i as u32 + 1).unwrap_or_default());
+}
+
+glib::wrapper! {
+    pub struct PwClockSettingsWindow(ObjectSubclass<imp::PwClockSettingsWindow>)
+        @extends gtk::Widget, gtk::Window, adw::Window,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl PwClockSettingsWindow {
+    pub(crate) fn new() -> Self {
+        glib::Object::new()
+    }
+}
@@ -1,8 +1,14 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use crate::{backend::PwDeviceObject, ui::PwProfileDropDown};
+use crate::{
+    backend::{PwDeviceObject, RouteDirection},
+    ui::PwProfileDropDown,
+};
+use glib::clone;
 use gtk::{prelude::*, subclass::prelude::*};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+
+const NSEC_PER_MSEC: f64 = 1_000_000.0;
 
 mod imp {
     use super::*;
@@ -17,9 +23,34 @@ mod imp {
         pub label: TemplateChild<gtk::Label>,
         #[template_child]
         pub profile_dropdown: TemplateChild<PwProfileDropDown>,
+        #[template_child]
+        pub latency_button: TemplateChild<gtk::MenuButton>,
+        #[template_child]
+        pub output_latency_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub output_latency: TemplateChild<gtk::SpinButton>,
+        #[template_child]
+        pub input_latency_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub input_latency: TemplateChild<gtk::SpinButton>,
+        #[template_child]
+        pub bluetooth_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub codec_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub codec_dropdown: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub battery_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub battery_icon: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub battery_label: TemplateChild<gtk::Label>,
 
         #[property(get, set, construct_only)]
         pub deviceobject: RefCell<Option<PwDeviceObject>>,
+
+        pub(super) block_latency_signal: Cell<bool>,
+        pub(super) block_codec_signal: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -54,12 +85,104 @@ mod imp {
                 .build();
 
             self.profile_dropdown.set_deviceobject(obj.deviceobject());
+
+            for (spinbutton, direction) in [(&self.output_latency, RouteDirection::Output), (&self.input_latency, RouteDirection::Input)] {
+                spinbutton.connect_value_changed(clone!(#[weak(rename_to = widget)] self, #[weak] deviceobject, move |spinbutton| {
+                    if !widget.block_latency_signal.get() {
+                        deviceobject.set_latency_offset(direction, (spinbutton.value() * NSEC_PER_MSEC) as i64);
+                    }
+                }));
+            }
+            deviceobject.connect_latency_offset_output_notify(clone!(#[weak(rename_to = widget)] self, move |_| widget.update_latency_offsets()));
+            deviceobject.connect_latency_offset_input_notify(clone!(#[weak(rename_to = widget)] self, move |_| widget.update_latency_offsets()));
+            deviceobject.connect_local(
+                "post-update-route",
+                false,
+                clone!(#[weak(rename_to = widget)] self, #[upgrade_or] None, move |_| {
+                    widget.update_latency_offsets();
+
+                    None
+                }),
+            );
+            self.update_latency_offsets();
+
+            if deviceobject.is_bluetooth() {
+                self.codec_dropdown.connect_selected_notify(clone!(#[weak(rename_to = widget)] self, #[weak] deviceobject, move |dropdown| {
+                    if widget.block_codec_signal.get() {
+                        return;
+                    }
+                    if let Some((codec, _)) = deviceobject.codecs().get(dropdown.selected() as usize) {
+                        deviceobject.set_codec(*codec);
+                    }
+                }));
+                deviceobject.connect_local(
+                    "post-update-codecs",
+                    false,
+                    clone!(#[weak(rename_to = widget)] self, #[upgrade_or] None, move |_| {
+                        widget.update_codecs();
+
+                        None
+                    }),
+                );
+                deviceobject.connect_battery_notify(clone!(#[weak(rename_to = widget)] self, move |_| widget.update_battery()));
+                self.update_codecs();
+                self.update_battery();
+            }
         }
     }
     impl WidgetImpl for PwDeviceBox {}
     impl ListBoxRowImpl for PwDeviceBox {}
 
-    impl PwDeviceBox {}
+    impl PwDeviceBox {
+        fn update_latency_offsets(&self) {
+            let deviceobject = self.obj().deviceobject().expect("Device object");
+
+            let has_output = deviceobject.routemodel_output().n_items() > 0;
+            let has_input = deviceobject.routemodel_input().n_items() > 0;
+            self.output_latency_label.set_visible(has_output);
+            self.output_latency.set_visible(has_output);
+            self.input_latency_label.set_visible(has_input);
+            self.input_latency.set_visible(has_input);
+            self.latency_button.set_visible(has_output || has_input);
+
+            self.block_latency_signal.set(true);
+            self.output_latency.set_value(deviceobject.latency_offset_output() as f64 / NSEC_PER_MSEC);
+            self.input_latency.set_value(deviceobject.latency_offset_input() as f64 / NSEC_PER_MSEC);
+            self.block_latency_signal.set(false);
+        }
+
+        fn update_codecs(&self) {
+            let deviceobject = self.obj().deviceobject().expect("Device object");
+            let codecs = deviceobject.codecs();
+
+            self.block_codec_signal.set(true);
+            let descriptions: Vec<&str> = codecs.iter().map(|(_, description)| description.as_str()).collect();
+            self.codec_dropdown.set_model(Some(&gtk::StringList::new(&descriptions)));
+            if let Some(position) = codecs.iter().position(|(codec, _)| *codec == deviceobject.codec()) {
+                self.codec_dropdown.set_selected(position as u32);
+            }
+            self.block_codec_signal.set(false);
+
+            self.codec_label.set_visible(!codecs.is_empty());
+            self.codec_dropdown.set_visible(!codecs.is_empty());
+            self.update_bluetooth_visibility();
+        }
+
+        fn update_battery(&self) {
+            let battery = self.obj().deviceobject().expect("Device object").battery();
+
+            if battery >= 0 {
+                self.battery_icon.set_icon_name(Some(&format!("battery-level-{}-symbolic", (battery + 5) / 10 * 10)));
+                self.battery_label.set_label(&format!("{battery}%"));
+            }
+            self.battery_box.set_visible(battery >= 0);
+            self.update_bluetooth_visibility();
+        }
+
+        fn update_bluetooth_visibility(&self) {
+            self.bluetooth_box.set_visible(self.codec_dropdown.is_visible() || self.battery_box.is_visible());
+        }
+    }
 }
 
 glib::wrapper! {
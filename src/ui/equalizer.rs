@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::backend::{EqBand, EqBandType, Equalizer, PwNodeObject, PwvucontrolManager};
+use gettextrs::gettext;
+use glib::clone;
+use gtk::{prelude::*, subclass::prelude::*};
+use std::cell::{Cell, OnceCell, RefCell};
+
+struct BandRow {
+    band_type: gtk::DropDown,
+    freq: gtk::SpinButton,
+    gain: gtk::SpinButton,
+    q: gtk::SpinButton,
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::PwEqualizerBox)]
+    pub struct PwEqualizerBox {
+        #[property(get, set, construct_only)]
+        pub(super) node_object: OnceCell<PwNodeObject>,
+
+        pub(super) enabled: gtk::Switch,
+        pub(super) grid: gtk::Grid,
+        pub(super) rows: RefCell<Vec<BandRow>>,
+        pub(super) block: Cell<bool>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwEqualizerBox {
+        const NAME: &'static str = "PwEqualizerBox";
+        type Type = super::PwEqualizerBox;
+        type ParentType = gtk::Box;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwEqualizerBox {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            obj.set_orientation(gtk::Orientation::Vertical);
+            obj.set_spacing(12);
+
+            let header = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+            let title = gtk::Label::builder().label(gettext("Equalizer")).xalign(0.0).hexpand(true).css_classes(["heading"]).build();
+            self.enabled.set_valign(gtk::Align::Center);
+            self.enabled.set_tooltip_text(Some(&gettext("Plays this output through an equalizer, its streams and the default move onto it")));
+            let reset = gtk::Button::builder().icon_name("edit-undo-symbolic").tooltip_text(gettext("Reset bands")).build();
+            header.append(&title);
+            header.append(&reset);
+            header.append(&self.enabled);
+            obj.append(&header);
+
+            self.grid.set_row_spacing(6);
+            self.grid.set_column_spacing(6);
+            for (column, label) in [gettext("Type"), gettext("Frequency (Hz)"), gettext("Gain (dB)"), gettext("Q")].iter().enumerate() {
+                self.grid.attach(&gtk::Label::builder().label(label).xalign(0.0).css_classes(["dim-label"]).build(), column as i32, 0, 1, 1);
+            }
+            obj.append(&self.grid);
+
+            self.enabled.connect_active_notify(clone!(#[weak(rename_to = widget)] self, move |_| widget.apply()));
+            reset.connect_clicked(clone!(#[weak(rename_to = widget)] self, move |_| {
+                let enabled = widget.enabled.is_active();
+                widget.load(&Equalizer { enabled, ..Default::default() });
+                widget.apply();
+            }));
+
+            let node = obj.node_object();
+            self.load(&PwvucontrolManager::default().equalizer(&node));
+        }
+    }
+
+    impl WidgetImpl for PwEqualizerBox {}
+    impl BoxImpl for PwEqualizerBox {}
+
+    impl PwEqualizerBox {
+        fn load(&self, equalizer: &Equalizer) {
+            self.block.set(true);
+
+            self.enabled.set_active(equalizer.enabled);
+
+            for row in self.rows.take() {
+                self.grid.remove(&row.band_type);
+                self.grid.remove(&row.freq);
+                self.grid.remove(&row.gain);
+                self.grid.remove(&row.q);
+            }
+
+            let type_labels = [gettext("Low shelf"), gettext("Peaking"), gettext("High shelf")];
+            let mut rows = Vec::new();
+            for (i, band) in equalizer.bands.iter().enumerate() {
+                let row = BandRow {
+                    band_type: gtk::DropDown::from_strings(&type_labels.iter().map(String::as_str).collect::<Vec<_>>()),
+                    freq: gtk::SpinButton::with_range(20.0, 20000.0, 10.0),
+                    gain: gtk::SpinButton::with_range(-24.0, 24.0, 0.5),
+                    q: gtk::SpinButton::with_range(0.1, 10.0, 0.1),
+                };
+                row.band_type.set_selected(EqBandType::ALL.iter().position(|x| *x == band.band_type).unwrap_or_default() as u32);
+                row.freq.set_value(band.freq as f64);
+                row.gain.set_digits(1);
+                row.gain.set_value(band.gain as f64);
+                row.q.set_digits(2);
+                row.q.set_value(band.q as f64);
+
+                let top = i as i32 + 1;
+                self.grid.attach(&row.band_type, 0, top, 1, 1);
+                self.grid.attach(&row.freq, 1, top, 1, 1);
+                self.grid.attach(&row.gain, 2, top, 1, 1);
+                self.grid.attach(&row.q, 3, top, 1, 1);
+
+                row.band_type.connect_selected_notify(clone!(#[weak(rename_to = widget)] self, move |_| widget.apply()));
+                for spin in [&row.freq, &row.gain, &row.q] {
+                    spin.connect_value_changed(clone!(#[weak(rename_to = widget)] self, move |_| widget.apply()));
+                }
+
+                rows.push(row);
+            }
+            self.rows.replace(rows);
+
+            self.block.set(false);
+        }
+
+        fn equalizer(&self) -> Equalizer {
+            let bands = self
+                .rows
+                .borrow()
+                .iter()
+                .map(|row| EqBand {
+                    band_type: EqBandType::ALL.get(row.band_type.selected() as usize).copied().unwrap_or(EqBandType::Peaking),
+                    freq: row.freq.value() as f32,
+                    gain: row.gain.value() as f32,
+                    q: row.q.value() as f32,
+                })
+                .collect();
+            Equalizer { enabled: self.enabled.is_active(), bands }
+        }
+
+        fn apply(&self) {
+            if self.block.get() {
+                return;
+            }
+            PwvucontrolManager::default().set_equalizer(&self.obj().node_object(), &self.equalizer());
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct PwEqualizerBox(ObjectSubclass<imp::PwEqualizerBox>)
+        @extends gtk::Widget, gtk::Box,
+        @implements gtk::Orientable;
+}
+
+impl PwEqualizerBox {
+    pub(crate) fn new(node_object: &PwNodeObject) -> Self {
+        glib::Object::builder().property("node-object", node_object).build()
+    }
+}
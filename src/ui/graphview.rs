@@ -0,0 +1,390 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::backend::{PortDirection, PwLinkObject, PwPortObject, PwvucontrolManager};
+use glib::clone;
+use gtk::{self, prelude::*, subclass::prelude::*};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use wireplumber as wp;
+use wp::pw::{PipewireObjectExt2, ProxyExt};
+
+const NODE_WIDTH: f64 = 220.0;
+const HEADER_HEIGHT: f64 = 26.0;
+const PORT_HEIGHT: f64 = 20.0;
+const COLUMN_SPACING: f64 = 120.0;
+const NODE_SPACING: f64 = 16.0;
+const MARGIN: f64 = 12.0;
+const PORT_RADIUS: f64 = 5.0;
+const HIT_RADIUS: f64 = 9.0;
+
+#[derive(Debug, Default)]
+struct NodeLayout {
+    name: String,
+    x: f64,
+    y: f64,
+    height: f64,
+}
+
+#[derive(Debug)]
+struct PortLayout {
+    port: PwPortObject,
+    x: f64,
+    y: f64,
+}
+
+#[derive(Debug, Default)]
+struct GraphLayout {
+    nodes: Vec<NodeLayout>,
+    ports: HashMap<u32, PortLayout>,
+    width: f64,
+    height: f64,
+}
+
+/// Which column a node goes in, based on the directions of its ports.
+fn column_for_ports(has_inputs: bool, has_outputs: bool) -> usize {
+    match (has_inputs, has_outputs) {
+        (false, true) => 0,
+        (true, true) => 1,
+        _ => 2,
+    }
+}
+
+/// Control points for the bezier curve drawn between two ports.
+fn link_curve(from: (f64, f64), to: (f64, f64)) -> [(f64, f64); 4] {
+    let offset = ((to.0 - from.0).abs() / 2.0).max(40.0);
+    [from, (from.0 + offset, from.1), (to.0 - offset, to.1), to]
+}
+
+fn point_on_curve(curve: &[(f64, f64); 4], t: f64) -> (f64, f64) {
+    let u = 1.0 - t;
+    let [p0, p1, p2, p3] = curve;
+    let x = u * u * u * p0.0 + 3.0 * u * u * t * p1.0 + 3.0 * u * t * t * p2.0 + t * t * t * p3.0;
+    let y = u * u * u * p0.1 + 3.0 * u * u * t * p1.1 + 3.0 * u * t * t * p2.1 + t * t * t * p3.1;
+    (x, y)
+}
+
+fn distance_to_curve(curve: &[(f64, f64); 4], x: f64, y: f64) -> f64 {
+    (0..=32)
+        .map(|i| point_on_curve(curve, i as f64 / 32.0))
+        .map(|(px, py)| ((px - x).powi(2) + (py - y).powi(2)).sqrt())
+        .fold(f64::MAX, f64::min)
+}
+
+mod imp {
+    use gtk::graphene;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct PwGraphView {
+        pub(super) layout: RefCell<GraphLayout>,
+        pub(super) drag_source: Cell<Option<u32>>,
+        pub(super) drag_position: Cell<(f64, f64)>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwGraphView {
+        const NAME: &'static str = "PwGraphView";
+        type Type = super::PwGraphView;
+        type ParentType = gtk::Widget;
+    }
+
+    impl ObjectImpl for PwGraphView {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            obj.add_css_class("graphview");
+
+            let manager = PwvucontrolManager::default();
+            for model in [manager.graph_node_model(), manager.port_model(), manager.link_model()] {
+                model.connect_items_changed(clone!(#[weak] obj, move |_, _, _, _| {
+                    obj.relayout();
+                }));
+            }
+
+            let drag = gtk::GestureDrag::new();
+            drag.connect_drag_begin(clone!(#[weak] obj, move |gesture, x, y| {
+                let imp = obj.imp();
+                match obj.port_at(x, y) {
+                    Some(port) => {
+                        imp.drag_source.set(Some(port));
+                        imp.drag_position.set((x, y));
+                    }
+                    None => gesture.set_state(gtk::EventSequenceState::Denied),
+                }
+            }));
+            drag.connect_drag_update(clone!(#[weak] obj, move |gesture, dx, dy| {
+                if let Some((x, y)) = gesture.start_point() {
+                    obj.imp().drag_position.set((x + dx, y + dy));
+                    obj.queue_draw();
+                }
+            }));
+            drag.connect_drag_end(clone!(#[weak] obj, move |gesture, dx, dy| {
+                let imp = obj.imp();
+                let Some(source) = imp.drag_source.take() else {
+                    return;
+                };
+                if let Some((x, y)) = gesture.start_point() {
+                    obj.finish_drag(source, obj.port_at(x + dx, y + dy));
+                }
+                obj.queue_draw();
+            }));
+            obj.add_controller(drag);
+
+            let click = gtk::GestureClick::new();
+            click.set_button(gtk::gdk::BUTTON_SECONDARY);
+            click.connect_pressed(clone!(#[weak] obj, move |_, _, x, y| {
+                if let Some(link) = obj.link_at(x, y) {
+                    link.destroy();
+                }
+            }));
+            obj.add_controller(click);
+
+            obj.relayout();
+        }
+    }
+
+    impl WidgetImpl for PwGraphView {
+        fn measure(&self, orientation: gtk::Orientation, _for_size: i32) -> (i32, i32, i32, i32) {
+            let layout = self.layout.borrow();
+            let size = match orientation {
+                gtk::Orientation::Horizontal => layout.width,
+                _ => layout.height,
+            } as i32;
+            (size, size, -1, -1)
+        }
+
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let obj = self.obj();
+            let layout = self.layout.borrow();
+            let fg = obj.color();
+
+            let bounds = graphene::Rect::new(0.0, 0.0, obj.width() as f32, obj.height() as f32);
+            let cr = snapshot.append_cairo(&bounds);
+
+            for node in layout.nodes.iter() {
+                cr.rectangle(node.x, node.y, NODE_WIDTH, node.height);
+                cr.set_source_rgba(fg.red() as f64, fg.green() as f64, fg.blue() as f64, 0.08);
+                let _ = cr.fill_preserve();
+                cr.set_source_rgba(fg.red() as f64, fg.green() as f64, fg.blue() as f64, 0.3);
+                cr.set_line_width(1.0);
+                let _ = cr.stroke();
+            }
+
+            let manager = PwvucontrolManager::default();
+            cr.set_line_width(2.0);
+            cr.set_source_rgba(0.21, 0.52, 0.89, 1.0);
+            for link in manager.link_model().iter::<PwLinkObject>().map_while(Result::ok) {
+                let (Some(from), Some(to)) = (layout.ports.get(&link.output_port()), layout.ports.get(&link.input_port())) else {
+                    continue;
+                };
+                let [p0, p1, p2, p3] = link_curve((from.x, from.y), (to.x, to.y));
+                cr.move_to(p0.0, p0.1);
+                cr.curve_to(p1.0, p1.1, p2.0, p2.1, p3.0, p3.1);
+                let _ = cr.stroke();
+            }
+
+            if let Some(source) = self.drag_source.get().and_then(|id| layout.ports.get(&id)) {
+                let (x, y) = self.drag_position.get();
+                let (from, to) = match source.port.direction() {
+                    PortDirection::Input => ((x, y), (source.x, source.y)),
+                    _ => ((source.x, source.y), (x, y)),
+                };
+                let [p0, p1, p2, p3] = link_curve(from, to);
+                cr.set_dash(&[4.0, 4.0], 0.0);
+                cr.move_to(p0.0, p0.1);
+                cr.curve_to(p1.0, p1.1, p2.0, p2.1, p3.0, p3.1);
+                let _ = cr.stroke();
+                cr.set_dash(&[], 0.0);
+            }
+
+            for port in layout.ports.values() {
+                cr.arc(port.x, port.y, PORT_RADIUS, 0.0, 2.0 * std::f64::consts::PI);
+                match port.port.direction() {
+                    PortDirection::Output => cr.set_source_rgba(0.2, 0.82, 0.48, 1.0),
+                    _ => cr.set_source_rgba(0.96, 0.83, 0.18, 1.0),
+                }
+                let _ = cr.fill();
+            }
+            drop(cr);
+
+            for node in layout.nodes.iter() {
+                self.append_label(snapshot, &node.name, node.x + 6.0, node.y + 4.0, NODE_WIDTH - 12.0, &fg, true);
+            }
+
+            for port in layout.ports.values() {
+                let half_width = NODE_WIDTH / 2.0 - PORT_RADIUS * 3.0;
+                let x = match port.port.direction() {
+                    PortDirection::Output => port.x - PORT_RADIUS * 2.0 - half_width,
+                    _ => port.x + PORT_RADIUS * 2.0,
+                };
+                self.append_label(snapshot, &port.port.name(), x, port.y - PORT_HEIGHT / 2.0 + 2.0, half_width, &fg, false);
+            }
+        }
+    }
+
+    impl PwGraphView {
+        #[allow(clippy::too_many_arguments)]
+        fn append_label(&self, snapshot: &gtk::Snapshot, text: &str, x: f64, y: f64, width: f64, color: &gtk::gdk::RGBA, bold: bool) {
+            let pango_layout = self.obj().create_pango_layout(Some(text));
+            pango_layout.set_width(width as i32 * gtk::pango::SCALE);
+            pango_layout.set_ellipsize(gtk::pango::EllipsizeMode::End);
+            if bold {
+                let attrs = gtk::pango::AttrList::new();
+                attrs.insert(gtk::pango::AttrInt::new_weight(gtk::pango::Weight::Bold));
+                pango_layout.set_attributes(Some(&attrs));
+            }
+
+            snapshot.save();
+            snapshot.translate(&graphene::Point::new(x as f32, y as f32));
+            snapshot.append_layout(&pango_layout, color);
+            snapshot.restore();
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct PwGraphView(ObjectSubclass<imp::PwGraphView>)
+    @extends gtk::Widget,
+    @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl PwGraphView {
+    pub fn new() -> Self {
+        glib::Object::builder().build()
+    }
+
+    fn relayout(&self) {
+        let manager = PwvucontrolManager::default();
+
+        let mut ports_by_node: HashMap<u32, (Vec<PwPortObject>, Vec<PwPortObject>)> = HashMap::new();
+        for port in manager.port_model().iter::<PwPortObject>().map_while(Result::ok) {
+            let entry = ports_by_node.entry(port.node_id()).or_default();
+            match port.direction() {
+                PortDirection::Input => entry.0.push(port),
+                PortDirection::Output => entry.1.push(port),
+                PortDirection::Unknown => {}
+            }
+        }
+
+        let mut layout = GraphLayout::default();
+        let mut column_heights = [MARGIN; 3];
+
+        for node in manager.graph_node_model().iter::<wp::pw::Node>().map_while(Result::ok) {
+            let Some((inputs, outputs)) = ports_by_node.get_mut(&node.bound_id()) else {
+                continue;
+            };
+            if inputs.is_empty() && outputs.is_empty() {
+                continue;
+            }
+            inputs.sort_by_key(|port| port.boundid());
+            outputs.sort_by_key(|port| port.boundid());
+
+            let column = column_for_ports(!inputs.is_empty(), !outputs.is_empty());
+            let x = MARGIN + column as f64 * (NODE_WIDTH + COLUMN_SPACING);
+            let y = column_heights[column];
+            let height = HEADER_HEIGHT + inputs.len().max(outputs.len()) as f64 * PORT_HEIGHT + 4.0;
+
+            for (row, port) in inputs.iter().enumerate() {
+                let port_y = y + HEADER_HEIGHT + (row as f64 + 0.5) * PORT_HEIGHT;
+                layout.ports.insert(port.boundid(), PortLayout { port: port.clone(), x, y: port_y });
+            }
+            for (row, port) in outputs.iter().enumerate() {
+                let port_y = y + HEADER_HEIGHT + (row as f64 + 0.5) * PORT_HEIGHT;
+                layout.ports.insert(port.boundid(), PortLayout { port: port.clone(), x: x + NODE_WIDTH, y: port_y });
+            }
+
+            let name = node
+                .pw_property::<String>("node.description")
+                .or_else(|_| node.pw_property::<String>("node.nick"))
+                .or_else(|_| node.pw_property::<String>("node.name"))
+                .unwrap_or_else(|_| node.bound_id().to_string());
+
+            layout.nodes.push(NodeLayout { name, x, y, height });
+            column_heights[column] += height + NODE_SPACING;
+        }
+
+        layout.width = MARGIN * 2.0 + 3.0 * NODE_WIDTH + 2.0 * COLUMN_SPACING;
+        layout.height = column_heights.into_iter().fold(0.0, f64::max) + MARGIN;
+
+        self.imp().layout.replace(layout);
+        self.queue_resize();
+        self.queue_draw();
+    }
+
+    fn port_at(&self, x: f64, y: f64) -> Option<u32> {
+        let layout = self.imp().layout.borrow();
+        layout
+            .ports
+            .iter()
+            .find(|(_, port)| (port.x - x).powi(2) + (port.y - y).powi(2) <= HIT_RADIUS.powi(2))
+            .map(|(id, _)| *id)
+    }
+
+    fn link_at(&self, x: f64, y: f64) -> Option<PwLinkObject> {
+        let manager = PwvucontrolManager::default();
+        let layout = self.imp().layout.borrow();
+
+        manager.link_model().iter::<PwLinkObject>().map_while(Result::ok).find(|link| {
+            let (Some(from), Some(to)) = (layout.ports.get(&link.output_port()), layout.ports.get(&link.input_port())) else {
+                return false;
+            };
+            distance_to_curve(&link_curve((from.x, from.y), (to.x, to.y)), x, y) <= HIT_RADIUS
+        })
+    }
+
+    /// Dropping an output on an input (or the other way round) links them.
+    /// Dropping a linked input port on empty space unlinks it.
+    fn finish_drag(&self, source: u32, target: Option<u32>) {
+        let manager = PwvucontrolManager::default();
+        let ports: HashMap<u32, PwPortObject> =
+            self.imp().layout.borrow().ports.iter().map(|(id, layout)| (*id, layout.port.clone())).collect();
+        let Some(source) = ports.get(&source) else {
+            return;
+        };
+
+        match target.and_then(|id| ports.get(&id)) {
+            Some(target) => match (source.direction(), target.direction()) {
+                (PortDirection::Output, PortDirection::Input) => manager.create_link(source, target),
+                (PortDirection::Input, PortDirection::Output) => manager.create_link(target, source),
+                _ => {}
+            },
+            None if source.direction() == PortDirection::Input => {
+                for link in manager.link_model().iter::<PwLinkObject>().map_while(Result::ok) {
+                    if link.input_port() == source.boundid() {
+                        link.destroy();
+                    }
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+impl Default for PwGraphView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nodes_are_placed_by_port_direction() {
+        assert_eq!(column_for_ports(false, true), 0);
+        assert_eq!(column_for_ports(true, true), 1);
+        assert_eq!(column_for_ports(true, false), 2);
+    }
+
+    #[test]
+    fn link_curve_hit_testing() {
+        let curve = link_curve((0.0, 0.0), (200.0, 100.0));
+        assert!(distance_to_curve(&curve, 0.0, 0.0) < 1.0);
+        assert!(distance_to_curve(&curve, 200.0, 100.0) < 1.0);
+        assert!(distance_to_curve(&curve, 100.0, 50.0) < HIT_RADIUS);
+        assert!(distance_to_curve(&curve, 0.0, 100.0) > HIT_RADIUS);
+    }
+}
@@ -0,0 +1,149 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{backend::HideRule, config::APP_ID};
+use adw::{prelude::*, subclass::prelude::*};
+use gettextrs::gettext;
+use glib::clone;
+use gtk::gio;
+use std::cell::OnceCell;
+
+mod imp {
+    use super::*;
+
+    #[derive(Default)]
+    pub struct PwHideRulesWindow {
+        pub(super) settings: OnceCell<gio::Settings>,
+        pub(super) group: adw::PreferencesGroup,
+        pub(super) rows: gtk::ListBox,
+        pub(super) new_rule: adw::EntryRow,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwHideRulesWindow {
+        const NAME: &'static str = "PwHideRulesWindow";
+        type Type = super::PwHideRulesWindow;
+        type ParentType = adw::Window;
+    }
+
+    impl ObjectImpl for PwHideRulesWindow {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            obj.set_title(Some(&gettext("Hidden Streams")));
+            obj.set_default_size(500, 500);
+
+            self.group.set_title(&gettext("Hiding rules"));
+            self.group.set_description(Some(&gettext(
+                "Streams, inputs and outputs with a PipeWire property matching a rule are hidden. \
+                 Write a property name to match when it is set, or name=value where * matches any text.",
+            )));
+
+            self.rows.set_selection_mode(gtk::SelectionMode::None);
+            self.rows.add_css_class("boxed-list");
+            self.group.add(&self.rows);
+
+            self.new_rule.set_title(&gettext("Add rule, e.g. node.name=monitor-*"));
+            self.new_rule.set_show_apply_button(true);
+            let new_rule_group = adw::PreferencesGroup::new();
+            new_rule_group.add(&self.new_rule);
+
+            let page = adw::PreferencesPage::new();
+            page.add(&self.group);
+            page.add(&new_rule_group);
+
+            let reset = gtk::Button::builder().label(gettext("Reset")).tooltip_text(gettext("Restore the default rules")).build();
+            let header_bar = adw::HeaderBar::new();
+            header_bar.pack_start(&reset);
+
+            let toolbar_view = adw::ToolbarView::new();
+            toolbar_view.add_top_bar(&header_bar);
+            toolbar_view.set_content(Some(&page));
+            obj.set_content(Some(&toolbar_view));
+
+            let settings = gio::Settings::new(APP_ID);
+            settings.connect_changed(Some("hide-rules"), clone!(#[weak(rename_to = widget)] self, move |_, _| widget.update_rows()));
+            self.settings.set(settings).expect("settings set already");
+
+            reset.connect_clicked(clone!(#[weak(rename_to = widget)] self, move |_| widget.settings().reset("hide-rules")));
+            self.new_rule.connect_apply(clone!(#[weak(rename_to = widget)] self, move |entry| {
+                match HideRule::parse(&entry.text()) {
+                    Some(rule) => {
+                        entry.remove_css_class("error");
+                        entry.set_text("");
+                        let mut rules = widget.rules();
+                        rules.push(rule.to_string());
+                        widget.set_rules(&rules);
+                    }
+                    None => entry.add_css_class("error"),
+                }
+            }));
+
+            self.update_rows();
+        }
+    }
+
+    impl WidgetImpl for PwHideRulesWindow {}
+    impl WindowImpl for PwHideRulesWindow {}
+    impl AdwWindowImpl for PwHideRulesWindow {}
+
+    impl PwHideRulesWindow {
+        fn settings(&self) -> &gio::Settings {
+            self.settings.get().expect("settings")
+        }
+
+        fn rules(&self) -> Vec<String> {
+            self.settings().strv("hide-rules").iter().map(|x| x.to_string()).collect()
+        }
+
+        fn set_rules(&self, rules: &[String]) {
+            if let Err(e) = self.settings().set_strv("hide-rules", rules) {
+                crate::pwvucontrol_warning!("Unable to save hide rules: {e}");
+            }
+        }
+
+        fn update_rows(&self) {
+            self.rows.remove_all();
+
+            let rules = self.rules();
+            if rules.is_empty() {
+                self.rows.append(&adw::ActionRow::builder().title(gettext("Nothing is hidden")).build());
+            }
+
+            for (i, rule) in rules.iter().enumerate() {
+                let row = adw::ActionRow::builder().title(glib::markup_escape_text(rule)).build();
+                if HideRule::parse(rule).is_none() {
+                    row.set_subtitle(&gettext("Invalid rule, ignored"));
+                }
+
+                let remove = gtk::Button::builder()
+                    .icon_name("user-trash-symbolic")
+                    .tooltip_text(gettext("Remove rule"))
+                    .valign(gtk::Align::Center)
+                    .css_classes(["flat"])
+                    .build();
+                remove.connect_clicked(clone!(#[weak(rename_to = widget)] self, move |_| {
+                    let mut rules = widget.rules();
+                    if i < rules.len() {
+                        rules.remove(i);
+                        widget.set_rules(&rules);
+                    }
+                }));
+                row.add_suffix(&remove);
+                self.rows.append(&row);
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct PwHideRulesWindow(ObjectSubclass<imp::PwHideRulesWindow>)
+        @extends gtk::Widget, gtk::Window, adw::Window,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl PwHideRulesWindow {
+    pub(crate) fn new() -> Self {
+        glib::Object::new()
+    }
+}
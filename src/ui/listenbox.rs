@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{
+    backend::{ListenLoopback, PwNodeObject, PwvucontrolManager},
+    ui::{PwvucontrolWindow, WithDefaultListModel},
+};
+use gettextrs::gettext;
+use glib::{clone, closure_local};
+use gtk::{prelude::*, subclass::prelude::*};
+use std::{
+    cell::{Cell, OnceCell, RefCell},
+    time::Duration,
+};
+
+/// Reloading the loopback interrupts the sound, so latency edits are applied once they settle.
+const LATENCY_APPLY_DELAY: Duration = Duration::from_millis(600);
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::PwListenBox)]
+    pub struct PwListenBox {
+        #[property(get, set, construct_only)]
+        pub(super) node_object: OnceCell<PwNodeObject>,
+
+        /// Whether the source is being played through a sink.
+        #[property(get)]
+        pub(super) listening: Cell<bool>,
+
+        pub(super) enabled: gtk::Switch,
+        pub(super) sink: gtk::DropDown,
+        pub(super) sink_model: OnceCell<WithDefaultListModel>,
+        pub(super) latency: gtk::SpinButton,
+        pub(super) block: Cell<bool>,
+        pub(super) latency_timeout: RefCell<Option<glib::SourceId>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwListenBox {
+        const NAME: &'static str = "PwListenBox";
+        type Type = super::PwListenBox;
+        type ParentType = gtk::Box;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwListenBox {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            obj.set_orientation(gtk::Orientation::Vertical);
+            obj.set_spacing(12);
+
+            let header = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+            let title = gtk::Label::builder().label(gettext("Listen to this source")).xalign(0.0).hexpand(true).css_classes(["heading"]).build();
+            self.enabled.set_valign(gtk::Align::Center);
+            self.enabled.set_tooltip_text(Some(&gettext("Plays this input through an output so you can monitor it")));
+            header.append(&title);
+            header.append(&self.enabled);
+            obj.append(&header);
+
+            let model = WithDefaultListModel::new(Some(&PwvucontrolManager::default().sink_model()));
+            self.sink.set_model(Some(&model));
+            self.sink.set_expression(Some(gtk::ClosureExpression::new::<Option<String>>(
+                gtk::Expression::NONE,
+                closure_local!(move |item: glib::Object| {
+                    if let Some(item) = item.downcast_ref::<PwNodeObject>() {
+                        Some(item.name())
+                    } else {
+                        item.downcast_ref::<gtk::StringObject>().map(|item| item.string().to_string())
+                    }
+                }),
+            )));
+            self.sink_model.set(model).expect("sink model set once");
+
+            self.latency.set_range(1.0, 1000.0);
+            self.latency.set_increments(5.0, 50.0);
+
+            let grid = gtk::Grid::builder().row_spacing(6).column_spacing(12).build();
+            for (row, (label, widget)) in
+                [(gettext("Play through"), self.sink.upcast_ref::<gtk::Widget>()), (gettext("Latency (ms)"), self.latency.upcast_ref())].into_iter().enumerate()
+            {
+                grid.attach(&gtk::Label::builder().label(label).xalign(0.0).css_classes(["dim-label"]).build(), 0, row as i32, 1, 1);
+                widget.set_hexpand(true);
+                grid.attach(widget, 1, row as i32, 1, 1);
+            }
+            obj.append(&grid);
+
+            self.load();
+
+            self.latency.connect_value_changed(clone!(#[weak(rename_to = widget)] self, move |_| widget.schedule_latency()));
+            self.enabled.connect_active_notify(clone!(#[weak(rename_to = widget)] self, move |_| widget.apply()));
+            self.sink.connect_selected_notify(clone!(#[weak(rename_to = widget)] self, move |_| widget.apply()));
+        }
+
+        fn dispose(&self) {
+            // Closing the page must not lose a latency edit that is still settling.
+            if let Some(timeout) = self.latency_timeout.take() {
+                timeout.remove();
+                self.apply_latency();
+            }
+        }
+    }
+
+    impl WidgetImpl for PwListenBox {}
+    impl BoxImpl for PwListenBox {}
+
+    impl PwListenBox {
+        fn load(&self) {
+            self.block.set(true);
+
+            let loopback = PwvucontrolManager::default().listen_loopback(&self.obj().node_object());
+            let latency = match &loopback {
+                Some(loopback) => loopback.latency_ms,
+                None => PwvucontrolWindow::default().imp().settings.uint("listen-latency-ms"),
+            };
+            self.latency.set_value(latency as f64);
+
+            let sink_name = loopback.as_ref().and_then(|x| x.sink_name.clone());
+            let position = sink_name.and_then(|sink_name| {
+                self.sink_model
+                    .get()?
+                    .iter::<glib::Object>()
+                    .map_while(Result::ok)
+                    .position(|item| item.downcast_ref::<PwNodeObject>().and_then(|x| x.node_property::<String>("node.name")).as_deref() == Some(&sink_name))
+            });
+            self.sink.set_selected(position.unwrap_or_default() as u32);
+
+            self.enabled.set_active(loopback.is_some());
+            self.listening.set(loopback.is_some());
+
+            self.block.set(false);
+        }
+
+        fn loopback(&self) -> ListenLoopback {
+            let sink_name = self.sink.selected_item().and_downcast::<PwNodeObject>().and_then(|sink| sink.node_property::<String>("node.name"));
+            ListenLoopback {
+                source_name: self.obj().node_object().node_property::<String>("node.name").unwrap_or_default(),
+                sink_name,
+                latency_ms: self.latency.value() as u32,
+            }
+        }
+
+        fn schedule_latency(&self) {
+            if self.block.get() {
+                return;
+            }
+            if let Some(timeout) = self.latency_timeout.take() {
+                timeout.remove();
+            }
+            let timeout = glib::timeout_add_local_once(LATENCY_APPLY_DELAY, clone!(#[weak(rename_to = widget)] self, move || {
+                widget.latency_timeout.take();
+                widget.apply_latency();
+            }));
+            self.latency_timeout.replace(Some(timeout));
+        }
+
+        fn apply_latency(&self) {
+            // The latency is remembered for the next source listened to.
+            if let Err(e) = PwvucontrolWindow::default().imp().settings.set_uint("listen-latency-ms", self.latency.value() as u32) {
+                crate::pwvucontrol_warning!("Unable to save listen latency: {e}");
+            }
+            self.apply();
+        }
+
+        fn apply(&self) {
+            if self.block.get() {
+                return;
+            }
+            let mut listening = self.enabled.is_active();
+            let loopback = listening.then(|| self.loopback());
+            if let Err(e) = PwvucontrolManager::default().set_listen_loopback(&self.obj().node_object(), loopback.as_ref()) {
+                crate::pwvucontrol_warning!("Unable to listen to {}: {e}", self.obj().node_object().name());
+                listening = false;
+                self.block.set(true);
+                self.enabled.set_active(false);
+                self.block.set(false);
+            }
+
+            if self.listening.replace(listening) != listening {
+                self.obj().notify_listening();
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Turns listening to a source on and off and picks the sink and latency, see [`ListenLoopback`].
+    pub struct PwListenBox(ObjectSubclass<imp::PwListenBox>)
+        @extends gtk::Widget, gtk::Box,
+        @implements gtk::Orientable;
+}
+
+impl PwListenBox {
+    pub(crate) fn new(node_object: &PwNodeObject) -> Self {
+        glib::Object::builder().property("node-object", node_object).build()
+    }
+}
@@ -1,11 +1,20 @@
 mod channelbox;
+mod clocksettings;
 mod devicebox;
-mod levelprovider;
+mod equalizer;
+mod graphview;
+mod hiderules;
+mod listenbox;
+mod nodeinspector;
 mod peakmeter;
+mod peakmetersettings;
+mod performanceview;
 mod profile_dropdown;
 mod profilerow;
 mod route_dropdown;
 mod sinkbox;
+mod speakertest;
+mod spectrumview;
 mod stream_dropdown;
 mod streambox;
 mod volumebox;
@@ -15,16 +24,25 @@ mod withdefaultlistmodel;
 mod utils;
 
 pub use channelbox::PwChannelBox;
-pub use levelprovider::LevelbarProvider;
+pub use clocksettings::PwClockSettingsWindow;
+pub use equalizer::PwEqualizerBox;
+pub use graphview::PwGraphView;
+pub use hiderules::PwHideRulesWindow;
+pub use listenbox::PwListenBox;
+pub use nodeinspector::PwNodeInspector;
 pub use peakmeter::PwPeakMeter;
+pub use peakmetersettings::PwPeakMeterSettingsWindow;
+pub use performanceview::PwPerformanceView;
 pub use profile_dropdown::PwProfileDropDown;
 pub use profilerow::PwProfileRow;
 pub use route_dropdown::PwRouteDropDown;
 pub use sinkbox::PwSinkBox;
+pub use speakertest::PwSpeakerTestWindow;
+pub use spectrumview::PwSpectrumView;
 pub use stream_dropdown::PwStreamDropDown;
 pub use streambox::PwStreamBox;
 pub use volumebox::PwVolumeBox;
-pub use volumescale::PwVolumeScale;
+pub use volumescale::{PwVolumeScale, MAX_OVERAMPLIFIED_VOLUME};
 pub use window::PwvucontrolWindow;
 pub use withdefaultlistmodel::WithDefaultListModel;
 pub use utils::remember_window_size;
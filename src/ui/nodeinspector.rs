@@ -0,0 +1,282 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::backend::{LevelMeter, Loudness, PwLinkObject, PwNodeObject, PwPortObject, PwvucontrolManager};
+use adw::{prelude::*, subclass::prelude::*};
+use gettextrs::gettext;
+use glib::{clone, SignalHandlerId};
+use gtk::pango;
+use std::cell::{OnceCell, RefCell};
+use wireplumber as wp;
+use wp::pw::{PipewireObjectExt, PipewireObjectExt2};
+
+const PARAM_IDS: [&str; 3] = ["Props", "EnumFormat", "Format"];
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::PwNodeInspector)]
+    pub struct PwNodeInspector {
+        #[property(get, set, construct_only)]
+        pub(super) node_object: OnceCell<PwNodeObject>,
+
+        pub(super) global_properties: gtk::ListBox,
+        pub(super) properties: gtk::ListBox,
+        pub(super) params: gtk::Box,
+        pub(super) links: gtk::ListBox,
+        pub(super) loudness: gtk::ListBox,
+        /// Momentary, short-term and RMS readings.
+        pub(super) loudness_labels: [gtk::Label; 3],
+        /// Only analysing while the loudness page is shown.
+        pub(super) loudness_meter: RefCell<Option<LevelMeter>>,
+
+        pub(super) node_handlers: RefCell<Vec<SignalHandlerId>>,
+        pub(super) link_model_handler: RefCell<Option<SignalHandlerId>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwNodeInspector {
+        const NAME: &'static str = "PwNodeInspector";
+        type Type = super::PwNodeInspector;
+        type ParentType = adw::Window;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwNodeInspector {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            let node = obj.node_object();
+            obj.set_title(Some(&node.name()));
+            obj.set_default_size(600, 700);
+
+            let stack = adw::ViewStack::new();
+
+            let properties_page = gtk::Box::new(gtk::Orientation::Vertical, 12);
+            properties_page.append(&section_label(&gettext("Global Properties")));
+            properties_page.append(&self.global_properties);
+            properties_page.append(&section_label(&gettext("Properties")));
+            properties_page.append(&self.properties);
+            stack.add_titled_with_icon(&scrolled(&properties_page), Some("properties"), &gettext("Properties"), "view-list-symbolic");
+
+            self.params.set_orientation(gtk::Orientation::Vertical);
+            self.params.set_spacing(12);
+            stack.add_titled_with_icon(&scrolled(&self.params), Some("params"), &gettext("Params"), "emblem-system-symbolic");
+
+            stack.add_titled_with_icon(&scrolled(&self.links), Some("links"), &gettext("Links"), "network-workgroup-symbolic");
+
+            let loudness_page = gtk::Box::new(gtk::Orientation::Vertical, 12);
+            loudness_page.append(&self.loudness);
+            loudness_page.append(
+                &gtk::Label::builder()
+                    .label(gettext("Loudness is measured as in EBU R128, RMS is unweighted."))
+                    .xalign(0.0)
+                    .wrap(true)
+                    .css_classes(["dim-label"])
+                    .build(),
+            );
+            for (label, title) in self.loudness_labels.iter().zip([gettext("Momentary (400 ms)"), gettext("Short-term (3 s)"), gettext("RMS (400 ms)")]) {
+                label.add_css_class("numeric");
+                let row = adw::ActionRow::builder().title(title).build();
+                row.add_suffix(label);
+                self.loudness.append(&row);
+            }
+            self.show_loudness(None);
+            stack.add_titled_with_icon(&scrolled(&loudness_page), Some("loudness"), &gettext("Loudness"), "audio-volume-high-symbolic");
+
+            stack.connect_visible_child_name_notify(clone!(#[weak(rename_to = widget)] self, move |stack| {
+                widget.set_analysing(stack.visible_child_name().as_deref() == Some("loudness"));
+            }));
+
+            for listbox in [&self.global_properties, &self.properties, &self.links, &self.loudness] {
+                listbox.set_selection_mode(gtk::SelectionMode::None);
+                listbox.add_css_class("boxed-list");
+            }
+
+            let header_bar = adw::HeaderBar::builder()
+                .title_widget(&adw::ViewSwitcher::builder().stack(&stack).policy(adw::ViewSwitcherPolicy::Wide).build())
+                .build();
+            let toolbar_view = adw::ToolbarView::new();
+            toolbar_view.add_top_bar(&header_bar);
+            toolbar_view.set_content(Some(&stack));
+            obj.set_content(Some(&toolbar_view));
+
+            let wpnode = node.wpnode();
+            let mut handlers = Vec::new();
+            for property in ["global-properties", "properties"] {
+                handlers.push(wpnode.connect_notify_local(
+                    Some(property),
+                    clone!(#[weak(rename_to = widget)] self, move |_, _| widget.update_properties()),
+                ));
+            }
+            handlers.push(wpnode.connect_params_changed(clone!(#[weak(rename_to = widget)] self, move |_, _| widget.update_params())));
+            self.node_handlers.replace(handlers);
+
+            let link_model = PwvucontrolManager::default().link_model();
+            self.link_model_handler.replace(Some(link_model.connect_items_changed(clone!(
+                #[weak(rename_to = widget)] self,
+                move |_, _, _, _| widget.update_links()
+            ))));
+
+            self.update_properties();
+            self.update_params();
+            self.update_links();
+        }
+
+        fn dispose(&self) {
+            self.loudness_meter.take();
+            if let Some(node) = self.node_object.get() {
+                let wpnode = node.wpnode();
+                for handler in self.node_handlers.take() {
+                    wpnode.disconnect(handler);
+                }
+            }
+            if let Some(handler) = self.link_model_handler.take() {
+                PwvucontrolManager::default().link_model().disconnect(handler);
+            }
+        }
+    }
+
+    impl WidgetImpl for PwNodeInspector {}
+    impl WindowImpl for PwNodeInspector {}
+    impl AdwWindowImpl for PwNodeInspector {}
+
+    impl PwNodeInspector {
+        fn set_analysing(&self, analysing: bool) {
+            if !analysing {
+                self.loudness_meter.take();
+                self.show_loudness(None);
+                return;
+            }
+
+            let node = self.obj().node_object();
+            let meter = PwvucontrolManager::default().add_loudness_meter(
+                node.boundid(),
+                &node.channel_positions(),
+                clone!(#[weak(rename_to = widget)] self, move |loudness| widget.show_loudness(Some(loudness))),
+            );
+            self.loudness_meter.replace(meter);
+        }
+
+        fn show_loudness(&self, loudness: Option<&Loudness>) {
+            let values = loudness.map(|x| [x.momentary, x.short_term, x.rms]);
+            for (i, (label, unit)) in self.loudness_labels.iter().zip(["LUFS", "LUFS", "dBFS"]).enumerate() {
+                let text = match values.map(|x| x[i]) {
+                    None => "–".to_string(),
+                    Some(value) if value.is_finite() => format!("{value:.1} {unit}"),
+                    Some(_) => format!("−∞ {unit}"),
+                };
+                label.set_label(&text);
+            }
+        }
+
+        fn update_properties(&self) {
+            let node = self.obj().node_object();
+            for (listbox, global) in [(&self.global_properties, true), (&self.properties, false)] {
+                listbox.remove_all();
+                for (key, value) in node.property_list(global) {
+                    let row = adw::ActionRow::builder().title(glib::markup_escape_text(&key)).subtitle(glib::markup_escape_text(&value)).subtitle_selectable(true).build();
+                    row.add_css_class("property");
+                    listbox.append(&row);
+                }
+            }
+        }
+
+        fn update_params(&self) {
+            let node = self.obj().node_object();
+            while let Some(child) = self.params.first_child() {
+                self.params.remove(&child);
+            }
+
+            for id in PARAM_IDS {
+                self.params.append(&section_label(id));
+                let text = node.params_text(id);
+                let label = gtk::Label::builder()
+                    .label(if text.is_empty() { gettext("Not available") } else { text })
+                    .xalign(0.0)
+                    .selectable(true)
+                    .wrap(true)
+                    .wrap_mode(pango::WrapMode::WordChar)
+                    .css_classes(["monospace"])
+                    .build();
+                self.params.append(&label);
+            }
+        }
+
+        fn update_links(&self) {
+            let manager = PwvucontrolManager::default();
+            let id = self.obj().node_object().boundid();
+            self.links.remove_all();
+
+            let port_name = |port_id: u32| {
+                manager
+                    .port_model()
+                    .iter::<PwPortObject>()
+                    .map_while(Result::ok)
+                    .find(|port| port.boundid() == port_id)
+                    .map(|port| port.name())
+                    .unwrap_or_else(|| port_id.to_string())
+            };
+            let node_name = |node_id: u32| {
+                manager.get_graph_node_by_id(node_id).and_then(|node| node.name()).map(|x| x.to_string()).unwrap_or_else(|| node_id.to_string())
+            };
+
+            let links: Vec<PwLinkObject> = manager
+                .link_model()
+                .iter::<PwLinkObject>()
+                .map_while(Result::ok)
+                .filter(|link| link.output_node() == id || link.input_node() == id)
+                .collect();
+
+            if links.is_empty() {
+                self.links.append(&adw::ActionRow::builder().title(gettext("Not linked")).build());
+                return;
+            }
+
+            for link in links {
+                let (title, subtitle) = if link.output_node() == id {
+                    (
+                        format!("{} → {}", port_name(link.output_port()), node_name(link.input_node())),
+                        port_name(link.input_port()),
+                    )
+                } else {
+                    (
+                        format!("{} ← {}", port_name(link.input_port()), node_name(link.output_node())),
+                        port_name(link.output_port()),
+                    )
+                };
+                let row = adw::ActionRow::builder().title(glib::markup_escape_text(&title)).subtitle(glib::markup_escape_text(&subtitle)).build();
+                self.links.append(&row);
+            }
+        }
+    }
+}
+
+fn section_label(text: &str) -> gtk::Label {
+    gtk::Label::builder().label(text).xalign(0.0).css_classes(["heading"]).build()
+}
+
+fn scrolled(child: &impl IsA<gtk::Widget>) -> gtk::ScrolledWindow {
+    let clamp = adw::Clamp::builder()
+        .child(child)
+        .maximum_size(800)
+        .margin_top(12)
+        .margin_bottom(12)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+    gtk::ScrolledWindow::builder().child(&clamp).vexpand(true).hscrollbar_policy(gtk::PolicyType::Never).build()
+}
+
+glib::wrapper! {
+    pub struct PwNodeInspector(ObjectSubclass<imp::PwNodeInspector>)
+        @extends gtk::Widget, gtk::Window, adw::Window,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl PwNodeInspector {
+    pub(crate) fn new(node_object: &PwNodeObject) -> Self {
+        glib::Object::builder().property("node-object", node_object).build()
+    }
+}
@@ -1,14 +1,25 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use gtk::{self, prelude::*, subclass::prelude::*};
+use gettextrs::gettext;
+use glib::clone;
+use gtk::{self, gio, pango, prelude::*, subclass::prelude::*};
 use std::cell::Cell;
 
+/// Lowest level shown when the meter uses a dBFS scale.
+const MIN_DB: f32 = -60.0;
+/// Levels from here on are counted as clipping.
+const CLIP_LEVEL: f32 = 1.0;
+/// How long the peak-hold marker stays put, in microseconds.
+const PEAK_HOLD_TIME: i64 = 1_500_000;
+const SCALE_TICKS: [f32; 9] = [0.0, -3.0, -6.0, -12.0, -20.0, -30.0, -40.0, -50.0, -60.0];
+const CLIP_WIDTH: f32 = 8.0;
+
 mod imp {
     use gtk::{graphene, gsk};
 
     use super::*;
 
-    #[derive(Debug, Default, glib::Properties)]
+    #[derive(Debug, glib::Properties)]
     #[properties(wrapper_type = super::PwPeakMeter)]
     pub struct PwPeakMeter {
         #[property(get, set = Self::set_level)]
@@ -16,6 +27,47 @@ mod imp {
 
         #[property(get, set)]
         pub(super) use_led: Cell<bool>,
+
+        /// Marks the highest recent level for a moment.
+        #[property(get, set)]
+        pub(super) peak_hold: Cell<bool>,
+
+        /// Shows a clip indicator that stays lit until clicked.
+        #[property(get, set)]
+        pub(super) show_clip: Cell<bool>,
+        #[property(get)]
+        pub(super) clipped: Cell<bool>,
+
+        /// Draws the level on a dBFS axis with tick marks below the bar.
+        #[property(get, set)]
+        pub(super) show_scale: Cell<bool>,
+
+        /// Levels above these many dBFS are drawn yellow and red.
+        #[property(get, set, minimum = -60.0, maximum = 0.0)]
+        pub(super) warning_db: Cell<f64>,
+        #[property(get, set, minimum = -60.0, maximum = 0.0)]
+        pub(super) danger_db: Cell<f64>,
+
+        pub(super) held_level: Cell<f32>,
+        pub(super) held_since: Cell<i64>,
+    }
+
+    impl Default for PwPeakMeter {
+        fn default() -> Self {
+            Self {
+                level: Default::default(),
+                use_led: Default::default(),
+                peak_hold: Default::default(),
+                show_clip: Default::default(),
+                clipped: Default::default(),
+                show_scale: Default::default(),
+                // 60% and 90% of full scale
+                warning_db: Cell::new(-4.4),
+                danger_db: Cell::new(-0.9),
+                held_level: Default::default(),
+                held_since: Default::default(),
+            }
+        }
     }
 
     #[glib::object_subclass]
@@ -28,33 +80,136 @@ mod imp {
     impl PwPeakMeter {
         fn set_level(&self, level: f32) {
             self.level.set(level);
+
+            let now = glib::monotonic_time();
+            if level >= self.held_level.get() || now - self.held_since.get() > PEAK_HOLD_TIME {
+                self.held_level.set(level);
+                self.held_since.set(now);
+            }
+
+            if self.show_clip.get() && level >= CLIP_LEVEL && !self.clipped.get() {
+                self.set_clipped(true);
+            }
+
             self.obj().queue_draw();
         }
+
+        pub(super) fn set_clipped(&self, clipped: bool) {
+            self.clipped.set(clipped);
+            self.obj().set_tooltip_text(clipped.then(|| gettext("Clipped, click to reset")).as_deref());
+            self.obj().notify_clipped();
+        }
+
+        /// Horizontal position of `level` as a fraction of the bar.
+        fn position(&self, level: f32) -> f32 {
+            if self.show_scale.get() {
+                db_position(amplitude_to_db(level))
+            } else {
+                level.clamp(0.0, 1.0)
+            }
+        }
+
+        fn db_to_position(&self, db: f32) -> f32 {
+            if self.show_scale.get() {
+                db_position(db)
+            } else {
+                db_to_amplitude(db)
+            }
+        }
+
+        fn scale_layout(&self, text: &str) -> pango::Layout {
+            let layout = self.obj().create_pango_layout(None);
+            layout.set_markup(&format!("<small>{text}</small>"));
+            layout
+        }
+
+        fn scale_height(&self) -> i32 {
+            if self.show_scale.get() {
+                self.scale_layout("0").pixel_size().1 + 2
+            } else {
+                0
+            }
+        }
+
+        fn snapshot_scale(&self, snapshot: &gtk::Snapshot, bar_width: f32, bar_height: f32) {
+            let color = self.obj().color();
+            let mut dimmed = color;
+            dimmed.set_alpha(color.alpha() * 0.5);
+
+            // From the right so 0 dB always gets its label.
+            let mut free_until = bar_width;
+            for db in SCALE_TICKS {
+                let x = (self.db_to_position(db) * bar_width).round();
+                snapshot.append_color(&dimmed, &graphene::Rect::new(x.min(bar_width - 1.0), bar_height, 1.0, 2.0));
+
+                let layout = self.scale_layout(&format!("{db}"));
+                let (label_width, _) = layout.pixel_size();
+                let label_x = (x - label_width as f32 / 2.0).min(bar_width - label_width as f32).max(0.0);
+                if label_x + label_width as f32 + 4.0 > free_until {
+                    continue;
+                }
+                free_until = label_x;
+
+                snapshot.save();
+                snapshot.translate(&graphene::Point::new(label_x, bar_height + 2.0));
+                snapshot.append_layout(&layout, &color);
+                snapshot.restore();
+            }
+        }
     }
 
     #[glib::derived_properties]
     impl ObjectImpl for PwPeakMeter {
         fn constructed(&self) {
             self.parent_constructed();
-            self.obj().add_css_class("vumeter");
+            let obj = self.obj();
+            obj.add_css_class("vumeter");
+
+            obj.connect_notify_local(None, |obj, pspec| match pspec.name() {
+                "level" | "tooltip-text" | "tooltip-markup" | "has-tooltip" => {}
+                "show-scale" => obj.queue_resize(),
+                _ => obj.queue_draw(),
+            });
+
+            let click = gtk::GestureClick::new();
+            click.connect_released(clone!(#[weak(rename_to = widget)] self, move |gesture, _, _, _| {
+                if widget.clipped.get() {
+                    widget.set_clipped(false);
+                    gesture.set_state(gtk::EventSequenceState::Claimed);
+                }
+            }));
+            obj.add_controller(click);
         }
     }
 
     impl WidgetImpl for PwPeakMeter {
         fn snapshot(&self, snapshot: &gtk::Snapshot) {
-            let num_blocks: u32 = (self.obj().width() / 20) as u32;
-            let green_limit: u32 = (0.6 * num_blocks as f32) as u32;
-            let yellow_limit: u32 = (0.9 * num_blocks as f32) as u32;
-
             let color_green = hex_to_rgb(0x33d17a);
             let color_yellow = hex_to_rgb(0xf6d32d);
             let color_red = hex_to_rgb(0xe01b24);
 
-            let width = self.obj().width() as u32;
-            let w = self.obj().width() as f32;
-            let h = self.obj().height() as f32;
+            let show_clip = self.show_clip.get();
+            let w = self.obj().width() as f32 - if show_clip { CLIP_WIDTH + 2.0 } else { 0.0 };
+            let h = (self.obj().height() - self.scale_height()) as f32;
+            if w <= 0.0 || h <= 0.0 {
+                return;
+            }
+            let width = w as u32;
+            let num_blocks: u32 = (width / 20).max(1);
+
+            let warning = self.db_to_position(self.warning_db.get() as f32);
+            let danger = self.db_to_position(self.danger_db.get() as f32).max(warning);
+            let color_at = |position: f32| {
+                if position < warning {
+                    color_green
+                } else if position < danger {
+                    color_yellow
+                } else {
+                    color_red
+                }
+            };
 
-            let level = self.level.get() as f32;
+            let level = self.position(self.level.get());
             let bounding_box = graphene::Rect::new(0.0, 0.0, w, h);
 
             let rounded_rect = gsk::RoundedRect::from_rect(bounding_box, 5.0);
@@ -62,7 +217,14 @@ mod imp {
             snapshot.push_rounded_clip(&rounded_rect);
 
             if !self.use_led.get() {
-                snapshot.append_color(&color_green, &graphene::Rect::new(0.0, 0.0, level * w, h));
+                let mut x = 0.0;
+                for (limit, color) in [(warning, color_green), (danger, color_yellow), (1.0, color_red)] {
+                    let end = level.min(limit) * w;
+                    if end > x {
+                        snapshot.append_color(&color, &graphene::Rect::new(x, 0.0, end - x, h));
+                        x = end;
+                    }
+                }
             } else {
                 let discrete_level = (level * num_blocks as f32).floor() as u32;
                 let mut block_width = width / num_blocks;
@@ -78,25 +240,43 @@ mod imp {
                         block_area_width -= 1;
                     }
 
-                    let color = if i < green_limit {
-                        color_green
-                    } else if i < yellow_limit {
-                        color_yellow
-                    } else {
-                        color_red
-                    };
+                    let color = color_at(i as f32 / num_blocks as f32);
                     snapshot.append_color(&color, &graphene::Rect::new(block_area_x as f32, 0.0, block_area_width as f32 - 1.0, h));
                     block_area_x += block_area_width;
                 }
             }
 
+            if self.peak_hold.get() && self.held_level.get() > 0.0 {
+                let held = self.position(self.held_level.get());
+                let x = (held * w - 2.0).max(0.0);
+                snapshot.append_color(&color_at(held), &graphene::Rect::new(x, 0.0, 2.0, h));
+            }
+
             snapshot.pop();
+
+            if show_clip {
+                let mut color = color_red;
+                if !self.clipped.get() {
+                    color.set_alpha(0.2);
+                }
+                let clip_box = graphene::Rect::new(w + 2.0, 0.0, CLIP_WIDTH, h);
+                snapshot.push_rounded_clip(&gsk::RoundedRect::from_rect(clip_box, 2.0));
+                snapshot.append_color(&color, &clip_box);
+                snapshot.pop();
+            }
+
+            if self.show_scale.get() {
+                self.snapshot_scale(snapshot, w, h);
+            }
         }
 
         fn measure(&self, orientation: gtk::Orientation, _for_size: i32) -> (i32, i32, i32, i32) {
             match orientation {
                 gtk::Orientation::Horizontal => (10, 10, -1, -1),
-                gtk::Orientation::Vertical => (10, 10, -1, -1),
+                gtk::Orientation::Vertical => {
+                    let size = 10 + self.scale_height();
+                    (size, size, -1, -1)
+                }
                 _ => panic!("Invalid orientation passed to measure"),
             }
         }
@@ -114,6 +294,20 @@ impl PwPeakMeter {
     pub fn new() -> Self {
         glib::Object::builder().build()
     }
+
+    /// Follows the peak meter options of the application settings.
+    pub(crate) fn bind_settings(&self, settings: &gio::Settings) {
+        for (key, property) in [
+            ("use-peakmeter-led", "use-led"),
+            ("peakmeter-peak-hold", "peak-hold"),
+            ("peakmeter-clip-indicator", "show-clip"),
+            ("peakmeter-db-scale", "show-scale"),
+            ("peakmeter-warning-db", "warning-db"),
+            ("peakmeter-danger-db", "danger-db"),
+        ] {
+            settings.bind(key, self, property).get_only().build();
+        }
+    }
 }
 
 impl Default for PwPeakMeter {
@@ -122,9 +316,43 @@ impl Default for PwPeakMeter {
     }
 }
 
-fn hex_to_rgb(hex: u32) -> gtk::gdk::RGBA {
+pub(super) fn hex_to_rgb(hex: u32) -> gtk::gdk::RGBA {
     let r = ((hex >> 16) & 0xFF) as f32 / 255.0;
     let g = ((hex >> 8) & 0xFF) as f32 / 255.0;
     let b = (hex & 0xFF) as f32 / 255.0;
     gtk::gdk::RGBA::new(r, g, b, 1.0)
 }
+
+fn amplitude_to_db(level: f32) -> f32 {
+    if level > 0.0 {
+        20.0 * level.log10()
+    } else {
+        f32::NEG_INFINITY
+    }
+}
+
+fn db_to_amplitude(db: f32) -> f32 {
+    10f32.powf(db / 20.0).clamp(0.0, 1.0)
+}
+
+/// Position of `db` on a scale running from [`MIN_DB`] to 0 dBFS.
+fn db_position(db: f32) -> f32 {
+    ((db - MIN_DB) / -MIN_DB).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_levels() {
+        assert_eq!(amplitude_to_db(1.0), 0.0);
+        assert!((amplitude_to_db(0.5) + 6.02).abs() < 0.01);
+        assert_eq!(amplitude_to_db(0.0), f32::NEG_INFINITY);
+        assert!((db_to_amplitude(-4.4) - 0.6).abs() < 0.01);
+        assert_eq!(db_position(0.0), 1.0);
+        assert_eq!(db_position(-30.0), 0.5);
+        assert_eq!(db_position(f32::NEG_INFINITY), 0.0);
+        assert_eq!(db_position(-90.0), 0.0);
+    }
+}
@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{config::APP_ID, ui::PwPeakMeter};
+use adw::{prelude::*, subclass::prelude::*};
+use gettextrs::gettext;
+use gtk::gio;
+
+mod imp {
+    use super::*;
+
+    #[derive(Default)]
+    pub struct PwPeakMeterSettingsWindow {
+        pub(super) preview: PwPeakMeter,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwPeakMeterSettingsWindow {
+        const NAME: &'static str = "PwPeakMeterSettingsWindow";
+        type Type = super::PwPeakMeterSettingsWindow;
+        type ParentType = adw::Window;
+    }
+
+    impl ObjectImpl for PwPeakMeterSettingsWindow {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            obj.set_title(Some(&gettext("Peak Meter")));
+            obj.set_default_size(450, -1);
+
+            let settings = gio::Settings::new(APP_ID);
+
+            let style = adw::PreferencesGroup::builder().title(gettext("Style")).build();
+            for (key, title, subtitle) in [
+                ("use-peakmeter-led", gettext("LED blocks"), None),
+                ("peakmeter-per-channel", gettext("Meter every channel"), Some(gettext("Shown in the expanded channel list"))),
+                ("peakmeter-db-scale", gettext("dBFS scale"), Some(gettext("Draws the level on a decibel axis with tick marks"))),
+                ("peakmeter-peak-hold", gettext("Peak hold"), Some(gettext("Marks the highest recent level"))),
+                ("peakmeter-clip-indicator", gettext("Clip indicator"), Some(gettext("Stays lit after reaching full scale until clicked"))),
+            ] {
+                let row = adw::SwitchRow::builder().title(title).build();
+                if let Some(subtitle) = subtitle {
+                    row.set_subtitle(&subtitle);
+                }
+                settings.bind(key, &row, "active").build();
+                style.add(&row);
+            }
+
+            let thresholds = adw::PreferencesGroup::builder()
+                .title(gettext("Thresholds"))
+                .description(gettext("Levels above these are drawn yellow and red"))
+                .build();
+            for (key, title) in [("peakmeter-warning-db", gettext("Warning level (dBFS)")), ("peakmeter-danger-db", gettext("Danger level (dBFS)"))] {
+                let row = adw::SpinRow::with_range(-60.0, 0.0, 0.5);
+                row.set_title(&title);
+                row.set_digits(1);
+                settings.bind(key, &row, "value").build();
+                thresholds.add(&row);
+            }
+
+            // Sweeps the preview meter through the whole range so the colors can be judged.
+            self.preview.set_margin_top(12);
+            self.preview.bind_settings(&settings);
+            self.preview.add_tick_callback(|meter, clock| {
+                let t = (clock.frame_time() % 4_000_000) as f32 / 2_000_000.0;
+                meter.set_level(if t < 1.0 { t } else { 2.0 - t });
+                glib::ControlFlow::Continue
+            });
+            let preview = adw::PreferencesGroup::builder().title(gettext("Preview")).build();
+            preview.add(&self.preview);
+
+            let page = adw::PreferencesPage::new();
+            page.add(&preview);
+            page.add(&style);
+            page.add(&thresholds);
+
+            let toolbar_view = adw::ToolbarView::new();
+            toolbar_view.add_top_bar(&adw::HeaderBar::new());
+            toolbar_view.set_content(Some(&page));
+            obj.set_content(Some(&toolbar_view));
+
+            // Keep the danger level at or above the warning level.
+            settings.connect_changed(None, |settings, key| {
+                let (warning, danger) = (settings.double("peakmeter-warning-db"), settings.double("peakmeter-danger-db"));
+                if warning > danger {
+                    let result = match key {
+                        "peakmeter-danger-db" => settings.set_double("peakmeter-warning-db", danger),
+                        "peakmeter-warning-db" => settings.set_double("peakmeter-danger-db", warning),
+                        _ => Ok(()),
+                    };
+                    if let Err(e) = result {
+                        crate::pwvucontrol_warning!("Unable to save peak meter thresholds: {e}");
+                    }
+                }
+            });
+        }
+    }
+
+    impl WidgetImpl for PwPeakMeterSettingsWindow {}
+    impl WindowImpl for PwPeakMeterSettingsWindow {}
+    impl AdwWindowImpl for PwPeakMeterSettingsWindow {}
+}
+
+glib::wrapper! {
+    pub struct PwPeakMeterSettingsWindow(ObjectSubclass<imp::PwPeakMeterSettingsWindow>)
+        @extends gtk::Widget, gtk::Window, adw::Window,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl PwPeakMeterSettingsWindow {
+    pub(crate) fn new() -> Self {
+        glib::Object::new()
+    }
+}
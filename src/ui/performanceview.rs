@@ -0,0 +1,220 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::backend::{NodeTiming, Profiler, ProfilerStats, PwvucontrolManager};
+use gettextrs::gettext;
+use glib::clone;
+use gtk::{prelude::*, subclass::prelude::*};
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+/// The table is redrawn this often, like pw-top does.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+const XRUNS_COLUMN: usize = 6;
+
+mod imp {
+    use super::*;
+
+    #[derive(Default)]
+    pub struct PwPerformanceView {
+        pub(super) grid: gtk::Grid,
+        pub(super) stack: gtk::Stack,
+        pub(super) stats: Rc<RefCell<ProfilerStats>>,
+        /// Only profiling while shown.
+        pub(super) profiler: RefCell<Option<Profiler>>,
+        pub(super) refresh: RefCell<Option<glib::SourceId>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwPerformanceView {
+        const NAME: &'static str = "PwPerformanceView";
+        type Type = super::PwPerformanceView;
+        type ParentType = gtk::Box;
+    }
+
+    impl ObjectImpl for PwPerformanceView {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            obj.set_orientation(gtk::Orientation::Vertical);
+            obj.set_spacing(12);
+            obj.set_margin_start(10);
+            obj.set_margin_end(10);
+            obj.set_margin_top(5);
+            obj.set_margin_bottom(5);
+
+            let header = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+            header.append(
+                &gtk::Label::builder()
+                    .label(gettext("Timing of every node in the last graph cycle, followers listed below their driver."))
+                    .xalign(0.0)
+                    .wrap(true)
+                    .hexpand(true)
+                    .css_classes(["dim-label"])
+                    .build(),
+            );
+            let reset = gtk::Button::builder().icon_name("edit-clear-all-symbolic").tooltip_text(gettext("Reset xrun counts")).build();
+            reset.add_css_class("flat");
+            reset.connect_clicked(clone!(#[weak(rename_to = widget)] self, move |_| {
+                widget.stats.borrow_mut().reset_xruns();
+                widget.update();
+            }));
+            header.append(&reset);
+            obj.append(&header);
+
+            self.grid.set_row_spacing(6);
+            self.grid.set_column_spacing(18);
+            self.stack.add_named(
+                &adw::StatusPage::builder()
+                    .title(gettext("No profiler data"))
+                    .description(gettext("Profiling needs libpipewire-module-profiler, which PipeWire loads by default"))
+                    .build(),
+                Some("empty"),
+            );
+            self.stack.add_named(&self.grid, Some("table"));
+            self.stack.set_vexpand(true);
+            obj.append(&self.stack);
+
+            self.update();
+        }
+
+        fn dispose(&self) {
+            self.stop();
+        }
+    }
+
+    impl WidgetImpl for PwPerformanceView {
+        fn map(&self) {
+            self.parent_map();
+            self.start();
+        }
+
+        fn unmap(&self) {
+            self.stop();
+            self.parent_unmap();
+        }
+    }
+
+    impl BoxImpl for PwPerformanceView {}
+
+    impl PwPerformanceView {
+        fn start(&self) {
+            if self.profiler.borrow().is_some() {
+                return;
+            }
+            let stats = self.stats.clone();
+            let profiler = PwvucontrolManager::default().add_profiler(move |samples| stats.borrow_mut().update(samples));
+            self.profiler.replace(profiler);
+
+            let refresh = glib::timeout_add_local(REFRESH_INTERVAL, clone!(#[weak(rename_to = widget)] self, #[upgrade_or] glib::ControlFlow::Break, move || {
+                widget.update();
+                glib::ControlFlow::Continue
+            }));
+            self.refresh.replace(Some(refresh));
+        }
+
+        fn stop(&self) {
+            self.profiler.take();
+            if let Some(refresh) = self.refresh.take() {
+                refresh.remove();
+            }
+        }
+
+        fn update(&self) {
+            while let Some(child) = self.grid.first_child() {
+                self.grid.remove(&child);
+            }
+
+            let rows = {
+                let mut stats = self.stats.borrow_mut();
+                let rows = stats.grouped();
+                stats.expire();
+                rows
+            };
+            self.stack.set_visible_child_name(if rows.is_empty() { "empty" } else { "table" });
+
+            let headings = [
+                gettext("Name"),
+                gettext("Quantum"),
+                gettext("Rate"),
+                gettext("Wait"),
+                gettext("Busy"),
+                gettext("DSP load"),
+                gettext("Xruns"),
+            ];
+            for (column, heading) in headings.iter().enumerate() {
+                self.grid.attach(&cell(heading, column, &["dim-label"]), column as i32, 0, 1, 1);
+            }
+
+            for (row, timing) in rows.iter().enumerate() {
+                let classes: &[&str] = if timing.is_driver() { &["heading"] } else { &[] };
+                let name = if timing.is_driver() { timing.name.clone() } else { format!("  {}", timing.name) };
+                for (column, text) in [
+                    name,
+                    timing.quantum.to_string(),
+                    timing.rate.to_string(),
+                    format_time(timing.wait),
+                    format_time(timing.busy),
+                    format!("{:.1} %", timing.load * 100.0),
+                    timing.xruns.to_string(),
+                ]
+                .iter()
+                .enumerate()
+                {
+                    let label = cell(text, column, classes);
+                    if column == XRUNS_COLUMN && timing.xruns > 0 {
+                        label.add_css_class("error");
+                    }
+                    self.grid.attach(&label, column as i32, row as i32 + 1, 1, 1);
+                }
+                if let Some(name) = self.grid.child_at(0, row as i32 + 1) {
+                    name.set_tooltip_text(Some(&tooltip(timing)));
+                }
+            }
+        }
+    }
+}
+
+/// Names are left aligned and take the remaining width, numbers are right aligned.
+fn cell(text: &str, column: usize, classes: &[&str]) -> gtk::Label {
+    let label = gtk::Label::builder().label(text).css_classes(classes.iter().copied().chain(["numeric"]).collect::<Vec<_>>()).build();
+    if column == 0 {
+        label.set_xalign(0.0);
+        label.set_hexpand(true);
+        label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+    } else {
+        label.set_xalign(1.0);
+    }
+    label
+}
+
+fn format_time(nsec: i64) -> String {
+    if nsec >= 1_000_000 {
+        format!("{:.2} ms", nsec as f64 / 1_000_000.0)
+    } else {
+        format!("{:.1} µs", nsec as f64 / 1000.0)
+    }
+}
+
+fn tooltip(timing: &NodeTiming) -> String {
+    let text = if timing.is_driver() { gettext("Driver, node {}") } else { gettext("Follower, node {}") };
+    formatx::formatx!(text, timing.id).unwrap_or_default()
+}
+
+glib::wrapper! {
+    /// Live table of the drivers and followers in the graph as reported by the PipeWire profiler, like pw-top.
+    pub struct PwPerformanceView(ObjectSubclass<imp::PwPerformanceView>)
+        @extends gtk::Widget, gtk::Box,
+        @implements gtk::Orientable;
+}
+
+impl PwPerformanceView {
+    pub fn new() -> Self {
+        glib::Object::builder().build()
+    }
+}
+
+impl Default for PwPerformanceView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
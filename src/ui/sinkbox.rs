@@ -1,18 +1,16 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use crate::{
-    backend::{NodeType, PwNodeObject, PwvucontrolManager},
+    backend::{Equalizer, NodeType, PwNodeObject, PwvucontrolManager},
     pwvucontrol_info,
-    ui::{PwRouteDropDown, PwVolumeBox, PwvucontrolWindow},
+    ui::{PwEqualizerBox, PwListenBox, PwRouteDropDown, PwSpeakerTestWindow, PwVolumeBox, PwvucontrolWindow},
 };
 
-use crate::pwvucontrol_warning;
 use glib::clone;
 use glib::closure_local;
 use gtk::{prelude::*, subclass::prelude::*};
 use std::cell::Cell;
 use std::cell::RefCell;
-use wireplumber as wp;
 
 mod imp {
     use super::*;
@@ -37,6 +35,15 @@ mod imp {
 
         #[template_child]
         pub route_dropdown: TemplateChild<PwRouteDropDown>,
+
+        #[template_child]
+        pub equalizer_button: TemplateChild<gtk::MenuButton>,
+
+        #[template_child]
+        pub speaker_test_button: TemplateChild<gtk::Button>,
+
+        #[template_child]
+        pub listen_button: TemplateChild<gtk::MenuButton>,
     }
 
     #[glib::object_subclass]
@@ -66,18 +73,61 @@ mod imp {
             self.volumebox.set_node_object(&item);
 
             if matches!(item.nodetype(), NodeType::Sink) {
-                self.volumebox.imp().volume_scale.connect_volume_notify(|_| {
-                    let window = PwvucontrolWindow::default();
-                    window.play_beep();
-                });
+                self.volumebox.imp().volume_scale.connect_volume_notify(clone!(#[weak] item, move |_| {
+                    PwvucontrolWindow::default().play_volume_feedback(&item);
+                }));
+            }
+            self.speaker_test_button.set_visible(matches!(item.nodetype(), NodeType::Sink));
+
+            if matches!(item.nodetype(), NodeType::Source) {
+                let listen_box = PwListenBox::new(&item);
+                // Highlighted while listening, the popover is closed most of the time.
+                listen_box.connect_listening_notify(clone!(#[weak(rename_to = widget)] self, move |listen_box| {
+                    widget.update_listen_button(listen_box.listening());
+                }));
+                self.update_listen_button(listen_box.listening());
+                self.listen_button.set_popover(Some(&gtk::Popover::builder().child(&listen_box).build()));
+            } else {
+                self.listen_button.set_visible(false);
             }
             let manager = PwvucontrolManager::default();
-            let defaultnodesapi = manager.default_nodes_api();
             let widget = self.obj();
-            let defaultnodesapi_closure = closure_local!(#[watch] widget, move |_: wp::plugin::Plugin| widget.imp().default_node_changed());
-            defaultnodesapi.connect_closure("changed", false, defaultnodesapi_closure);
+            let signal = match item.nodetype() {
+                NodeType::Sink => "notify::default-sink",
+                _ => "notify::default-source",
+            };
+            let default_node_closure = closure_local!(#[watch] widget, move |_: PwvucontrolManager, _: glib::ParamSpec| widget.imp().default_node_changed());
+            manager.connect_closure(signal, false, default_node_closure);
             self.default_node_changed();
 
+            // Only sinks can be equalized, and equalizing the equalizer's own sink would just stack filter-chains.
+            if matches!(item.nodetype(), NodeType::Sink) && item.node_property::<String>("node.name").is_some_and(|name| !Equalizer::is_equalizer_node(&name)) {
+                let popover = gtk::Popover::builder().child(&PwEqualizerBox::new(&item)).build();
+                self.equalizer_button.set_popover(Some(&popover));
+            } else {
+                self.equalizer_button.set_visible(false);
+            }
+
+            // Dropping a stream row here moves the stream to this device. Hovering a tab during the drag switches to it.
+            let drop_target = gtk::DropTarget::new(PwNodeObject::static_type(), gtk::gdk::DragAction::MOVE);
+            drop_target.set_preload(true);
+            drop_target.connect_value_notify(clone!(#[weak] item, move |target| {
+                let stream = target.value().and_then(|value| value.get::<PwNodeObject>().ok());
+                if stream.is_some_and(|stream| !stream.can_target(&item)) {
+                    target.reject();
+                }
+            }));
+            drop_target.connect_drop(clone!(#[weak] item, #[upgrade_or] false, move |_, value, _, _| {
+                match value.get::<PwNodeObject>() {
+                    Ok(stream) if stream.can_target(&item) => {
+                        stream.set_default_target(&item);
+                        true
+                    }
+                    _ => false,
+                }
+            }));
+            obj.add_controller(drop_target);
+
             self.route_dropdown.set_nodeobject(Some(&item));
             self.route_dropdown.connect_visible_notify(clone!(#[weak(rename_to = widget)] self, move |dropdown| {
                 widget.portlabel.set_visible(dropdown.is_visible());
@@ -106,39 +156,39 @@ mod imp {
             }
 
             let node = self.volumebox.node_object().expect("nodeobj");
-            let node_name: String = if _togglebutton.is_active() { node.node_property("node.name").unwrap_or_default() } else { "".to_string() };
-
-            let manager = PwvucontrolManager::default();
 
-            let core = manager.imp().wp_core.get().expect("Core");
-            let defaultnodesapi = wp::plugin::Plugin::find(core, "default-nodes-api").expect("Get mixer-api");
+            PwvucontrolManager::default().set_default_configured_node(&node, _togglebutton.is_active());
+        }
 
-            let type_name = match node.nodetype() {
-                NodeType::Sink => "Audio/Sink",
-                NodeType::Source => match node.is_virtual() {
-                    true => "Audio/Source/Virtual",
-                    false => "Audio/Source",
-                },
-                _ => unreachable!(),
+        #[template_callback]
+        fn speaker_test_button_clicked(&self) {
+            let Some(node) = self.obj().node_object() else {
+                return;
             };
+            let speaker_test = PwSpeakerTestWindow::new(&node);
+            speaker_test.set_transient_for(self.obj().root().and_downcast_ref::<gtk::Window>());
+            speaker_test.present();
+        }
 
-            let result: bool = defaultnodesapi.emit_by_name("set-default-configured-node-name", &[&type_name, &node_name]);
-            wp::info!("set-default-configured-node-name result: {result:?}");
+        fn update_listen_button(&self, listening: bool) {
+            if listening {
+                self.listen_button.add_css_class("accent");
+            } else {
+                self.listen_button.remove_css_class("accent");
+            }
         }
 
         fn default_node_changed(&self) {
             let manager = PwvucontrolManager::default();
-            let defaultnodesapi = manager.default_nodes_api();
 
             let node = self.obj().node_object().expect("nodeobj");
-            let Some(media_class) = node.node_property::<String>("media.class") else {
-                pwvucontrol_warning!("{} is missing media.class property", node.name());
-                return;
+            let default_node = match node.nodetype() {
+                NodeType::Sink => manager.default_sink(),
+                _ => manager.default_source(),
             };
-            let id: u32 = defaultnodesapi.emit_by_name("get-default-node", &[&media_class]);
 
             self.block_default_node_toggle_signal.set(true);
-            self.default_sink_toggle.set_active(node.boundid() == id);
+            self.default_sink_toggle.set_active(default_node.is_some() && default_node == node.node_property::<String>("node.name"));
             self.block_default_node_toggle_signal.set(false);
         }
     }
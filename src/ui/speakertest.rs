@@ -0,0 +1,249 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::backend::{LevelMeter, PwNodeObject, PwvucontrolManager, SignalGenerator, TestSignal};
+use adw::{prelude::*, subclass::prelude::*};
+use gettextrs::gettext;
+use glib::clone;
+use pipewire::spa::sys::{
+    SPA_AUDIO_CHANNEL_FC, SPA_AUDIO_CHANNEL_FL, SPA_AUDIO_CHANNEL_FLC, SPA_AUDIO_CHANNEL_FR, SPA_AUDIO_CHANNEL_FRC, SPA_AUDIO_CHANNEL_LFE, SPA_AUDIO_CHANNEL_MONO,
+    SPA_AUDIO_CHANNEL_RC, SPA_AUDIO_CHANNEL_RL, SPA_AUDIO_CHANNEL_RLC, SPA_AUDIO_CHANNEL_RR, SPA_AUDIO_CHANNEL_RRC, SPA_AUDIO_CHANNEL_SL, SPA_AUDIO_CHANNEL_SR,
+};
+use std::{
+    cell::{Cell, OnceCell, RefCell},
+    rc::Rc,
+};
+use wireplumber as wp;
+
+/// Used when the sink has not told its rate yet.
+const FALLBACK_RATE: u32 = 48000;
+/// Width of the speaker layout grid, the listener sits in the middle.
+const COLUMNS: i32 = 5;
+
+/// Where a channel is drawn relative to the listener at (2, 1), `None` for channels without a place in the room.
+fn channel_cell(position: u32) -> Option<(i32, i32)> {
+    Some(match position {
+        SPA_AUDIO_CHANNEL_FL => (0, 0),
+        SPA_AUDIO_CHANNEL_FLC => (1, 0),
+        SPA_AUDIO_CHANNEL_FC | SPA_AUDIO_CHANNEL_MONO => (2, 0),
+        SPA_AUDIO_CHANNEL_FRC => (3, 0),
+        SPA_AUDIO_CHANNEL_FR => (4, 0),
+        SPA_AUDIO_CHANNEL_SL => (0, 1),
+        SPA_AUDIO_CHANNEL_LFE => (3, 1),
+        SPA_AUDIO_CHANNEL_SR => (4, 1),
+        SPA_AUDIO_CHANNEL_RL => (0, 2),
+        SPA_AUDIO_CHANNEL_RLC => (1, 2),
+        SPA_AUDIO_CHANNEL_RC => (2, 2),
+        SPA_AUDIO_CHANNEL_RRC => (3, 2),
+        SPA_AUDIO_CHANNEL_RR => (4, 2),
+        _ => return None,
+    })
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::PwSpeakerTestWindow)]
+    pub struct PwSpeakerTestWindow {
+        #[property(get, set, construct_only)]
+        pub(super) node_object: OnceCell<PwNodeObject>,
+
+        pub(super) positions: RefCell<Vec<u32>>,
+        pub(super) buttons: RefCell<Vec<gtk::ToggleButton>>,
+        pub(super) generator: RefCell<Option<Rc<RefCell<SignalGenerator>>>>,
+        pub(super) signal: Cell<TestSignal>,
+        pub(super) playback: RefCell<Option<LevelMeter>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwSpeakerTestWindow {
+        const NAME: &'static str = "PwSpeakerTestWindow";
+        type Type = super::PwSpeakerTestWindow;
+        type ParentType = adw::Window;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PwSpeakerTestWindow {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.setup();
+        }
+
+        fn dispose(&self) {
+            self.playback.take();
+        }
+    }
+
+    impl WidgetImpl for PwSpeakerTestWindow {}
+
+    impl WindowImpl for PwSpeakerTestWindow {
+        fn close_request(&self) -> glib::Propagation {
+            self.playback.take();
+            self.parent_close_request()
+        }
+    }
+
+    impl AdwWindowImpl for PwSpeakerTestWindow {}
+
+    impl PwSpeakerTestWindow {
+        fn setup(&self) {
+            let obj = self.obj();
+            let node = obj.node_object();
+            obj.set_title(Some(&formatx::formatx!(gettext("Test Speakers – {}"), node.name()).unwrap_or_default()));
+            obj.set_default_size(450, -1);
+
+            let mut positions = node.channel_positions();
+            if positions.is_empty() {
+                positions = vec![SPA_AUDIO_CHANNEL_FL, SPA_AUDIO_CHANNEL_FR];
+            }
+
+            let grid = gtk::Grid::builder()
+                .row_spacing(12)
+                .column_spacing(12)
+                .row_homogeneous(true)
+                .column_homogeneous(true)
+                .halign(gtk::Align::Center)
+                .margin_top(24)
+                .margin_bottom(24)
+                .margin_start(24)
+                .margin_end(24)
+                .build();
+
+            let listener = gtk::Image::builder().icon_name("avatar-default-symbolic").pixel_size(48).tooltip_text(gettext("Listener")).build();
+            listener.add_css_class("dim-label");
+            grid.attach(&listener, 2, 1, 1, 1);
+
+            let audio_channels = wp::spa::SpaIdTable::from_name("Spa:Enum:AudioChannel");
+            let mut buttons = Vec::with_capacity(positions.len());
+            let mut extra = 0;
+            for (index, position) in positions.iter().enumerate() {
+                let name = audio_channels
+                    .as_ref()
+                    .and_then(|table| table.find_value(*position))
+                    .and_then(|value| value.short_name())
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| (index + 1).to_string());
+
+                let button = gtk::ToggleButton::builder()
+                    .label(&name)
+                    .tooltip_text(formatx::formatx!(gettext("Play on {}"), &name).unwrap_or_default())
+                    .width_request(64)
+                    .height_request(48)
+                    .build();
+                button.add_css_class("circular");
+                button.connect_toggled(clone!(#[weak(rename_to = widget)] self, move |button| {
+                    widget.channel_toggled(index, button.is_active());
+                }));
+
+                // Channels sharing a cell or without one go below the room.
+                let cell = channel_cell(*position).filter(|(column, row)| grid.child_at(*column, *row).is_none());
+                let (column, row) = cell.unwrap_or_else(|| {
+                    extra += 1;
+                    ((extra - 1) % COLUMNS, 3 + (extra - 1) / COLUMNS)
+                });
+                grid.attach(&button, column, row, 1, 1);
+                buttons.push(button);
+            }
+            self.buttons.replace(buttons);
+            self.positions.replace(positions);
+
+            let tone = gtk::ToggleButton::builder().label(gettext("Tone")).active(true).build();
+            let noise = gtk::ToggleButton::builder().label(gettext("Noise")).group(&tone).build();
+            noise.connect_toggled(clone!(#[weak(rename_to = widget)] self, move |noise| {
+                widget.set_signal(if noise.is_active() { TestSignal::Noise } else { TestSignal::Tone });
+            }));
+            let signal_box = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+            signal_box.add_css_class("linked");
+            signal_box.append(&tone);
+            signal_box.append(&noise);
+
+            let header_bar = adw::HeaderBar::new();
+            header_bar.pack_start(&signal_box);
+
+            let status = adw::StatusPage::builder()
+                .description(gettext("Click a speaker to play a test sound through it"))
+                .child(&grid)
+                .build();
+
+            let toolbar_view = adw::ToolbarView::new();
+            toolbar_view.add_top_bar(&header_bar);
+            toolbar_view.set_content(Some(&status));
+            obj.set_content(Some(&toolbar_view));
+        }
+
+        fn set_signal(&self, signal: TestSignal) {
+            self.signal.set(signal);
+            if let Some(generator) = self.generator.borrow().as_ref() {
+                generator.borrow_mut().set_signal(signal);
+            }
+        }
+
+        fn channel_toggled(&self, index: usize, active: bool) {
+            if !active {
+                // Only stop when the playing channel was turned off, not when switching to another.
+                if self.buttons.borrow().iter().all(|button| !button.is_active()) {
+                    self.stop();
+                }
+                return;
+            }
+
+            let others: Vec<gtk::ToggleButton> = self.buttons.borrow().iter().enumerate().filter(|(i, _)| *i != index).map(|(_, b)| b.clone()).collect();
+            for button in others {
+                button.set_active(false);
+            }
+
+            if self.playback.borrow().is_none() && !self.start() {
+                if let Some(button) = self.buttons.borrow().get(index) {
+                    button.set_active(false);
+                }
+                return;
+            }
+            if let Some(generator) = self.generator.borrow().as_ref() {
+                generator.borrow_mut().set_channel(Some(index));
+            }
+        }
+
+        fn start(&self) -> bool {
+            let node = self.obj().node_object();
+            let positions = self.positions.borrow().clone();
+            let rate = node.format().map(|x| x.rate).filter(|x| *x > 0).unwrap_or(FALLBACK_RATE);
+
+            let generator = Rc::new(RefCell::new(SignalGenerator::new(rate, positions.len())));
+            generator.borrow_mut().set_signal(self.signal.get());
+
+            let playback = PwvucontrolManager::default().add_playback(node.boundid(), &positions, rate, &gettext("Speaker test"), None, {
+                let generator = generator.clone();
+                move |samples| generator.borrow_mut().fill(samples)
+            });
+            match playback {
+                Ok(playback) => {
+                    self.generator.replace(Some(generator));
+                    self.playback.replace(Some(playback));
+                    true
+                }
+                Err(e) => {
+                    crate::pwvucontrol_warning!("Unable to play test sound on {}: {e}", node.name());
+                    false
+                }
+            }
+        }
+
+        fn stop(&self) {
+            self.playback.take();
+            self.generator.take();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Plays a test sound through one channel of a sink at a time, the channels laid out as in the room.
+    pub struct PwSpeakerTestWindow(ObjectSubclass<imp::PwSpeakerTestWindow>)
+        @extends gtk::Widget, gtk::Window, adw::Window,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl PwSpeakerTestWindow {
+    pub(crate) fn new(node_object: &PwNodeObject) -> Self {
+        glib::Object::builder().property("node-object", node_object).build()
+    }
+}
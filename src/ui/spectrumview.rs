@@ -0,0 +1,152 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::peakmeter::hex_to_rgb;
+use crate::backend::{bin_frequency, SPECTRUM_RATE};
+use gtk::{self, prelude::*, subclass::prelude::*};
+use std::cell::RefCell;
+
+const MIN_FREQUENCY: f32 = 20.0;
+const MAX_FREQUENCY: f32 = SPECTRUM_RATE as f32 / 2.0;
+/// Bottom of the level axis, in dBFS.
+const MIN_DB: f32 = -90.0;
+/// How far a bin may drop between two spectra, keeps the display from flickering.
+const FALL_DB: f32 = 3.0;
+const BAR_WIDTH: f32 = 3.0;
+const FREQUENCY_LINES: [(f32, &str); 6] = [(50.0, "50"), (100.0, "100"), (500.0, "500"), (1000.0, "1k"), (5000.0, "5k"), (10000.0, "10k")];
+const LEVEL_LINES: [f32; 2] = [-30.0, -60.0];
+
+mod imp {
+    use gtk::graphene;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct PwSpectrumView {
+        /// Displayed level of every bin in dBFS.
+        pub(super) bins: RefCell<Vec<f32>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PwSpectrumView {
+        const NAME: &'static str = "PwSpectrumView";
+        type Type = super::PwSpectrumView;
+        type ParentType = gtk::Widget;
+    }
+
+    impl ObjectImpl for PwSpectrumView {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.obj().add_css_class("spectrum");
+        }
+    }
+
+    impl WidgetImpl for PwSpectrumView {
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let w = self.obj().width() as f32;
+            let h = self.obj().height() as f32;
+            if w <= 0.0 || h <= 0.0 {
+                return;
+            }
+
+            let color = self.obj().color();
+            let mut grid = color;
+            grid.set_alpha(color.alpha() * 0.15);
+            let bar_color = hex_to_rgb(0x3584e4);
+
+            for db in LEVEL_LINES {
+                snapshot.append_color(&grid, &graphene::Rect::new(0.0, level_y(db, h).round(), w, 1.0));
+            }
+
+            let bins = self.bins.borrow();
+            if !bins.is_empty() {
+                let mut x = 0.0;
+                while x < w {
+                    // Bars span a range of bins, the loudest of them counts.
+                    let first = frequency_bin(x_frequency(x, w));
+                    let last = frequency_bin(x_frequency(x + BAR_WIDTH, w)).max(first + 1).min(bins.len());
+                    let level = bins.get(first..last).and_then(|x| x.iter().copied().reduce(f32::max)).unwrap_or(MIN_DB);
+                    let y = level_y(level, h);
+                    snapshot.append_color(&bar_color, &graphene::Rect::new(x, y, BAR_WIDTH - 1.0, h - y));
+                    x += BAR_WIDTH;
+                }
+            }
+
+            let mut label_color = color;
+            label_color.set_alpha(color.alpha() * 0.6);
+            for (frequency, text) in FREQUENCY_LINES {
+                let x = frequency_x(frequency, w).round();
+                snapshot.append_color(&grid, &graphene::Rect::new(x, 0.0, 1.0, h));
+
+                let layout = self.obj().create_pango_layout(None);
+                layout.set_markup(&format!("<small>{text}</small>"));
+                snapshot.save();
+                snapshot.translate(&graphene::Point::new(x + 2.0, 0.0));
+                snapshot.append_layout(&layout, &label_color);
+                snapshot.restore();
+            }
+        }
+
+        fn measure(&self, orientation: gtk::Orientation, _for_size: i32) -> (i32, i32, i32, i32) {
+            match orientation {
+                gtk::Orientation::Horizontal => (200, 400, -1, -1),
+                gtk::Orientation::Vertical => (100, 200, -1, -1),
+                _ => panic!("Invalid orientation passed to measure"),
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Draws a frequency spectrum as computed by [`crate::backend::SpectrumAnalyzer`] on a logarithmic frequency axis.
+    pub struct PwSpectrumView(ObjectSubclass<imp::PwSpectrumView>)
+    @extends gtk::Widget,
+    @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl PwSpectrumView {
+    pub fn new() -> Self {
+        glib::Object::builder().build()
+    }
+
+    /// Shows a new spectrum, levels drop back gradually.
+    pub fn set_spectrum(&self, spectrum: &[f32]) {
+        let mut bins = self.imp().bins.borrow_mut();
+        if bins.len() != spectrum.len() {
+            bins.clear();
+            bins.extend_from_slice(spectrum);
+        } else {
+            for (bin, level) in bins.iter_mut().zip(spectrum) {
+                *bin = level.max(*bin - FALL_DB);
+            }
+        }
+        drop(bins);
+        self.queue_draw();
+    }
+
+    pub fn clear(&self) {
+        self.imp().bins.borrow_mut().clear();
+        self.queue_draw();
+    }
+}
+
+impl Default for PwSpectrumView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn frequency_x(frequency: f32, width: f32) -> f32 {
+    (frequency / MIN_FREQUENCY).ln() / (MAX_FREQUENCY / MIN_FREQUENCY).ln() * width
+}
+
+fn x_frequency(x: f32, width: f32) -> f32 {
+    MIN_FREQUENCY * (MAX_FREQUENCY / MIN_FREQUENCY).powf(x / width)
+}
+
+fn frequency_bin(frequency: f32) -> usize {
+    (frequency / bin_frequency(1)).round() as usize
+}
+
+fn level_y(db: f32, height: f32) -> f32 {
+    (db / MIN_DB).clamp(0.0, 1.0) * height
+}
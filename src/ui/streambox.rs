@@ -53,10 +53,13 @@ mod imp {
 
             self.volumebox.set_node_object(&item);
 
-            let defaultnodesapi = manager.default_nodes_api();
             let widget = self.obj();
-            let defaultnodesapi_closure = closure_local!(#[watch] widget, move |_: wp::plugin::Plugin| widget.update_output_device_dropdown());
-            defaultnodesapi.connect_closure("changed", false, defaultnodesapi_closure);
+            let signal = match item.nodetype() {
+                crate::backend::NodeType::StreamInput => "notify::default-source",
+                _ => "notify::default-sink",
+            };
+            let default_node_closure = closure_local!(#[watch] widget, move |_: PwvucontrolManager, _: glib::ParamSpec| widget.update_output_device_dropdown());
+            manager.connect_closure(signal, false, default_node_closure);
             widget.update_output_device_dropdown();
 
             self.parent_constructed();
@@ -78,6 +81,16 @@ mod imp {
             // Create our custom output dropdown widget and add it to the layout
             self.output_dropdown.set_nodeobj(Some(&item));
 
+            // Streams can be dropped on a device row to move them there, see PwSinkBox.
+            let drag_source = gtk::DragSource::builder().actions(gtk::gdk::DragAction::MOVE).build();
+            drag_source.connect_prepare(clone!(#[weak] item, #[upgrade_or] None, move |_, _, _| {
+                Some(gtk::gdk::ContentProvider::for_value(&item.to_value()))
+            }));
+            drag_source.connect_drag_begin(clone!(#[weak(rename_to = widget)] self, move |source, _| {
+                source.set_icon(Some(&gtk::WidgetPaintable::new(Some(&*widget.obj()))), 0, 0);
+            }));
+            obj.add_controller(drag_source);
+
             glib::idle_add_local_once(clone!(#[weak(rename_to = widget)] self, move || {
                 widget.obj().update_output_device_dropdown();
             }));
@@ -1,12 +1,17 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use crate::{
-    backend::{NodeType, PwChannelObject, PwNodeObject, PwvucontrolManager},
-    ui::{LevelbarProvider, PwChannelBox, PwPeakMeter, PwVolumeScale},
+    backend::{LevelMeter, NodeType, PwChannelObject, PwNodeObject, PwvucontrolManager},
+    ui::{PwChannelBox, PwNodeInspector, PwPeakMeter, PwSpectrumView, PwVolumeScale},
 };
-use glib::{clone, ControlFlow, SignalHandlerId};
-use gtk::{prelude::*, subclass::prelude::*};
-use std::cell::{Cell, RefCell};
+use gettextrs::gettext;
+use glib::{clone, SignalHandlerId, SourceId};
+use gtk::{gio, prelude::*, subclass::prelude::*};
+use std::{
+    cell::{Cell, RefCell},
+    time::Duration,
+};
+use wireplumber as wp;
 
 mod imp {
     use glib::property::PropertySet;
@@ -21,9 +26,14 @@ mod imp {
         pub(super) node_object: RefCell<Option<PwNodeObject>>,
 
         metadata_changed_event: Cell<Option<SignalHandlerId>>,
-        levelbarprovider: Cell<Option<LevelbarProvider>>,
-        timeoutid: Cell<Option<gtk::TickCallbackId>>,
-        pub(super) level: Cell<f32>,
+        settings_changed_event: Cell<Option<SignalHandlerId>>,
+        /// Handlers on the node object, which outlives the row.
+        node_object_events: RefCell<Vec<SignalHandlerId>>,
+        level_meter: RefCell<Option<LevelMeter>>,
+        /// Only analysing while the spectrum popover is open.
+        spectrum_meter: RefCell<Option<LevelMeter>>,
+        /// Shows the progress of the node's recording, which the manager keeps.
+        record_status_timeout: Cell<Option<SourceId>>,
 
         // Template widgets
         #[template_child]
@@ -52,6 +62,16 @@ mod imp {
         pub monitorvolumescale: TemplateChild<PwVolumeScale>,
         #[template_child]
         pub container: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub spectrum_view: TemplateChild<PwSpectrumView>,
+        #[template_child]
+        pub record_button: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub record_status: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub state_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub suspend_button: TemplateChild<gtk::Button>,
     }
 
     #[glib::object_subclass]
@@ -63,7 +83,9 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             PwPeakMeter::ensure_type();
+            PwSpectrumView::ensure_type();
             klass.bind_template();
+            klass.bind_template_callbacks();
         }
 
         fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
@@ -81,7 +103,11 @@ mod imp {
             }));
 
             let window = crate::ui::PwvucontrolWindow::default();
-            window.imp().settings.bind("use-peakmeter-led", &self.peak_meter.get(), "use-led").get_only().build();
+            self.peak_meter.bind_settings(&window.imp().settings);
+            let handler = window.imp().settings.connect_changed(Some("peakmeter-per-channel"), clone!(#[weak(rename_to = widget)] self, move |_, _| {
+                widget.restart_levelbar();
+            }));
+            self.settings_changed_event.set(Some(handler));
         }
 
         fn dispose(&self) {
@@ -92,16 +118,27 @@ mod imp {
                 };
             };
 
+            if let Some(sid) = self.settings_changed_event.take() {
+                crate::ui::PwvucontrolWindow::default().imp().settings.disconnect(sid);
+            }
+            if let Some(node) = self.node_object.borrow().as_ref() {
+                for sid in self.node_object_events.take() {
+                    node.disconnect(sid);
+                }
+            }
+
+            // The recording itself goes on until stopped, this row may just have been filtered out.
+            if let Some(timeout) = self.record_status_timeout.take() {
+                timeout.remove();
+            }
+
             self.dispose_template();
         }
     }
 
     impl WidgetImpl for PwVolumeBox {
         fn unmap(&self) {
-            if let Some(t) = self.timeoutid.take() {
-                t.remove();
-            }
-            self.levelbarprovider.take();
+            self.teardownlevelbar();
             self.parent_unmap();
         }
 
@@ -120,6 +157,56 @@ mod imp {
     }
     impl ListBoxRowImpl for PwVolumeBox {}
 
+    #[gtk::template_callbacks]
+    impl PwVolumeBox {
+        #[template_callback]
+        fn inspect_button_clicked(&self) {
+            let Some(node) = self.node_object.borrow().clone() else {
+                return;
+            };
+            let inspector = PwNodeInspector::new(&node);
+            inspector.set_transient_for(self.obj().root().and_downcast_ref::<gtk::Window>());
+            inspector.present();
+        }
+
+        #[template_callback]
+        fn suspend_button_clicked(&self) {
+            if let Some(node) = self.node_object.borrow().as_ref() {
+                node.suspend();
+            }
+        }
+
+        #[template_callback]
+        fn spectrum_popover_shown(&self) {
+            let Some(node) = self.node_object.borrow().clone() else {
+                return;
+            };
+            let meter = PwvucontrolManager::default().add_spectrum_meter(node.boundid(), clone!(#[weak(rename_to = widget)] self, move |spectrum| {
+                widget.spectrum_view.set_spectrum(spectrum);
+            }));
+            self.spectrum_meter.replace(meter);
+        }
+
+        #[template_callback]
+        fn spectrum_popover_closed(&self) {
+            self.spectrum_meter.take();
+            self.spectrum_view.clear();
+        }
+
+        #[template_callback]
+        fn record_button_toggled(&self) {
+            let Some(node) = self.node_object.borrow().clone() else {
+                return;
+            };
+            let recording = PwvucontrolManager::default().recording_status(&node).is_some();
+            match (self.record_button.is_active(), recording) {
+                (true, false) => self.choose_recording_file(),
+                (false, true) => self.stop_recording(),
+                _ => {}
+            }
+        }
+    }
+
     impl BuildableImpl for PwVolumeBox {
         fn add_child(&self, builder: &gtk::Builder, child: &glib::Object, type_: Option<&str>) {
             if type_ == Some("extra") {
@@ -165,6 +252,18 @@ mod imp {
 
             item.bind_property("mainvolume", &self.mainvolumescale.get(), "volume").sync_create().bidirectional().build();
 
+            self.suspend_button.set_visible(matches!(item.nodetype(), NodeType::Sink | NodeType::Source));
+            let handler = item.connect_state_notify(clone!(#[weak(rename_to = widget)] self, move |item| {
+                widget.update_state(item);
+            }));
+            self.node_object_events.borrow_mut().push(handler);
+            self.update_state(item);
+
+            // Pick up a recording started before this row was created.
+            if PwvucontrolManager::default().recording_status(item).is_some() {
+                self.show_recording();
+            }
+
             self.channel_listbox.bind_model(
                 Some(&item.channelmodel()),
                 move |item| {
@@ -175,21 +274,175 @@ mod imp {
                     .upcast::<gtk::Widget>()
                 },
             );
+
+            // The channel positions are only known once the format arrives.
+            let handler = item.connect_local("format", false, clone!(#[weak(rename_to = widget)] self, #[upgrade_or] None, move |_| {
+                if widget.per_channel() {
+                    widget.restart_levelbar();
+                }
+
+                None
+            }));
+            self.node_object_events.borrow_mut().push(handler);
+        }
+
+        fn update_state(&self, item: &PwNodeObject) {
+            let state = item.state();
+            let (text, class) = match state {
+                wp::pw::NodeState::Running => (gettext("Running"), "success"),
+                wp::pw::NodeState::Idle => (gettext("Idle"), "dim-label"),
+                wp::pw::NodeState::Suspended => (gettext("Suspended"), "dim-label"),
+                wp::pw::NodeState::Error => (gettext("Error"), "error"),
+                _ => (String::new(), "dim-label"),
+            };
+            self.state_label.set_label(&text);
+            self.state_label.set_css_classes(&["caption", class]);
+            self.state_label.set_tooltip_text(item.state_error().as_deref());
+
+            // Suspending only makes sense while the device is open.
+            self.suspend_button.set_sensitive(matches!(state, wp::pw::NodeState::Running | wp::pw::NodeState::Idle));
+        }
+
+        fn choose_recording_file(&self) {
+            let Some(node) = self.node_object.borrow().clone() else {
+                return;
+            };
+
+            let filters = gio::ListStore::new::<gtk::FileFilter>();
+            for (name, pattern) in [(gettext("FLAC audio"), "*.flac"), (gettext("WAV audio"), "*.wav")] {
+                let filter = gtk::FileFilter::new();
+                filter.set_name(Some(&name));
+                filter.add_pattern(pattern);
+                filters.append(&filter);
+            }
+            let timestamp = glib::DateTime::now_local().and_then(|x| x.format("%Y%m%d-%H%M%S")).unwrap_or_default();
+            let name: String = node.name().chars().map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' }).collect();
+
+            let dialog = gtk::FileDialog::builder()
+                .title(gettext("Record To"))
+                .accept_label(gettext("_Record"))
+                .initial_name(format!("{name}-{timestamp}.flac"))
+                .filters(&filters)
+                .modal(true)
+                .build();
+            dialog.save(
+                self.obj().root().and_downcast_ref::<gtk::Window>(),
+                gio::Cancellable::NONE,
+                clone!(#[weak(rename_to = widget)] self, move |result| {
+                    match result.ok().and_then(|file| file.path()) {
+                        Some(path) => widget.start_recording(&node, &path),
+                        None => widget.record_button.set_active(false),
+                    }
+                }),
+            );
+        }
+
+        fn start_recording(&self, node: &PwNodeObject, path: &std::path::Path) {
+            if let Err(e) = PwvucontrolManager::default().start_recording(node, path) {
+                crate::pwvucontrol_warning!("Unable to record {}: {e}", node.name());
+                self.show_record_status(&formatx::formatx!(gettext("Not recording: {}"), e.to_string()).unwrap_or_default(), None);
+                self.record_button.set_active(false);
+                return;
+            }
+            self.show_recording();
+        }
+
+        fn show_recording(&self) {
+            self.record_button.set_active(true);
+            self.record_button.set_tooltip_text(Some(&gettext("Stop recording")));
+            self.update_record_status();
+
+            let timeout = glib::timeout_add_local(Duration::from_millis(500), clone!(#[weak(rename_to = widget)] self, #[upgrade_or] glib::ControlFlow::Break, move || {
+                widget.update_record_status();
+                glib::ControlFlow::Continue
+            }));
+            if let Some(timeout) = self.record_status_timeout.replace(Some(timeout)) {
+                timeout.remove();
+            }
+        }
+
+        fn stop_recording(&self) {
+            if let Some(timeout) = self.record_status_timeout.take() {
+                timeout.remove();
+            }
+            let recorder = self.node_object.borrow().as_ref().and_then(|node| PwvucontrolManager::default().stop_recording(node));
+            self.record_button.set_active(false);
+            self.record_button.set_tooltip_text(Some(&gettext("Record to a file")));
+
+            let Some(recorder) = recorder else {
+                return;
+            };
+            let path = recorder.path().to_owned();
+            let file_name = path.file_name().map(|x| x.to_string_lossy().to_string()).unwrap_or_default();
+            let status = match recorder.error() {
+                Some(e) => formatx::formatx!(gettext("Recording failed: {}"), e).unwrap_or_default(),
+                None => match recorder.stop() {
+                    Ok(()) => formatx::formatx!(gettext("Saved {}"), file_name).unwrap_or_default(),
+                    Err(e) => formatx::formatx!(gettext("Recording failed: {}"), e.to_string()).unwrap_or_default(),
+                },
+            };
+            self.show_record_status(&status, Some(&path.to_string_lossy()));
+        }
+
+        fn update_record_status(&self) {
+            let status = self.node_object.borrow().as_ref().and_then(|node| PwvucontrolManager::default().recording_status(node));
+            let Some(status) = status else {
+                return;
+            };
+            if status.error.is_some() {
+                self.stop_recording();
+                return;
+            }
+
+            let seconds = status.elapsed.as_secs();
+            let text = format!("{}:{:02} · {}", seconds / 60, seconds % 60, glib::format_size(status.bytes));
+            self.show_record_status(&text, Some(&status.path.to_string_lossy()));
+        }
+
+        fn show_record_status(&self, text: &str, tooltip: Option<&str>) {
+            self.record_status.set_label(text);
+            self.record_status.set_tooltip_text(tooltip);
+            self.record_status.set_visible(true);
+        }
+
+        fn per_channel(&self) -> bool {
+            crate::ui::PwvucontrolWindow::default().imp().settings.boolean("peakmeter-per-channel")
         }
 
         fn setuplevelbar(&self) {
             let item = self.node_object.borrow();
             let item = item.as_ref().unwrap();
 
-            if let Ok(provider) = LevelbarProvider::new(&self.obj(), item.boundid()) {
-                self.levelbarprovider.set(Some(provider));
+            let positions = if self.per_channel() { item.channel_positions() } else { Vec::new() };
 
-                let callbackid = self.obj().add_tick_callback(|widget, _fc| {
-                    widget.imp().peak_meter.set_level(widget.imp().level.get());
-                    ControlFlow::Continue
-                });
-                self.timeoutid.set(Some(callbackid));
+            let obj = self.obj();
+            let meter = PwvucontrolManager::default().add_level_meter(item.boundid(), &positions, clone!(#[weak] obj, move |levels| {
+                obj.set_levels(levels);
+            }));
+            self.level_meter.replace(meter);
+        }
+
+        fn teardownlevelbar(&self) {
+            self.level_meter.take();
+        }
+
+        fn restart_levelbar(&self) {
+            if self.obj().is_mapped() && self.peak_meter.is_visible() {
+                self.teardownlevelbar();
+                self.setuplevelbar();
+            }
+        }
+
+        pub(super) fn channel_rows(&self) -> Vec<PwChannelBox> {
+            let mut rows = Vec::new();
+            let mut child = self.channel_listbox.first_child();
+            while let Some(row) = child {
+                child = row.next_sibling();
+                if let Ok(row) = row.downcast::<PwChannelBox>() {
+                    rows.push(row);
+                }
             }
+            rows
         }
     }
 }
@@ -202,7 +455,14 @@ glib::wrapper! {
 }
 
 impl PwVolumeBox {
-    pub(crate) fn set_level(&self, level: f32) {
-        self.imp().level.set(level);
+    /// Shows the latest peak of every metered channel, a single one unless metering per channel.
+    pub(crate) fn set_levels(&self, levels: &[f32]) {
+        let imp = self.imp();
+        imp.peak_meter.set_level(levels.iter().copied().fold(0.0, f32::max));
+        if levels.len() > 1 && imp.revealer.reveals_child() {
+            for (row, level) in imp.channel_rows().iter().zip(levels.iter()) {
+                row.set_level(*level);
+            }
+        }
     }
 }
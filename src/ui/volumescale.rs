@@ -5,6 +5,9 @@ use gettextrs::gettext;
 use gtk::{prelude::*, subclass::prelude::*};
 use std::cell::Cell;
 
+/// Slider position at the end of the scale when over-amplification is enabled.
+pub const MAX_OVERAMPLIFIED_VOLUME: f64 = 1.525;
+
 mod imp {
     use super::*;
 
@@ -131,9 +134,9 @@ mod imp {
 
             if overamplification {
                 if self.show_ticks.get() {
-                    volume_scale.add_mark(1.525, gtk::PositionType::Bottom, Some(&gettext("150%")));
+                    volume_scale.add_mark(MAX_OVERAMPLIFIED_VOLUME, gtk::PositionType::Bottom, Some(&gettext("150%")));
                 }
-                volume_scale.set_range(0.0, 1.525);
+                volume_scale.set_range(0.0, MAX_OVERAMPLIFIED_VOLUME);
             } else {
                 volume_scale.set_range(0.0, 1.0);
             }
@@ -4,15 +4,15 @@ use std::time::Duration;
 
 use crate::{
     application::PwvucontrolApplication,
-    backend::{PwDeviceObject, PwNodeObject, PwvucontrolManager},
+    backend::{FeedbackSound, LevelMeter, PwDeviceObject, PwNodeObject, PwvucontrolManager, Scene, VirtualDevice, VirtualDeviceKind},
     config::{APP_ID, PROFILE},
-    ui::{devicebox::PwDeviceBox, PwSinkBox, PwStreamBox},
+    ui::{devicebox::PwDeviceBox, PwClockSettingsWindow, PwGraphView, PwHideRulesWindow, PwPeakMeterSettingsWindow, PwPerformanceView, PwSinkBox, PwStreamBox},
 };
-use adw::subclass::prelude::*;
+use adw::{prelude::*, subclass::prelude::*};
 use gettextrs::gettext;
 use glib::clone;
 use gtk::{gio, prelude::*};
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::time;
 
 pub enum PwvucontrolWindowView {
@@ -45,6 +45,10 @@ mod imp {
         pub reconnectbtn: TemplateChild<gtk::Button>,
         #[template_child]
         pub info_banner: TemplateChild<adw::Banner>,
+        #[template_child]
+        pub search_bar: TemplateChild<gtk::SearchBar>,
+        #[template_child]
+        pub search_entry: TemplateChild<gtk::SearchEntry>,
 
         #[template_child]
         pub playbackviewstack: TemplateChild<gtk::Stack>,
@@ -54,10 +58,16 @@ mod imp {
         pub inputviewstack: TemplateChild<gtk::Stack>,
         #[template_child]
         pub outputviewstack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub scenes_menu: TemplateChild<gio::Menu>,
+        #[template_child]
+        pub virtual_devices_menu: TemplateChild<gio::Menu>,
 
         pub settings: gio::Settings,
 
         pub beep_elapsed: Cell<time::Instant>,
+        /// The volume feedback sound playing and the timeout stopping it.
+        pub feedback_playback: RefCell<Option<(LevelMeter, glib::SourceId)>>,
     }
 
     impl Default for PwvucontrolWindow {
@@ -74,11 +84,16 @@ mod imp {
                 reconnectbtn: TemplateChild::default(),
                 settings: gio::Settings::new(APP_ID),
                 info_banner: TemplateChild::default(),
+                search_bar: TemplateChild::default(),
+                search_entry: TemplateChild::default(),
                 playbackviewstack: TemplateChild::default(),
                 recordviewstack: TemplateChild::default(),
                 inputviewstack: TemplateChild::default(),
                 outputviewstack: TemplateChild::default(),
+                scenes_menu: TemplateChild::default(),
+                virtual_devices_menu: TemplateChild::default(),
                 beep_elapsed: Cell::new(std::time::Instant::now()),
+                feedback_playback: RefCell::default(),
             }
         }
     }
@@ -90,6 +105,8 @@ mod imp {
         type ParentType = adw::ApplicationWindow;
 
         fn class_init(klass: &mut Self::Class) {
+            PwGraphView::ensure_type();
+            PwPerformanceView::ensure_type();
             klass.bind_template();
         }
 
@@ -132,7 +149,106 @@ mod imp {
                     window.select_tab(parameter);
                 })
                 .build();
-            self.obj().add_action_entries([action_switchtab]);
+            let action_save_scene = gio::ActionEntry::builder("save-scene")
+                .activate(move |window: &super::PwvucontrolWindow, _action, _parameter| {
+                    window.show_save_scene_dialog();
+                })
+                .build();
+
+            let action_load_scene = gio::ActionEntry::builder("load-scene")
+                .parameter_type(Some(&String::static_variant_type()))
+                .activate(move |_window: &super::PwvucontrolWindow, _action, parameter| {
+                    let name = parameter
+                        .expect("Could not get parameter.")
+                        .get::<String>()
+                        .expect("The variant needs to be of type `String`.");
+
+                    match Scene::load(&name) {
+                        Ok(scene) => scene.apply(&PwvucontrolManager::default()),
+                        Err(e) => crate::pwvucontrol_warning!("Unable to load scene {name}: {e}"),
+                    }
+                })
+                .build();
+
+            let action_delete_scene = gio::ActionEntry::builder("delete-scene")
+                .parameter_type(Some(&String::static_variant_type()))
+                .activate(move |window: &super::PwvucontrolWindow, _action, parameter| {
+                    let name = parameter
+                        .expect("Could not get parameter.")
+                        .get::<String>()
+                        .expect("The variant needs to be of type `String`.");
+
+                    if let Err(e) = Scene::delete(&name) {
+                        crate::pwvucontrol_warning!("Unable to delete scene {name}: {e}");
+                    }
+                    window.update_scenes_menu();
+                })
+                .build();
+
+            let action_add_virtual_device = gio::ActionEntry::builder("add-virtual-device")
+                .activate(move |window: &super::PwvucontrolWindow, _action, _parameter| {
+                    window.show_add_virtual_device_dialog();
+                })
+                .build();
+
+            let action_remove_virtual_device = gio::ActionEntry::builder("remove-virtual-device")
+                .parameter_type(Some(&String::static_variant_type()))
+                .activate(move |window: &super::PwvucontrolWindow, _action, parameter| {
+                    let name = parameter
+                        .expect("Could not get parameter.")
+                        .get::<String>()
+                        .expect("The variant needs to be of type `String`.");
+
+                    PwvucontrolManager::default().remove_virtual_device(&name);
+                    window.update_virtual_devices_menu();
+                })
+                .build();
+
+            let action_clock_settings = gio::ActionEntry::builder("clock-settings")
+                .activate(move |window: &super::PwvucontrolWindow, _action, _parameter| {
+                    let clock_settings = PwClockSettingsWindow::new();
+                    clock_settings.set_transient_for(Some(window));
+                    clock_settings.present();
+                })
+                .build();
+
+            let action_hide_rules = gio::ActionEntry::builder("hide-rules")
+                .activate(move |window: &super::PwvucontrolWindow, _action, _parameter| {
+                    let hide_rules = PwHideRulesWindow::new();
+                    hide_rules.set_transient_for(Some(window));
+                    hide_rules.present();
+                })
+                .build();
+
+            let action_peakmeter_settings = gio::ActionEntry::builder("peakmeter-settings")
+                .activate(move |window: &super::PwvucontrolWindow, _action, _parameter| {
+                    let peakmeter_settings = PwPeakMeterSettingsWindow::new();
+                    peakmeter_settings.set_transient_for(Some(window));
+                    peakmeter_settings.present();
+                })
+                .build();
+
+            let action_search = gio::ActionEntry::builder("search")
+                .activate(move |window: &super::PwvucontrolWindow, _action, _parameter| {
+                    let search_bar = &window.imp().search_bar;
+                    search_bar.set_search_mode(!search_bar.is_search_mode());
+                })
+                .build();
+
+            self.obj().add_action_entries([
+                action_switchtab,
+                action_search,
+                action_save_scene,
+                action_load_scene,
+                action_delete_scene,
+                action_add_virtual_device,
+                action_remove_virtual_device,
+                action_clock_settings,
+                action_hide_rules,
+                action_peakmeter_settings,
+            ]);
+            self.obj().update_scenes_menu();
+            self.obj().update_virtual_devices_menu();
 
             crate::ui::remember_window_size(self.obj().upcast_ref(), &self.settings);
 
@@ -253,9 +369,29 @@ mod imp {
             self.obj().add_action(&overamplification_action);
             let use_led_peakmeter_action = self.settings.create_action("use-peakmeter-led");
             self.obj().add_action(&use_led_peakmeter_action);
+            let peakmeter_per_channel_action = self.settings.create_action("peakmeter-per-channel");
+            self.obj().add_action(&peakmeter_per_channel_action);
             let beep_on_volume_changes_action = self.settings.create_action("beep-on-volume-changes");
             self.obj().add_action(&beep_on_volume_changes_action);
+            let volume_feedback_sound_action = self.settings.create_action("volume-feedback-sound");
+            self.obj().add_action(&volume_feedback_sound_action);
+
+            self.search_entry.connect_search_changed(|entry| {
+                let manager = PwvucontrolManager::default();
+                for model in [manager.stream_output_model(), manager.stream_input_model(), manager.source_model(), manager.sink_model()] {
+                    model.set_search(entry.text());
+                }
+            });
+
+            // Not saved, hidden streams should stay hidden on the next start.
+            let show_hidden_action = gio::PropertyAction::new("show-hidden", &manager, "show-hidden");
+            self.obj().add_action(&show_hidden_action);
 
+            // All level meters are updated in one go each frame.
+            self.obj().add_tick_callback(|_, _| {
+                PwvucontrolManager::default().dispatch_levels();
+                glib::ControlFlow::Continue
+            });
         }
     }
     impl WidgetImpl for PwvucontrolWindow {}
@@ -334,13 +470,164 @@ impl PwvucontrolWindow {
         imp.info_banner.set_revealed(manager.node_model().n_items() == 0);
     }
 
-    pub(crate) fn play_beep(&self) {
-        if !self.imp().settings.boolean("beep-on-volume-changes") {
+    fn update_scenes_menu(&self) {
+        let scenes_menu = self.imp().scenes_menu.get();
+        scenes_menu.remove_all();
+
+        let save_section = gio::Menu::new();
+        save_section.append(Some(&gettext("_Save Current Setup…")), Some("win.save-scene"));
+        scenes_menu.append_section(None, &save_section);
+
+        let scenes = Scene::list();
+        if scenes.is_empty() {
             return;
         }
-        if self.imp().beep_elapsed.get().elapsed() > Duration::from_secs(1) {
-            WidgetExt::display(self).beep();
-            self.imp().beep_elapsed.set(time::Instant::now());
+
+        let load_section = gio::Menu::new();
+        let delete_menu = gio::Menu::new();
+        for name in scenes.iter() {
+            load_section.append(Some(name), Some(gio::Action::print_detailed_name("win.load-scene", Some(&name.to_variant())).as_str()));
+            delete_menu.append(Some(name), Some(gio::Action::print_detailed_name("win.delete-scene", Some(&name.to_variant())).as_str()));
+        }
+        load_section.append_submenu(Some(&gettext("_Delete Scene")), &delete_menu);
+        scenes_menu.append_section(None, &load_section);
+    }
+
+    fn show_save_scene_dialog(&self) {
+        let entry = gtk::Entry::builder().placeholder_text(gettext("Scene name")).activates_default(true).build();
+
+        let dialog = adw::MessageDialog::builder()
+            .transient_for(self)
+            .modal(true)
+            .heading(gettext("Save Scene"))
+            .body(gettext("Saves the volumes, stream outputs, card profiles and ports of all devices. Saving over an existing scene replaces it."))
+            .extra_child(&entry)
+            .default_response("save")
+            .close_response("cancel")
+            .build();
+        dialog.add_responses(&[("cancel", &gettext("_Cancel")), ("save", &gettext("_Save"))]);
+        dialog.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+        dialog.set_response_enabled("save", false);
+
+        entry.connect_changed(clone!(#[weak] dialog, move |entry| {
+            dialog.set_response_enabled("save", Scene::is_valid_name(&entry.text()));
+        }));
+
+        dialog.connect_response(Some("save"), clone!(#[weak(rename_to = window)] self, #[weak] entry, move |_, _| {
+            let name = entry.text();
+            let scene = Scene::capture(&PwvucontrolManager::default());
+            if let Err(e) = scene.save(name.trim()) {
+                crate::pwvucontrol_warning!("Unable to save scene {name}: {e}");
+            }
+            window.update_scenes_menu();
+        }));
+
+        dialog.present();
+    }
+
+    fn update_virtual_devices_menu(&self) {
+        let menu = self.imp().virtual_devices_menu.get();
+        menu.remove_all();
+
+        let add_section = gio::Menu::new();
+        add_section.append(Some(&gettext("_Add Virtual Device…")), Some("win.add-virtual-device"));
+        menu.append_section(None, &add_section);
+
+        let devices = PwvucontrolManager::default().virtual_devices();
+        if devices.is_empty() {
+            return;
+        }
+
+        let remove_section = gio::Menu::new();
+        for device in devices.iter() {
+            let label = formatx::formatx!(gettext("Remove {}"), &device.description).unwrap_or_default();
+            remove_section.append(Some(&label), Some(gio::Action::print_detailed_name("win.remove-virtual-device", Some(&device.name.to_variant())).as_str()));
+        }
+        menu.append_section(None, &remove_section);
+    }
+
+    fn show_add_virtual_device_dialog(&self) {
+        let kinds = VirtualDeviceKind::ALL;
+        let kind_labels: Vec<String> = kinds
+            .iter()
+            .map(|kind| match kind {
+                VirtualDeviceKind::NullSink => gettext("Null output (sink)"),
+                VirtualDeviceKind::VirtualSource => gettext("Virtual input (source)"),
+                VirtualDeviceKind::Loopback => gettext("Loopback"),
+            })
+            .collect();
+        let kind_dropdown = gtk::DropDown::from_strings(&kind_labels.iter().map(String::as_str).collect::<Vec<_>>());
+        let entry = gtk::Entry::builder().placeholder_text(gettext("Name")).activates_default(true).build();
+
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 12);
+        content.append(&kind_dropdown);
+        content.append(&entry);
+
+        let dialog = adw::MessageDialog::builder()
+            .transient_for(self)
+            .modal(true)
+            .heading(gettext("Add Virtual Device"))
+            .body(gettext("Null outputs and virtual inputs are kept until PipeWire restarts and are recreated when pwvucontrol starts. Loopbacks only exist while pwvucontrol is running."))
+            .extra_child(&content)
+            .default_response("add")
+            .close_response("cancel")
+            .build();
+        dialog.add_responses(&[("cancel", &gettext("_Cancel")), ("add", &gettext("_Add"))]);
+        dialog.set_response_appearance("add", adw::ResponseAppearance::Suggested);
+        dialog.set_response_enabled("add", false);
+
+        entry.connect_changed(clone!(#[weak] dialog, move |entry| {
+            dialog.set_response_enabled("add", !entry.text().trim().is_empty());
+        }));
+
+        dialog.connect_response(Some("add"), clone!(#[weak(rename_to = window)] self, #[weak] entry, #[weak] kind_dropdown, move |_, _| {
+            let manager = PwvucontrolManager::default();
+            let kind = kinds[kind_dropdown.selected() as usize];
+            manager.add_virtual_device(VirtualDevice::new(kind, &entry.text(), &manager.virtual_devices()));
+            window.update_virtual_devices_menu();
+        }));
+
+        dialog.present();
+    }
+
+    /// Plays the chosen feedback sound through `sink`, the sink's own volume makes it as loud as anything else played there.
+    pub(crate) fn play_volume_feedback(&self, sink: &PwNodeObject) {
+        let imp = self.imp();
+        if !imp.settings.boolean("beep-on-volume-changes") || imp.beep_elapsed.get().elapsed() <= Duration::from_secs(1) {
+            return;
+        }
+        imp.beep_elapsed.set(time::Instant::now());
+
+        let sound = FeedbackSound::from_nick(&imp.settings.string("volume-feedback-sound")).unwrap_or_default();
+        let mut positions = sink.channel_positions();
+        if positions.is_empty() {
+            positions = vec![pipewire::spa::sys::SPA_AUDIO_CHANNEL_FL, pipewire::spa::sys::SPA_AUDIO_CHANNEL_FR];
+        }
+        let channels = positions.len();
+        let rate = sink.format().map(|x| x.rate).filter(|x| *x > 0).unwrap_or(48000);
+        let samples = sound.render(rate);
+        let duration = Duration::from_secs_f64(samples.len() as f64 / rate as f64);
+
+        let mut samples = samples.into_iter();
+        let playback = PwvucontrolManager::default().add_playback(sink.boundid(), &positions, rate, &gettext("Volume change"), Some("audio-volume-change"), move |buffer| {
+            for frame in buffer.chunks_exact_mut(channels) {
+                frame.fill(samples.next().unwrap_or_default());
+            }
+        });
+        let playback = match playback {
+            Ok(playback) => playback,
+            Err(e) => {
+                crate::pwvucontrol_warning!("Unable to play volume feedback on {}: {e}", sink.name());
+                return;
+            }
+        };
+
+        // Leave the stream some time to drain before disconnecting.
+        let timeout = glib::timeout_add_local_once(duration + Duration::from_millis(500), clone!(#[weak(rename_to = window)] self, move || {
+            window.imp().feedback_playback.take();
+        }));
+        if let Some((_, timeout)) = imp.feedback_playback.replace(Some((playback, timeout))) {
+            timeout.remove();
         }
     }
 
@@ -351,6 +638,8 @@ impl PwvucontrolWindow {
             3 => self.imp().stack.set_visible_child_name("inputdevices"),
             4 => self.imp().stack.set_visible_child_name("outputdevices"),
             5 => self.imp().stack.set_visible_child_name("cards"),
+            6 => self.imp().stack.set_visible_child_name("graph"),
+            7 => self.imp().stack.set_visible_child_name("performance"),
             _ => {}
         }
     }